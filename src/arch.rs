@@ -1,6 +1,15 @@
 use std::collections::HashMap;
+use std::fmt;
 use lazy_static::lazy_static;
 
+// Width of the integer registers, shared by the encoder and the harts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Xlen
+{
+    X32,
+    X64
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Format
 {
@@ -58,6 +67,42 @@ pub enum Opcode
     Op64        = 0b1111011
 }
 
+impl TryFrom<u8> for Opcode
+{
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error>
+    { // Map the lower 7 bits of an instruction onto it's major opcode.
+        match value
+        {
+            0b0000011 => Ok(Opcode::Load),
+            0b0000111 => Ok(Opcode::LoadFp),
+            0b0001111 => Ok(Opcode::MiscMem),
+            0b0010011 => Ok(Opcode::OpImm),
+            0b0010111 => Ok(Opcode::AuiPC),
+            0b0011011 => Ok(Opcode::OpImm32),
+            0b0100011 => Ok(Opcode::Store),
+            0b0100111 => Ok(Opcode::StoreFp),
+            0b0101111 => Ok(Opcode::Amo),
+            0b0110011 => Ok(Opcode::Op),
+            0b0111011 => Ok(Opcode::Op32),
+            0b0110111 => Ok(Opcode::Lui),
+            0b1000011 => Ok(Opcode::MAdd),
+            0b1000111 => Ok(Opcode::MSub),
+            0b1001011 => Ok(Opcode::NmSub),
+            0b1001111 => Ok(Opcode::NmAdd),
+            0b1010011 => Ok(Opcode::OpFp),
+            0b1011011 => Ok(Opcode::OpImm64),
+            0b1100011 => Ok(Opcode::Branch),
+            0b1100111 => Ok(Opcode::Jalr),
+            0b1101111 => Ok(Opcode::Jal),
+            0b1110011 => Ok(Opcode::System),
+            0b1111011 => Ok(Opcode::Op64),
+            _ => Err(value)
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ShiftType
 {
@@ -83,6 +128,7 @@ pub enum ISA
     RV128E,   // Base Integer Instruction Set (128-bit, reduced registers)
     ZiFencei, // Memory Fence Instruction
     Zicsr,    // Control and Status Register (CSR) Instructions
+    Priv,     // Privileged Architecture Instructions (trap return and interrupt wait)
     RV32M,    // M-extension (Integer Multiplication and Division) for 32-bit
     RV64M,    // M-extension for 64-bit
     RV128M,   // M-extension for 128-bit
@@ -100,6 +146,106 @@ pub enum ISA
     RV128Q    // Q-extension for 128-bit
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegisterFile
+{
+    Int,    // Integer registers (x0-x31).
+    Float   // Floating point registers (f0-f31).
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OperandType
+{
+    Rd(RegisterFile),                               // Destination register.
+    Rs1(RegisterFile),                              // First source register.
+    Rs2(RegisterFile),                              // Second source register.
+    Rs3(RegisterFile),                              // Third source register (fused multiply-add).
+    Immediate(u8 /* Width */, bool /* Signed */),   // Immediate value.
+    Shamt(u8 /* Width */),                          // Shift amount.
+    Offset(u8 /* Width */),                         // Signed PC relative offset, multiple of 2.
+    Address,                                        // Memory address (signed 12-bit offset from rs1).
+    Csr,                                            // 12-bit control and status register address.
+    CsrImmediate,                                   // 5-bit unsigned immediate held in the rs1 field.
+    Fence,                                          // 4-bit predecessor/successor set (iorw).
+    RoundingMode                                    // Optional floating point rounding mode (defaults to dyn).
+}
+
+impl OperandType
+{
+    // Inclusive range of values accepted by immediate operand types.
+    pub fn range(&self) -> Option<(i64, i64)>
+    {
+        match self
+        {
+            OperandType::Immediate(width, true) | OperandType::Offset(width) =>
+                Some((-(1 << (width - 1)), (1 << (width - 1)) - 1)),
+            OperandType::Immediate(width, false) | OperandType::Shamt(width) =>
+                Some((0, (1 << width) - 1)),
+            OperandType::Csr          => Some((0, 0xFFF)),
+            OperandType::CsrImmediate => Some((0, 0b11111)),
+            OperandType::Fence        => Some((0, 0b1111)),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for OperandType
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let prefix = |file: &RegisterFile| if *file == RegisterFile::Float { "f" } else { "" };
+
+        match self
+        {
+            OperandType::Rd(file)               => write!(f, "{}rd", prefix(file)),
+            OperandType::Rs1(file)              => write!(f, "{}rs1", prefix(file)),
+            OperandType::Rs2(file)              => write!(f, "{}rs2", prefix(file)),
+            OperandType::Rs3(file)              => write!(f, "{}rs3", prefix(file)),
+            OperandType::Immediate(width, true)  => write!(f, "imm{}", width),
+            OperandType::Immediate(width, false) => write!(f, "uimm{}", width),
+            OperandType::Shamt(width)           => write!(f, "shamt{}", width),
+            OperandType::Offset(width)          => write!(f, "offset{}", width),
+            OperandType::Address                => write!(f, "offset(rs1)"),
+            OperandType::Csr                    => write!(f, "csr"),
+            OperandType::CsrImmediate           => write!(f, "uimm5"),
+            OperandType::Fence                  => write!(f, "iorw"),
+            OperandType::RoundingMode           => write!(f, "[rm]")
+        }
+    }
+}
+
+// Floating point rounding mode names, indexed by the value of the `rm` field.
+pub const ROUNDING_MODES: [&str; 8] = ["rne", "rtz", "rdn", "rup", "rmm", "", "", "dyn"];
+
+// Operand signatures shared between RV_ISA entries.
+const NONE: &[OperandType]              = &[];
+const RD_UIMM20: &[OperandType]         = &[OperandType::Rd(RegisterFile::Int), OperandType::Immediate(20, false)];
+const RD_OFFSET21: &[OperandType]       = &[OperandType::Rd(RegisterFile::Int), OperandType::Offset(21)];
+const RD_ADDRESS: &[OperandType]        = &[OperandType::Rd(RegisterFile::Int), OperandType::Address];
+const FRD_ADDRESS: &[OperandType]       = &[OperandType::Rd(RegisterFile::Float), OperandType::Address];
+const RS2_ADDRESS: &[OperandType]       = &[OperandType::Rs2(RegisterFile::Int), OperandType::Address];
+const FRS2_ADDRESS: &[OperandType]      = &[OperandType::Rs2(RegisterFile::Float), OperandType::Address];
+const RS1_RS2_OFFSET13: &[OperandType]  = &[OperandType::Rs1(RegisterFile::Int), OperandType::Rs2(RegisterFile::Int), OperandType::Offset(13)];
+const RD_RS1_IMM12: &[OperandType]      = &[OperandType::Rd(RegisterFile::Int), OperandType::Rs1(RegisterFile::Int), OperandType::Immediate(12, true)];
+const RD_RS1_SHAMT5: &[OperandType]     = &[OperandType::Rd(RegisterFile::Int), OperandType::Rs1(RegisterFile::Int), OperandType::Shamt(5)];
+const RD_RS1_SHAMT6: &[OperandType]     = &[OperandType::Rd(RegisterFile::Int), OperandType::Rs1(RegisterFile::Int), OperandType::Shamt(6)];
+const RD_RS1_SHAMT7: &[OperandType]     = &[OperandType::Rd(RegisterFile::Int), OperandType::Rs1(RegisterFile::Int), OperandType::Shamt(7)];
+const RD_RS1_RS2: &[OperandType]        = &[OperandType::Rd(RegisterFile::Int), OperandType::Rs1(RegisterFile::Int), OperandType::Rs2(RegisterFile::Int)];
+const RD_RS1: &[OperandType]            = &[OperandType::Rd(RegisterFile::Int), OperandType::Rs1(RegisterFile::Int)];
+const RS1_RS2: &[OperandType]           = &[OperandType::Rs1(RegisterFile::Int), OperandType::Rs2(RegisterFile::Int)];
+const FENCE: &[OperandType]             = &[OperandType::Fence, OperandType::Fence];
+const RD_CSR_RS1: &[OperandType]        = &[OperandType::Rd(RegisterFile::Int), OperandType::Csr, OperandType::Rs1(RegisterFile::Int)];
+const RD_CSR_UIMM5: &[OperandType]      = &[OperandType::Rd(RegisterFile::Int), OperandType::Csr, OperandType::CsrImmediate];
+const FRD_FRS1_FRS2_RM: &[OperandType]  = &[OperandType::Rd(RegisterFile::Float), OperandType::Rs1(RegisterFile::Float), OperandType::Rs2(RegisterFile::Float), OperandType::RoundingMode];
+const FRD_FRS1_FRS2: &[OperandType]     = &[OperandType::Rd(RegisterFile::Float), OperandType::Rs1(RegisterFile::Float), OperandType::Rs2(RegisterFile::Float)];
+const FRD_FRS1_RM: &[OperandType]       = &[OperandType::Rd(RegisterFile::Float), OperandType::Rs1(RegisterFile::Float), OperandType::RoundingMode];
+const RD_FRS1_FRS2: &[OperandType]      = &[OperandType::Rd(RegisterFile::Int), OperandType::Rs1(RegisterFile::Float), OperandType::Rs2(RegisterFile::Float)];
+const RD_FRS1_RM: &[OperandType]        = &[OperandType::Rd(RegisterFile::Int), OperandType::Rs1(RegisterFile::Float), OperandType::RoundingMode];
+const FRD_RS1_RM: &[OperandType]        = &[OperandType::Rd(RegisterFile::Float), OperandType::Rs1(RegisterFile::Int), OperandType::RoundingMode];
+const RD_FRS1: &[OperandType]           = &[OperandType::Rd(RegisterFile::Int), OperandType::Rs1(RegisterFile::Float)];
+const FRD_RS1: &[OperandType]           = &[OperandType::Rd(RegisterFile::Float), OperandType::Rs1(RegisterFile::Int)];
+const FRD_FRS1_FRS2_FRS3_RM: &[OperandType] = &[OperandType::Rd(RegisterFile::Float), OperandType::Rs1(RegisterFile::Float), OperandType::Rs2(RegisterFile::Float), OperandType::Rs3(RegisterFile::Float), OperandType::RoundingMode];
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Instruction
 {
@@ -112,7 +258,8 @@ pub struct Instruction
     pub funct12: Option<u16>,
     pub float_format: Option<FloatFormat>,
     pub shift: Option<ShiftType>,
-    pub rs2: Option<u8>
+    pub rs2: Option<u8>,
+    pub operands: &'static [OperandType]
 }
 
 impl Instruction 
@@ -130,10 +277,17 @@ impl Instruction
             funct12: None,
             float_format: None,
             shift: None,
-            rs2: None
+            rs2: None,
+            operands: NONE
         }
     }
 
+    // Assembly syntax of the operands (e.g. "rd, rs1, imm12").
+    pub fn syntax(&self) -> String
+    {
+        self.operands.iter().map(|operand| operand.to_string()).collect::<Vec<String>>().join(", ")
+    }
+
     fn with_funct3(mut self, function: u8) -> Self
     {
         self.funct3 = Some(function);
@@ -175,6 +329,12 @@ impl Instruction
         self.rs2 = Some(rs2);
         self
     }
+
+    fn with_operands(mut self, operands: &'static [OperandType]) -> Self
+    {
+        self.operands = operands;
+        self
+    }
 }
 
 lazy_static!
@@ -182,273 +342,278 @@ lazy_static!
     pub static ref RV_ISA: HashMap<&'static str, Instruction> =
     {
         let mut map = HashMap::new();
-        map.insert("lui",       Instruction::new(Opcode::Lui,     Format::UType, ISA::RV32I));
-        map.insert("auipc",     Instruction::new(Opcode::AuiPC,   Format::UType, ISA::RV32I));
-        map.insert("jal",       Instruction::new(Opcode::Jal,     Format::UJType, ISA::RV32I));
-        map.insert("jalr",      Instruction::new(Opcode::Jalr,    Format::IType, ISA::RV32I).with_funct3(0b000));
-        map.insert("beq",       Instruction::new(Opcode::Branch,  Format::SBType, ISA::RV32I).with_funct3(0b000));
-        map.insert("bne",       Instruction::new(Opcode::Branch,  Format::SBType, ISA::RV32I).with_funct3(0b001));
-        map.insert("blt",       Instruction::new(Opcode::Branch,  Format::SBType, ISA::RV32I).with_funct3(0b100));
-        map.insert("bge",       Instruction::new(Opcode::Branch,  Format::SBType, ISA::RV32I).with_funct3(0b101));
-        map.insert("bltu",      Instruction::new(Opcode::Branch,  Format::SBType, ISA::RV32I).with_funct3(0b110));
-        map.insert("bgeu",      Instruction::new(Opcode::Branch,  Format::SBType, ISA::RV32I).with_funct3(0b111));
-        map.insert("lb",        Instruction::new(Opcode::Load,    Format::IType, ISA::RV32I).with_funct3(0b000));
-        map.insert("lh",        Instruction::new(Opcode::Load,    Format::IType, ISA::RV32I).with_funct3(0b001));
-        map.insert("lw",        Instruction::new(Opcode::Load,    Format::IType, ISA::RV32I).with_funct3(0b010));
-        map.insert("lbu",       Instruction::new(Opcode::Load,    Format::IType, ISA::RV32I).with_funct3(0b100));
-        map.insert("lhu",       Instruction::new(Opcode::Load,    Format::IType, ISA::RV32I).with_funct3(0b101));
-        map.insert("sb",        Instruction::new(Opcode::Store,   Format::SType, ISA::RV32I).with_funct3(0b000));
-        map.insert("sh",        Instruction::new(Opcode::Store,   Format::SType, ISA::RV32I).with_funct3(0b001));
-        map.insert("sw",        Instruction::new(Opcode::Store,   Format::SType, ISA::RV32I).with_funct3(0b010));
-        map.insert("addi",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b000));
-        map.insert("slti",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b010));
-        map.insert("sltiu",     Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b011));
-        map.insert("xori",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b100));
-        map.insert("ori",       Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b110));
-        map.insert("andi",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b111));
-        map.insert("slli",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b001).with_shift(ShiftType::SLL));
-        map.insert("srli",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b101).with_shift(ShiftType::SRL));
-        map.insert("srai",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b101).with_shift(ShiftType::SRA));
-        map.insert("add",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b000).with_funct7(0b0000000));
-        map.insert("sub",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b000).with_funct7(0b0100000));
-        map.insert("sll",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b001).with_funct7(0b0000000));
-        map.insert("slt",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b010).with_funct7(0b0000000));
-        map.insert("sltu",      Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b011).with_funct7(0b0000000));
-        map.insert("xor",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b100).with_funct7(0b0000000));
-        map.insert("srl",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b101).with_funct7(0b0000000));
-        map.insert("sra",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b101).with_funct7(0b0100000));
-        map.insert("or",        Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b110).with_funct7(0b0000000));
-        map.insert("and",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b111).with_funct7(0b0000000));
-        map.insert("fence",     Instruction::new(Opcode::MiscMem, Format::IType, ISA::RV32I).with_funct3(0b000));
-        map.insert("ecall",     Instruction::new(Opcode::System,  Format::IType, ISA::RV32I).with_funct3(0b000).with_funct12(0b000000000000));
-        map.insert("ebreak",    Instruction::new(Opcode::System,  Format::IType, ISA::RV32I).with_funct3(0b000).with_funct12(0b000000000001));
-
-        map.insert("addiw",     Instruction::new(Opcode::OpImm32, Format::IType, ISA::RV64I).with_funct3(0b000));
-        map.insert("slliw",     Instruction::new(Opcode::OpImm32, Format::IType, ISA::RV64I).with_funct3(0b001).with_shift(ShiftType::SLLW));
-        map.insert("srliw",     Instruction::new(Opcode::OpImm32, Format::IType, ISA::RV64I).with_funct3(0b101).with_shift(ShiftType::SRLW));
-        map.insert("sraiw",     Instruction::new(Opcode::OpImm32, Format::IType, ISA::RV64I).with_funct3(0b101).with_shift(ShiftType::SRAW));
-        map.insert("addw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64I).with_funct3(0b000).with_funct7(0b0000000));
-        map.insert("subw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64I).with_funct3(0b000).with_funct7(0b0100000));
-        map.insert("sllw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64I).with_funct3(0b001).with_funct7(0b0000000));
-        map.insert("srlw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64I).with_funct3(0b101).with_funct7(0b0000000));
-        map.insert("sraw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64I).with_funct3(0b101).with_funct7(0b0100000));
-        map.insert("ld",        Instruction::new(Opcode::Load,    Format::IType, ISA::RV64I).with_funct3(0b011));
-        map.insert("lwu",       Instruction::new(Opcode::Load,    Format::IType, ISA::RV64I).with_funct3(0b110));
-        map.insert("sd",        Instruction::new(Opcode::Store,   Format::SType, ISA::RV64I).with_funct3(0b011));
-
-        map.insert("addid",     Instruction::new(Opcode::OpImm64, Format::IType, ISA::RV128I).with_funct3(0b000));
-        map.insert("sllid",     Instruction::new(Opcode::OpImm64, Format::IType, ISA::RV128I).with_funct3(0b001).with_shift(ShiftType::SLLD));
-        map.insert("srlid",     Instruction::new(Opcode::OpImm64, Format::IType, ISA::RV128I).with_funct3(0b101).with_shift(ShiftType::SRLD));
-        map.insert("sraid",     Instruction::new(Opcode::OpImm64, Format::IType, ISA::RV128I).with_funct3(0b101).with_shift(ShiftType::SRAD));
-        map.insert("addd",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128I).with_funct3(0b000).with_funct7(0b0000000));
-        map.insert("subd",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128I).with_funct3(0b000).with_funct7(0b0100000));
-        map.insert("slld",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128I).with_funct3(0b001).with_funct7(0b0000000));
-        map.insert("srld",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128I).with_funct3(0b101).with_funct7(0b0000000));
-        map.insert("srad",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128I).with_funct3(0b101).with_funct7(0b0100000));
-        map.insert("lq",        Instruction::new(Opcode::MiscMem, Format::IType, ISA::RV128I).with_funct3(0b010));
-        map.insert("ldu",       Instruction::new(Opcode::Load,    Format::IType, ISA::RV128I).with_funct3(0b111));
-        map.insert("sq",        Instruction::new(Opcode::Store,   Format::SType, ISA::RV128I).with_funct3(0b100));
-
-        map.insert("fence.i",   Instruction::new(Opcode::MiscMem, Format::SType, ISA::ZiFencei).with_funct3(0b001));
-
-        map.insert("csrrw",     Instruction::new(Opcode::System,  Format::IType, ISA::Zicsr).with_funct3(0b001));
-        map.insert("csrrs",     Instruction::new(Opcode::System,  Format::IType, ISA::Zicsr).with_funct3(0b010));
-        map.insert("csrrc",     Instruction::new(Opcode::System,  Format::IType, ISA::Zicsr).with_funct3(0b011));
-        map.insert("csrrwi",    Instruction::new(Opcode::System,  Format::IType, ISA::Zicsr).with_funct3(0b101));
-        map.insert("csrrsi",    Instruction::new(Opcode::System,  Format::IType, ISA::Zicsr).with_funct3(0b110));
-        map.insert("csrrci",    Instruction::new(Opcode::System,  Format::IType, ISA::Zicsr).with_funct3(0b111));
-
-        map.insert("mul",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b000).with_funct7(0b0000001));
-        map.insert("mulh",      Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b001).with_funct7(0b0000001));
-        map.insert("mulhsu",    Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b010).with_funct7(0b0000001));
-        map.insert("mulhu",     Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b011).with_funct7(0b0000001));
-        map.insert("div",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b100).with_funct7(0b0000001));
-        map.insert("divu",      Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b101).with_funct7(0b0000001));
-        map.insert("rem",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b110).with_funct7(0b0000001));
-        map.insert("remu",      Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b111).with_funct7(0b0000001));
-
-        map.insert("mulw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64M).with_funct3(0b000).with_funct7(0b0000001));
-        map.insert("divw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64M).with_funct3(0b100).with_funct7(0b0000001));
-        map.insert("divuw",     Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64M).with_funct3(0b101).with_funct7(0b0000001));
-        map.insert("remw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64M).with_funct3(0b110).with_funct7(0b0000001));
-        map.insert("remuw",     Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64M).with_funct3(0b111).with_funct7(0b0000001));
-
-        map.insert("muld",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128M).with_funct3(0b000).with_funct7(0b0000001));
-        map.insert("divd",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128M).with_funct3(0b100).with_funct7(0b0000001));
-        map.insert("divud",     Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128M).with_funct3(0b101).with_funct7(0b0000001));
-        map.insert("remd",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128M).with_funct3(0b110).with_funct7(0b0000001));
-        map.insert("remud",     Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128M).with_funct3(0b111).with_funct7(0b0000001));
-
-        map.insert("lr.w",      Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b00010));
-        map.insert("sc.w",      Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b00011));
-        map.insert("amoswap.w", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b00001));
-        map.insert("amoadd.w",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b00000));
-        map.insert("amoxor.w",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b00100));
-        map.insert("amoand.w",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b01100));
-        map.insert("amoor.w",   Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b01000));
-        map.insert("amomin.w",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b10000));
-        map.insert("amomax.w",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b10100));
-        map.insert("amominu.w", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b11000));
-        map.insert("amomaxu.w", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b11100));
-
-        map.insert("lr.d",      Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b00010));
-        map.insert("sc.d",      Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b00011));
-        map.insert("amoswap.d", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b00001));
-        map.insert("amoadd.d",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b00000));
-        map.insert("amoxor.d",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b00100));
-        map.insert("amoand.d",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b01100));
-        map.insert("amoor.d",   Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b01000));
-        map.insert("amomin.d",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b10000));
-        map.insert("amomax.d",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b10100));
-        map.insert("amominu.d", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b11000));
-        map.insert("amomaxu.d", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b11100));
-
-        map.insert("lr.q",      Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b00010));
-        map.insert("sc.q",      Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b00011));
-        map.insert("amoswap.q", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b00001));
-        map.insert("amoadd.q",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b00000));
-        map.insert("amoxor.q",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b00100));
-        map.insert("amoand.q",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b01100));
-        map.insert("amoor.q",   Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b01000));
-        map.insert("amomin.q",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b10000));
-        map.insert("amomax.q",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b10100));
-        map.insert("amominu.q", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b11000));
-        map.insert("amomaxu.q", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b11100));
-
-        map.insert("flw",       Instruction::new(Opcode::LoadFp,  Format::IType, ISA::RV32F).with_funct3(FloatWidth::Single as u8));
-        map.insert("fsw",       Instruction::new(Opcode::StoreFp, Format::SType, ISA::RV32F).with_funct3(FloatWidth::Single as u8));
-        map.insert("fmadd.s",   Instruction::new(Opcode::MAdd,    Format::R4Type, ISA::RV32F).with_float_format(FloatFormat::Single));
-        map.insert("fmsub.s",   Instruction::new(Opcode::MSub,    Format::R4Type, ISA::RV32F).with_float_format(FloatFormat::Single));
-        map.insert("fnmadd.s",  Instruction::new(Opcode::NmAdd,   Format::R4Type, ISA::RV32F).with_float_format(FloatFormat::Single));
-        map.insert("fnmsub.s",  Instruction::new(Opcode::NmSub,   Format::R4Type, ISA::RV32F).with_float_format(FloatFormat::Single));
-        map.insert("fadd.s",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00000).with_float_format(FloatFormat::Single));
-        map.insert("fsub.s",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00001).with_float_format(FloatFormat::Single));
-        map.insert("fmul.s",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00010).with_float_format(FloatFormat::Single));
-        map.insert("fdiv.s",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00011).with_float_format(FloatFormat::Single));
-        map.insert("fsqrt.s",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b01011).with_rs2(0b00000).with_float_format(FloatFormat::Single));
-        map.insert("fsgnj.s",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00100).with_funct3(0b000).with_float_format(FloatFormat::Single));
-        map.insert("fsgnjn.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00100).with_funct3(0b001).with_float_format(FloatFormat::Single));
-        map.insert("fsgnjx.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00100).with_funct3(0b010).with_float_format(FloatFormat::Single));
-        map.insert("fmin.s",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00101).with_funct3(0b000).with_float_format(FloatFormat::Single));
-        map.insert("fmax.s",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00101).with_funct3(0b001).with_float_format(FloatFormat::Single));
-        map.insert("feq.s",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b10100).with_funct3(0b010).with_float_format(FloatFormat::Single));
-        map.insert("flt.s",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b10100).with_funct3(0b001).with_float_format(FloatFormat::Single));
-        map.insert("fle.s",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b10100).with_funct3(0b000).with_float_format(FloatFormat::Single));
-        map.insert("fcvt.w.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11000).with_rs2(0b00000).with_float_format(FloatFormat::Single));
-        map.insert("fcvt.wu.s", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11000).with_rs2(0b00001).with_float_format(FloatFormat::Single));
-        map.insert("fcvt.s.w",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11010).with_rs2(0b00000).with_float_format(FloatFormat::Single));
-        map.insert("fcvt.s.wu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11010).with_rs2(0b00001).with_float_format(FloatFormat::Single));
-        map.insert("fclass.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11100).with_rs2(0b00000).with_funct3(0b001).with_float_format(FloatFormat::Single));
-
-        map.insert("fmv.x.w",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11100).with_rs2(0b00000).with_funct3(0b000).with_float_format(FloatFormat::Single));
-        map.insert("fmv.w.x",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11110).with_rs2(0b00000).with_funct3(0b000).with_float_format(FloatFormat::Single));
-        map.insert("fcvt.l.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64F).with_funct5(0b11000).with_rs2(0b00010).with_float_format(FloatFormat::Single));
-        map.insert("fcvt.lu.s", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64F).with_funct5(0b11000).with_rs2(0b00011).with_float_format(FloatFormat::Single));
-        map.insert("fcvt.s.l",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64F).with_funct5(0b11010).with_rs2(0b00010).with_float_format(FloatFormat::Single));
-        map.insert("fcvt.s.lu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64F).with_funct5(0b11010).with_rs2(0b00011).with_float_format(FloatFormat::Single));
-
-        map.insert("fcvt.t.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128F).with_funct5(0b11000).with_rs2(0b00100).with_float_format(FloatFormat::Single));
-        map.insert("fcvt.tu.s", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128F).with_funct5(0b11000).with_rs2(0b00101).with_float_format(FloatFormat::Single));
-        map.insert("fcvt.s.t",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128F).with_funct5(0b11010).with_rs2(0b00100).with_float_format(FloatFormat::Single));
-        map.insert("fcvt.s.tu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128F).with_funct5(0b11010).with_rs2(0b00101).with_float_format(FloatFormat::Single));
-
-        map.insert("fld",       Instruction::new(Opcode::LoadFp,  Format::IType, ISA::RV32D).with_funct3(FloatWidth::Double as u8));
-        map.insert("fsd",       Instruction::new(Opcode::StoreFp, Format::SType, ISA::RV32D).with_funct3(FloatWidth::Double as u8));
-
-        map.insert("fmadd.d",   Instruction::new(Opcode::MAdd,    Format::R4Type, ISA::RV32D).with_float_format(FloatFormat::Double));
-        map.insert("fmsub.d",   Instruction::new(Opcode::MSub,    Format::R4Type, ISA::RV32D).with_float_format(FloatFormat::Double));
-        map.insert("fnmadd.d",  Instruction::new(Opcode::NmAdd,   Format::R4Type, ISA::RV32D).with_float_format(FloatFormat::Double));
-        map.insert("fnmsub.d",  Instruction::new(Opcode::NmSub,   Format::R4Type, ISA::RV32D).with_float_format(FloatFormat::Double));
-
-        map.insert("fadd.d",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00000).with_float_format(FloatFormat::Double));
-        map.insert("fsub.d",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00001).with_float_format(FloatFormat::Double));
-        map.insert("fmul.d",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00010).with_float_format(FloatFormat::Double));
-        map.insert("fdiv.d",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00011).with_float_format(FloatFormat::Double));
-
-        map.insert("fsqrt.d",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b01011).with_rs2(0b00000).with_float_format(FloatFormat::Double));
-
-        map.insert("fsgnj.d",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00100).with_funct3(0b000).with_float_format(FloatFormat::Double));
-        map.insert("fsgnjn.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00100).with_funct3(0b001).with_float_format(FloatFormat::Double));
-        map.insert("fsgnjx.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00100).with_funct3(0b010).with_float_format(FloatFormat::Double));
-        map.insert("fmin.d",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00101).with_funct3(0b000).with_float_format(FloatFormat::Double));
-        map.insert("fmax.d",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00101).with_funct3(0b001).with_float_format(FloatFormat::Double));
-
-        map.insert("feq.d",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b10100).with_funct3(0b010).with_float_format(FloatFormat::Double));
-        map.insert("flt.d",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b10100).with_funct3(0b001).with_float_format(FloatFormat::Double));
-        map.insert("fle.d",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b10100).with_funct3(0b000).with_float_format(FloatFormat::Double));
-
-        map.insert("fcvt.w.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b11000).with_rs2(0b00000).with_float_format(FloatFormat::Double));
-        map.insert("fcvt.wu.d", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b11000).with_rs2(0b00001).with_float_format(FloatFormat::Double));
-        map.insert("fcvt.d.w",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b11010).with_rs2(0b00000).with_float_format(FloatFormat::Double));
-        map.insert("fcvt.d.wu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b11010).with_rs2(0b00001).with_float_format(FloatFormat::Double));
-
-        map.insert("fcvt.s.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b01000).with_rs2(0b00000).with_float_format(FloatFormat::Single));
-        map.insert("fcvt.d.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b01000).with_rs2(0b00000).with_float_format(FloatFormat::Double));
-
-        map.insert("fclass.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b11100).with_rs2(0b00000).with_funct3(0b001).with_float_format(FloatFormat::Double));
-
-        map.insert("fmv.x.d",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64D).with_funct5(0b11100).with_rs2(0b00000).with_funct3(0b000).with_float_format(FloatFormat::Double));
-        map.insert("fmv.d.x",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64D).with_funct5(0b11110).with_rs2(0b00000).with_funct3(0b000).with_float_format(FloatFormat::Double));
-
-        map.insert("fcvt.l.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64D).with_funct5(0b11000).with_rs2(0b00010).with_float_format(FloatFormat::Double));
-        map.insert("fcvt.lu.d", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64D).with_funct5(0b11000).with_rs2(0b00011).with_float_format(FloatFormat::Double));
-        map.insert("fcvt.d.l",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64D).with_funct5(0b11010).with_rs2(0b00010).with_float_format(FloatFormat::Double));
-        map.insert("fcvt.d.lu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64D).with_funct5(0b11010).with_rs2(0b00011).with_float_format(FloatFormat::Double));
-
-        map.insert("fcvt.t.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128D).with_funct5(0b11000).with_rs2(0b00100).with_float_format(FloatFormat::Double));
-        map.insert("fcvt.tu.d", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128D).with_funct5(0b11000).with_rs2(0b00101).with_float_format(FloatFormat::Double));
-        map.insert("fcvt.d.t",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128D).with_funct5(0b11010).with_rs2(0b00100).with_float_format(FloatFormat::Double));
-        map.insert("fcvt.d.tu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128D).with_funct5(0b11010).with_rs2(0b00101).with_float_format(FloatFormat::Double));
-
-        map.insert("flq",       Instruction::new(Opcode::LoadFp,  Format::IType, ISA::RV32Q).with_funct3(FloatWidth::Quad as u8));
-        map.insert("fsq",       Instruction::new(Opcode::StoreFp, Format::SType, ISA::RV32Q).with_funct3(FloatWidth::Quad as u8));
-
-        map.insert("fmadd.q",   Instruction::new(Opcode::MAdd,    Format::R4Type, ISA::RV32Q).with_float_format(FloatFormat::Quad));
-        map.insert("fmsub.q",   Instruction::new(Opcode::MSub,    Format::R4Type, ISA::RV32Q).with_float_format(FloatFormat::Quad));
-        map.insert("fnmadd.q",  Instruction::new(Opcode::NmAdd,   Format::R4Type, ISA::RV32Q).with_float_format(FloatFormat::Quad));
-        map.insert("fnmsub.q",  Instruction::new(Opcode::NmSub,   Format::R4Type, ISA::RV32Q).with_float_format(FloatFormat::Quad));
-
-        map.insert("fadd.q",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00000).with_float_format(FloatFormat::Quad));
-        map.insert("fsub.q",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00001).with_float_format(FloatFormat::Quad));
-        map.insert("fmul.q",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00010).with_float_format(FloatFormat::Quad));
-        map.insert("fdiv.q",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00011).with_float_format(FloatFormat::Quad));
-
-        map.insert("fsqrt.q",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b01011).with_rs2(0b00000).with_float_format(FloatFormat::Quad));
-
-        map.insert("fsgnj.q",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00100).with_funct3(0b000).with_float_format(FloatFormat::Quad));
-        map.insert("fsgnjn.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00100).with_funct3(0b001).with_float_format(FloatFormat::Quad));
-        map.insert("fsgnjx.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00100).with_funct3(0b010).with_float_format(FloatFormat::Quad));
-        map.insert("fmin.q",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00101).with_funct3(0b000).with_float_format(FloatFormat::Quad));
-        map.insert("fmax.q",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00101).with_funct3(0b001).with_float_format(FloatFormat::Quad));
-
-        map.insert("feq.q",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b10100).with_funct3(0b010).with_float_format(FloatFormat::Quad));
-        map.insert("flt.q",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b10100).with_funct3(0b001).with_float_format(FloatFormat::Quad));
-        map.insert("fle.q",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b10100).with_funct3(0b000).with_float_format(FloatFormat::Quad));
-
-        map.insert("fcvt.w.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b11000).with_rs2(0b00000).with_float_format(FloatFormat::Quad));
-        map.insert("fcvt.wu.q", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b11000).with_rs2(0b00001).with_float_format(FloatFormat::Quad));
-        map.insert("fcvt.q.w",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b11010).with_rs2(0b00000).with_float_format(FloatFormat::Quad));
-        map.insert("fcvt.q.wu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b11010).with_rs2(0b00001).with_float_format(FloatFormat::Quad));
-
-        map.insert("fcvt.s.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b01000).with_rs2(0b00000).with_float_format(FloatFormat::Single));
-        map.insert("fcvt.q.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b01000).with_rs2(0b00000).with_float_format(FloatFormat::Quad));
-        map.insert("fcvt.d.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b01000).with_rs2(0b00000).with_float_format(FloatFormat::Double));
-        map.insert("fcvt.q.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b01000).with_rs2(0b00000).with_float_format(FloatFormat::Quad));
-
-        map.insert("fclass.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b11100).with_rs2(0b00000).with_funct3(0b001).with_float_format(FloatFormat::Quad));
-
-        map.insert("fcvt.l.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64Q).with_funct5(0b11000).with_rs2(0b00010).with_float_format(FloatFormat::Quad));
-        map.insert("fcvt.lu.q", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64Q).with_funct5(0b11000).with_rs2(0b00011).with_float_format(FloatFormat::Quad));
-        map.insert("fcvt.q.l",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64Q).with_funct5(0b11010).with_rs2(0b00010).with_float_format(FloatFormat::Quad));
-        map.insert("fcvt.q.lu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64Q).with_funct5(0b11010).with_rs2(0b00011).with_float_format(FloatFormat::Quad));
-
-        map.insert("fmv.x.q",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128Q).with_funct5(0b11100).with_rs2(0b00000).with_funct3(0b000).with_float_format(FloatFormat::Quad));
-        map.insert("fmv.q.x",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128Q).with_funct5(0b11110).with_rs2(0b00000).with_funct3(0b000).with_float_format(FloatFormat::Quad));
-
-        map.insert("fcvt.t.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128Q).with_funct5(0b11000).with_rs2(0b00100).with_float_format(FloatFormat::Quad));
-        map.insert("fcvt.tu.q", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128Q).with_funct5(0b11000).with_rs2(0b00101).with_float_format(FloatFormat::Quad));
-        map.insert("fcvt.q.t",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128Q).with_funct5(0b11010).with_rs2(0b00100).with_float_format(FloatFormat::Quad));
-        map.insert("fcvt.q.tu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128Q).with_funct5(0b11010).with_rs2(0b00101).with_float_format(FloatFormat::Quad));
+        map.insert("lui",       Instruction::new(Opcode::Lui,     Format::UType, ISA::RV32I).with_operands(RD_UIMM20));
+        map.insert("auipc",     Instruction::new(Opcode::AuiPC,   Format::UType, ISA::RV32I).with_operands(RD_UIMM20));
+        map.insert("jal",       Instruction::new(Opcode::Jal,     Format::UJType, ISA::RV32I).with_operands(RD_OFFSET21));
+        map.insert("jalr",      Instruction::new(Opcode::Jalr,    Format::IType, ISA::RV32I).with_funct3(0b000).with_operands(RD_ADDRESS));
+        map.insert("beq",       Instruction::new(Opcode::Branch,  Format::SBType, ISA::RV32I).with_funct3(0b000).with_operands(RS1_RS2_OFFSET13));
+        map.insert("bne",       Instruction::new(Opcode::Branch,  Format::SBType, ISA::RV32I).with_funct3(0b001).with_operands(RS1_RS2_OFFSET13));
+        map.insert("blt",       Instruction::new(Opcode::Branch,  Format::SBType, ISA::RV32I).with_funct3(0b100).with_operands(RS1_RS2_OFFSET13));
+        map.insert("bge",       Instruction::new(Opcode::Branch,  Format::SBType, ISA::RV32I).with_funct3(0b101).with_operands(RS1_RS2_OFFSET13));
+        map.insert("bltu",      Instruction::new(Opcode::Branch,  Format::SBType, ISA::RV32I).with_funct3(0b110).with_operands(RS1_RS2_OFFSET13));
+        map.insert("bgeu",      Instruction::new(Opcode::Branch,  Format::SBType, ISA::RV32I).with_funct3(0b111).with_operands(RS1_RS2_OFFSET13));
+        map.insert("lb",        Instruction::new(Opcode::Load,    Format::IType, ISA::RV32I).with_funct3(0b000).with_operands(RD_ADDRESS));
+        map.insert("lh",        Instruction::new(Opcode::Load,    Format::IType, ISA::RV32I).with_funct3(0b001).with_operands(RD_ADDRESS));
+        map.insert("lw",        Instruction::new(Opcode::Load,    Format::IType, ISA::RV32I).with_funct3(0b010).with_operands(RD_ADDRESS));
+        map.insert("lbu",       Instruction::new(Opcode::Load,    Format::IType, ISA::RV32I).with_funct3(0b100).with_operands(RD_ADDRESS));
+        map.insert("lhu",       Instruction::new(Opcode::Load,    Format::IType, ISA::RV32I).with_funct3(0b101).with_operands(RD_ADDRESS));
+        map.insert("sb",        Instruction::new(Opcode::Store,   Format::SType, ISA::RV32I).with_funct3(0b000).with_operands(RS2_ADDRESS));
+        map.insert("sh",        Instruction::new(Opcode::Store,   Format::SType, ISA::RV32I).with_funct3(0b001).with_operands(RS2_ADDRESS));
+        map.insert("sw",        Instruction::new(Opcode::Store,   Format::SType, ISA::RV32I).with_funct3(0b010).with_operands(RS2_ADDRESS));
+        map.insert("addi",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b000).with_operands(RD_RS1_IMM12));
+        map.insert("slti",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b010).with_operands(RD_RS1_IMM12));
+        map.insert("sltiu",     Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b011).with_operands(RD_RS1_IMM12));
+        map.insert("xori",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b100).with_operands(RD_RS1_IMM12));
+        map.insert("ori",       Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b110).with_operands(RD_RS1_IMM12));
+        map.insert("andi",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b111).with_operands(RD_RS1_IMM12));
+        map.insert("slli",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b001).with_shift(ShiftType::SLL).with_operands(RD_RS1_SHAMT6));
+        map.insert("srli",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b101).with_shift(ShiftType::SRL).with_operands(RD_RS1_SHAMT6));
+        map.insert("srai",      Instruction::new(Opcode::OpImm,   Format::IType, ISA::RV32I).with_funct3(0b101).with_shift(ShiftType::SRA).with_operands(RD_RS1_SHAMT6));
+        map.insert("add",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b000).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("sub",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b000).with_funct7(0b0100000).with_operands(RD_RS1_RS2));
+        map.insert("sll",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b001).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("slt",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b010).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("sltu",      Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b011).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("xor",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b100).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("srl",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b101).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("sra",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b101).with_funct7(0b0100000).with_operands(RD_RS1_RS2));
+        map.insert("or",        Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b110).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("and",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32I).with_funct3(0b111).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("fence",     Instruction::new(Opcode::MiscMem, Format::IType, ISA::RV32I).with_funct3(0b000).with_operands(FENCE));
+        map.insert("ecall",     Instruction::new(Opcode::System,  Format::IType, ISA::RV32I).with_funct3(0b000).with_funct12(0b000000000000).with_operands(NONE));
+        map.insert("ebreak",    Instruction::new(Opcode::System,  Format::IType, ISA::RV32I).with_funct3(0b000).with_funct12(0b000000000001).with_operands(NONE));
+
+        map.insert("addiw",     Instruction::new(Opcode::OpImm32, Format::IType, ISA::RV64I).with_funct3(0b000).with_operands(RD_RS1_IMM12));
+        map.insert("slliw",     Instruction::new(Opcode::OpImm32, Format::IType, ISA::RV64I).with_funct3(0b001).with_shift(ShiftType::SLLW).with_operands(RD_RS1_SHAMT5));
+        map.insert("srliw",     Instruction::new(Opcode::OpImm32, Format::IType, ISA::RV64I).with_funct3(0b101).with_shift(ShiftType::SRLW).with_operands(RD_RS1_SHAMT5));
+        map.insert("sraiw",     Instruction::new(Opcode::OpImm32, Format::IType, ISA::RV64I).with_funct3(0b101).with_shift(ShiftType::SRAW).with_operands(RD_RS1_SHAMT5));
+        map.insert("addw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64I).with_funct3(0b000).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("subw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64I).with_funct3(0b000).with_funct7(0b0100000).with_operands(RD_RS1_RS2));
+        map.insert("sllw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64I).with_funct3(0b001).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("srlw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64I).with_funct3(0b101).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("sraw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64I).with_funct3(0b101).with_funct7(0b0100000).with_operands(RD_RS1_RS2));
+        map.insert("ld",        Instruction::new(Opcode::Load,    Format::IType, ISA::RV64I).with_funct3(0b011).with_operands(RD_ADDRESS));
+        map.insert("lwu",       Instruction::new(Opcode::Load,    Format::IType, ISA::RV64I).with_funct3(0b110).with_operands(RD_ADDRESS));
+        map.insert("sd",        Instruction::new(Opcode::Store,   Format::SType, ISA::RV64I).with_funct3(0b011).with_operands(RS2_ADDRESS));
+
+        map.insert("addid",     Instruction::new(Opcode::OpImm64, Format::IType, ISA::RV128I).with_funct3(0b000).with_operands(RD_RS1_IMM12));
+        map.insert("sllid",     Instruction::new(Opcode::OpImm64, Format::IType, ISA::RV128I).with_funct3(0b001).with_shift(ShiftType::SLLD).with_operands(RD_RS1_SHAMT7));
+        map.insert("srlid",     Instruction::new(Opcode::OpImm64, Format::IType, ISA::RV128I).with_funct3(0b101).with_shift(ShiftType::SRLD).with_operands(RD_RS1_SHAMT7));
+        map.insert("sraid",     Instruction::new(Opcode::OpImm64, Format::IType, ISA::RV128I).with_funct3(0b101).with_shift(ShiftType::SRAD).with_operands(RD_RS1_SHAMT7));
+        map.insert("addd",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128I).with_funct3(0b000).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("subd",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128I).with_funct3(0b000).with_funct7(0b0100000).with_operands(RD_RS1_RS2));
+        map.insert("slld",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128I).with_funct3(0b001).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("srld",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128I).with_funct3(0b101).with_funct7(0b0000000).with_operands(RD_RS1_RS2));
+        map.insert("srad",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128I).with_funct3(0b101).with_funct7(0b0100000).with_operands(RD_RS1_RS2));
+        map.insert("lq",        Instruction::new(Opcode::MiscMem, Format::IType, ISA::RV128I).with_funct3(0b010).with_operands(RD_ADDRESS));
+        map.insert("ldu",       Instruction::new(Opcode::Load,    Format::IType, ISA::RV128I).with_funct3(0b111).with_operands(RD_ADDRESS));
+        map.insert("sq",        Instruction::new(Opcode::Store,   Format::SType, ISA::RV128I).with_funct3(0b100).with_operands(RS2_ADDRESS));
+
+        map.insert("fence.i",   Instruction::new(Opcode::MiscMem, Format::SType, ISA::ZiFencei).with_funct3(0b001).with_operands(NONE));
+
+        map.insert("sret",      Instruction::new(Opcode::System,  Format::IType, ISA::Priv).with_funct3(0b000).with_funct12(0b000100000010).with_operands(NONE));
+        map.insert("mret",      Instruction::new(Opcode::System,  Format::IType, ISA::Priv).with_funct3(0b000).with_funct12(0b001100000010).with_operands(NONE));
+        map.insert("wfi",       Instruction::new(Opcode::System,  Format::IType, ISA::Priv).with_funct3(0b000).with_funct12(0b000100000101).with_operands(NONE));
+        map.insert("sfence.vma", Instruction::new(Opcode::System, Format::RType, ISA::Priv).with_funct3(0b000).with_funct7(0b0001001).with_operands(RS1_RS2));
+
+        map.insert("csrrw",     Instruction::new(Opcode::System,  Format::IType, ISA::Zicsr).with_funct3(0b001).with_operands(RD_CSR_RS1));
+        map.insert("csrrs",     Instruction::new(Opcode::System,  Format::IType, ISA::Zicsr).with_funct3(0b010).with_operands(RD_CSR_RS1));
+        map.insert("csrrc",     Instruction::new(Opcode::System,  Format::IType, ISA::Zicsr).with_funct3(0b011).with_operands(RD_CSR_RS1));
+        map.insert("csrrwi",    Instruction::new(Opcode::System,  Format::IType, ISA::Zicsr).with_funct3(0b101).with_operands(RD_CSR_UIMM5));
+        map.insert("csrrsi",    Instruction::new(Opcode::System,  Format::IType, ISA::Zicsr).with_funct3(0b110).with_operands(RD_CSR_UIMM5));
+        map.insert("csrrci",    Instruction::new(Opcode::System,  Format::IType, ISA::Zicsr).with_funct3(0b111).with_operands(RD_CSR_UIMM5));
+
+        map.insert("mul",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b000).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("mulh",      Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b001).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("mulhsu",    Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b010).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("mulhu",     Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b011).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("div",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b100).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("divu",      Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b101).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("rem",       Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b110).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("remu",      Instruction::new(Opcode::Op,      Format::RType, ISA::RV32M).with_funct3(0b111).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+
+        map.insert("mulw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64M).with_funct3(0b000).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("divw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64M).with_funct3(0b100).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("divuw",     Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64M).with_funct3(0b101).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("remw",      Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64M).with_funct3(0b110).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("remuw",     Instruction::new(Opcode::Op32,    Format::RType, ISA::RV64M).with_funct3(0b111).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+
+        map.insert("muld",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128M).with_funct3(0b000).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("divd",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128M).with_funct3(0b100).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("divud",     Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128M).with_funct3(0b101).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("remd",      Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128M).with_funct3(0b110).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+        map.insert("remud",     Instruction::new(Opcode::Op64,    Format::RType, ISA::RV128M).with_funct3(0b111).with_funct7(0b0000001).with_operands(RD_RS1_RS2));
+
+        map.insert("lr.w",      Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b00010).with_rs2(0b00000).with_operands(RD_RS1));
+        map.insert("sc.w",      Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b00011).with_operands(RD_RS1_RS2));
+        map.insert("amoswap.w", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b00001).with_operands(RD_RS1_RS2));
+        map.insert("amoadd.w",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b00000).with_operands(RD_RS1_RS2));
+        map.insert("amoxor.w",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b00100).with_operands(RD_RS1_RS2));
+        map.insert("amoand.w",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b01100).with_operands(RD_RS1_RS2));
+        map.insert("amoor.w",   Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b01000).with_operands(RD_RS1_RS2));
+        map.insert("amomin.w",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b10000).with_operands(RD_RS1_RS2));
+        map.insert("amomax.w",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b10100).with_operands(RD_RS1_RS2));
+        map.insert("amominu.w", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b11000).with_operands(RD_RS1_RS2));
+        map.insert("amomaxu.w", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV32A).with_funct3(0b010).with_funct5(0b11100).with_operands(RD_RS1_RS2));
+
+        map.insert("lr.d",      Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b00010).with_rs2(0b00000).with_operands(RD_RS1));
+        map.insert("sc.d",      Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b00011).with_operands(RD_RS1_RS2));
+        map.insert("amoswap.d", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b00001).with_operands(RD_RS1_RS2));
+        map.insert("amoadd.d",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b00000).with_operands(RD_RS1_RS2));
+        map.insert("amoxor.d",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b00100).with_operands(RD_RS1_RS2));
+        map.insert("amoand.d",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b01100).with_operands(RD_RS1_RS2));
+        map.insert("amoor.d",   Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b01000).with_operands(RD_RS1_RS2));
+        map.insert("amomin.d",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b10000).with_operands(RD_RS1_RS2));
+        map.insert("amomax.d",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b10100).with_operands(RD_RS1_RS2));
+        map.insert("amominu.d", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b11000).with_operands(RD_RS1_RS2));
+        map.insert("amomaxu.d", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV64A).with_funct3(0b011).with_funct5(0b11100).with_operands(RD_RS1_RS2));
+
+        map.insert("lr.q",      Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b00010).with_rs2(0b00000).with_operands(RD_RS1));
+        map.insert("sc.q",      Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b00011).with_operands(RD_RS1_RS2));
+        map.insert("amoswap.q", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b00001).with_operands(RD_RS1_RS2));
+        map.insert("amoadd.q",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b00000).with_operands(RD_RS1_RS2));
+        map.insert("amoxor.q",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b00100).with_operands(RD_RS1_RS2));
+        map.insert("amoand.q",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b01100).with_operands(RD_RS1_RS2));
+        map.insert("amoor.q",   Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b01000).with_operands(RD_RS1_RS2));
+        map.insert("amomin.q",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b10000).with_operands(RD_RS1_RS2));
+        map.insert("amomax.q",  Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b10100).with_operands(RD_RS1_RS2));
+        map.insert("amominu.q", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b11000).with_operands(RD_RS1_RS2));
+        map.insert("amomaxu.q", Instruction::new(Opcode::Amo,     Format::RType, ISA::RV128A).with_funct3(0b100).with_funct5(0b11100).with_operands(RD_RS1_RS2));
+
+        map.insert("flw",       Instruction::new(Opcode::LoadFp,  Format::IType, ISA::RV32F).with_funct3(FloatWidth::Single as u8).with_operands(FRD_ADDRESS));
+        map.insert("fsw",       Instruction::new(Opcode::StoreFp, Format::SType, ISA::RV32F).with_funct3(FloatWidth::Single as u8).with_operands(FRS2_ADDRESS));
+        map.insert("fmadd.s",   Instruction::new(Opcode::MAdd,    Format::R4Type, ISA::RV32F).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_FRS2_FRS3_RM));
+        map.insert("fmsub.s",   Instruction::new(Opcode::MSub,    Format::R4Type, ISA::RV32F).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_FRS2_FRS3_RM));
+        map.insert("fnmadd.s",  Instruction::new(Opcode::NmAdd,   Format::R4Type, ISA::RV32F).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_FRS2_FRS3_RM));
+        map.insert("fnmsub.s",  Instruction::new(Opcode::NmSub,   Format::R4Type, ISA::RV32F).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_FRS2_FRS3_RM));
+        map.insert("fadd.s",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00000).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_FRS2_RM));
+        map.insert("fsub.s",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00001).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_FRS2_RM));
+        map.insert("fmul.s",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00010).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_FRS2_RM));
+        map.insert("fdiv.s",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00011).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_FRS2_RM));
+        map.insert("fsqrt.s",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b01011).with_rs2(0b00000).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_RM));
+        map.insert("fsgnj.s",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00100).with_funct3(0b000).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_FRS2));
+        map.insert("fsgnjn.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00100).with_funct3(0b001).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_FRS2));
+        map.insert("fsgnjx.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00100).with_funct3(0b010).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_FRS2));
+        map.insert("fmin.s",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00101).with_funct3(0b000).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_FRS2));
+        map.insert("fmax.s",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b00101).with_funct3(0b001).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_FRS2));
+        map.insert("feq.s",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b10100).with_funct3(0b010).with_float_format(FloatFormat::Single).with_operands(RD_FRS1_FRS2));
+        map.insert("flt.s",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b10100).with_funct3(0b001).with_float_format(FloatFormat::Single).with_operands(RD_FRS1_FRS2));
+        map.insert("fle.s",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b10100).with_funct3(0b000).with_float_format(FloatFormat::Single).with_operands(RD_FRS1_FRS2));
+        map.insert("fcvt.w.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11000).with_rs2(0b00000).with_float_format(FloatFormat::Single).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.wu.s", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11000).with_rs2(0b00001).with_float_format(FloatFormat::Single).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.s.w",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11010).with_rs2(0b00000).with_float_format(FloatFormat::Single).with_operands(FRD_RS1_RM));
+        map.insert("fcvt.s.wu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11010).with_rs2(0b00001).with_float_format(FloatFormat::Single).with_operands(FRD_RS1_RM));
+        map.insert("fclass.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11100).with_rs2(0b00000).with_funct3(0b001).with_float_format(FloatFormat::Single).with_operands(RD_FRS1));
+
+        map.insert("fmv.x.w",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11100).with_rs2(0b00000).with_funct3(0b000).with_float_format(FloatFormat::Single).with_operands(RD_FRS1));
+        map.insert("fmv.w.x",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32F).with_funct5(0b11110).with_rs2(0b00000).with_funct3(0b000).with_float_format(FloatFormat::Single).with_operands(FRD_RS1));
+        map.insert("fcvt.l.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64F).with_funct5(0b11000).with_rs2(0b00010).with_float_format(FloatFormat::Single).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.lu.s", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64F).with_funct5(0b11000).with_rs2(0b00011).with_float_format(FloatFormat::Single).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.s.l",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64F).with_funct5(0b11010).with_rs2(0b00010).with_float_format(FloatFormat::Single).with_operands(FRD_RS1_RM));
+        map.insert("fcvt.s.lu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64F).with_funct5(0b11010).with_rs2(0b00011).with_float_format(FloatFormat::Single).with_operands(FRD_RS1_RM));
+
+        map.insert("fcvt.t.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128F).with_funct5(0b11000).with_rs2(0b00100).with_float_format(FloatFormat::Single).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.tu.s", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128F).with_funct5(0b11000).with_rs2(0b00101).with_float_format(FloatFormat::Single).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.s.t",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128F).with_funct5(0b11010).with_rs2(0b00100).with_float_format(FloatFormat::Single).with_operands(FRD_RS1_RM));
+        map.insert("fcvt.s.tu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128F).with_funct5(0b11010).with_rs2(0b00101).with_float_format(FloatFormat::Single).with_operands(FRD_RS1_RM));
+
+        map.insert("fld",       Instruction::new(Opcode::LoadFp,  Format::IType, ISA::RV32D).with_funct3(FloatWidth::Double as u8).with_operands(FRD_ADDRESS));
+        map.insert("fsd",       Instruction::new(Opcode::StoreFp, Format::SType, ISA::RV32D).with_funct3(FloatWidth::Double as u8).with_operands(FRS2_ADDRESS));
+
+        map.insert("fmadd.d",   Instruction::new(Opcode::MAdd,    Format::R4Type, ISA::RV32D).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_FRS2_FRS3_RM));
+        map.insert("fmsub.d",   Instruction::new(Opcode::MSub,    Format::R4Type, ISA::RV32D).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_FRS2_FRS3_RM));
+        map.insert("fnmadd.d",  Instruction::new(Opcode::NmAdd,   Format::R4Type, ISA::RV32D).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_FRS2_FRS3_RM));
+        map.insert("fnmsub.d",  Instruction::new(Opcode::NmSub,   Format::R4Type, ISA::RV32D).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_FRS2_FRS3_RM));
+
+        map.insert("fadd.d",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00000).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_FRS2_RM));
+        map.insert("fsub.d",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00001).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_FRS2_RM));
+        map.insert("fmul.d",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00010).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_FRS2_RM));
+        map.insert("fdiv.d",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00011).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_FRS2_RM));
+
+        map.insert("fsqrt.d",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b01011).with_rs2(0b00000).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_RM));
+
+        map.insert("fsgnj.d",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00100).with_funct3(0b000).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_FRS2));
+        map.insert("fsgnjn.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00100).with_funct3(0b001).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_FRS2));
+        map.insert("fsgnjx.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00100).with_funct3(0b010).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_FRS2));
+        map.insert("fmin.d",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00101).with_funct3(0b000).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_FRS2));
+        map.insert("fmax.d",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b00101).with_funct3(0b001).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_FRS2));
+
+        map.insert("feq.d",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b10100).with_funct3(0b010).with_float_format(FloatFormat::Double).with_operands(RD_FRS1_FRS2));
+        map.insert("flt.d",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b10100).with_funct3(0b001).with_float_format(FloatFormat::Double).with_operands(RD_FRS1_FRS2));
+        map.insert("fle.d",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b10100).with_funct3(0b000).with_float_format(FloatFormat::Double).with_operands(RD_FRS1_FRS2));
+
+        map.insert("fcvt.w.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b11000).with_rs2(0b00000).with_float_format(FloatFormat::Double).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.wu.d", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b11000).with_rs2(0b00001).with_float_format(FloatFormat::Double).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.d.w",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b11010).with_rs2(0b00000).with_float_format(FloatFormat::Double).with_operands(FRD_RS1_RM));
+        map.insert("fcvt.d.wu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b11010).with_rs2(0b00001).with_float_format(FloatFormat::Double).with_operands(FRD_RS1_RM));
+
+        map.insert("fcvt.s.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b01000).with_rs2(0b00001).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_RM));
+        map.insert("fcvt.d.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b01000).with_rs2(0b00000).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_RM));
+
+        map.insert("fclass.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32D).with_funct5(0b11100).with_rs2(0b00000).with_funct3(0b001).with_float_format(FloatFormat::Double).with_operands(RD_FRS1));
+
+        map.insert("fmv.x.d",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64D).with_funct5(0b11100).with_rs2(0b00000).with_funct3(0b000).with_float_format(FloatFormat::Double).with_operands(RD_FRS1));
+        map.insert("fmv.d.x",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64D).with_funct5(0b11110).with_rs2(0b00000).with_funct3(0b000).with_float_format(FloatFormat::Double).with_operands(FRD_RS1));
+
+        map.insert("fcvt.l.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64D).with_funct5(0b11000).with_rs2(0b00010).with_float_format(FloatFormat::Double).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.lu.d", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64D).with_funct5(0b11000).with_rs2(0b00011).with_float_format(FloatFormat::Double).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.d.l",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64D).with_funct5(0b11010).with_rs2(0b00010).with_float_format(FloatFormat::Double).with_operands(FRD_RS1_RM));
+        map.insert("fcvt.d.lu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64D).with_funct5(0b11010).with_rs2(0b00011).with_float_format(FloatFormat::Double).with_operands(FRD_RS1_RM));
+
+        map.insert("fcvt.t.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128D).with_funct5(0b11000).with_rs2(0b00100).with_float_format(FloatFormat::Double).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.tu.d", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128D).with_funct5(0b11000).with_rs2(0b00101).with_float_format(FloatFormat::Double).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.d.t",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128D).with_funct5(0b11010).with_rs2(0b00100).with_float_format(FloatFormat::Double).with_operands(FRD_RS1_RM));
+        map.insert("fcvt.d.tu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128D).with_funct5(0b11010).with_rs2(0b00101).with_float_format(FloatFormat::Double).with_operands(FRD_RS1_RM));
+
+        map.insert("flq",       Instruction::new(Opcode::LoadFp,  Format::IType, ISA::RV32Q).with_funct3(FloatWidth::Quad as u8).with_operands(FRD_ADDRESS));
+        map.insert("fsq",       Instruction::new(Opcode::StoreFp, Format::SType, ISA::RV32Q).with_funct3(FloatWidth::Quad as u8).with_operands(FRS2_ADDRESS));
+
+        map.insert("fmadd.q",   Instruction::new(Opcode::MAdd,    Format::R4Type, ISA::RV32Q).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_FRS2_FRS3_RM));
+        map.insert("fmsub.q",   Instruction::new(Opcode::MSub,    Format::R4Type, ISA::RV32Q).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_FRS2_FRS3_RM));
+        map.insert("fnmadd.q",  Instruction::new(Opcode::NmAdd,   Format::R4Type, ISA::RV32Q).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_FRS2_FRS3_RM));
+        map.insert("fnmsub.q",  Instruction::new(Opcode::NmSub,   Format::R4Type, ISA::RV32Q).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_FRS2_FRS3_RM));
+
+        map.insert("fadd.q",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00000).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_FRS2_RM));
+        map.insert("fsub.q",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00001).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_FRS2_RM));
+        map.insert("fmul.q",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00010).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_FRS2_RM));
+        map.insert("fdiv.q",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00011).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_FRS2_RM));
+
+        map.insert("fsqrt.q",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b01011).with_rs2(0b00000).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_RM));
+
+        map.insert("fsgnj.q",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00100).with_funct3(0b000).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_FRS2));
+        map.insert("fsgnjn.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00100).with_funct3(0b001).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_FRS2));
+        map.insert("fsgnjx.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00100).with_funct3(0b010).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_FRS2));
+        map.insert("fmin.q",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00101).with_funct3(0b000).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_FRS2));
+        map.insert("fmax.q",    Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b00101).with_funct3(0b001).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_FRS2));
+
+        map.insert("feq.q",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b10100).with_funct3(0b010).with_float_format(FloatFormat::Quad).with_operands(RD_FRS1_FRS2));
+        map.insert("flt.q",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b10100).with_funct3(0b001).with_float_format(FloatFormat::Quad).with_operands(RD_FRS1_FRS2));
+        map.insert("fle.q",     Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b10100).with_funct3(0b000).with_float_format(FloatFormat::Quad).with_operands(RD_FRS1_FRS2));
+
+        map.insert("fcvt.w.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b11000).with_rs2(0b00000).with_float_format(FloatFormat::Quad).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.wu.q", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b11000).with_rs2(0b00001).with_float_format(FloatFormat::Quad).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.q.w",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b11010).with_rs2(0b00000).with_float_format(FloatFormat::Quad).with_operands(FRD_RS1_RM));
+        map.insert("fcvt.q.wu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b11010).with_rs2(0b00001).with_float_format(FloatFormat::Quad).with_operands(FRD_RS1_RM));
+
+        map.insert("fcvt.s.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b01000).with_rs2(0b00011).with_float_format(FloatFormat::Single).with_operands(FRD_FRS1_RM));
+        map.insert("fcvt.q.s",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b01000).with_rs2(0b00000).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_RM));
+        map.insert("fcvt.d.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b01000).with_rs2(0b00011).with_float_format(FloatFormat::Double).with_operands(FRD_FRS1_RM));
+        map.insert("fcvt.q.d",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b01000).with_rs2(0b00001).with_float_format(FloatFormat::Quad).with_operands(FRD_FRS1_RM));
+
+        map.insert("fclass.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV32Q).with_funct5(0b11100).with_rs2(0b00000).with_funct3(0b001).with_float_format(FloatFormat::Quad).with_operands(RD_FRS1));
+
+        map.insert("fcvt.l.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64Q).with_funct5(0b11000).with_rs2(0b00010).with_float_format(FloatFormat::Quad).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.lu.q", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64Q).with_funct5(0b11000).with_rs2(0b00011).with_float_format(FloatFormat::Quad).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.q.l",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64Q).with_funct5(0b11010).with_rs2(0b00010).with_float_format(FloatFormat::Quad).with_operands(FRD_RS1_RM));
+        map.insert("fcvt.q.lu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV64Q).with_funct5(0b11010).with_rs2(0b00011).with_float_format(FloatFormat::Quad).with_operands(FRD_RS1_RM));
+
+        map.insert("fmv.x.q",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128Q).with_funct5(0b11100).with_rs2(0b00000).with_funct3(0b000).with_float_format(FloatFormat::Quad).with_operands(RD_FRS1));
+        map.insert("fmv.q.x",   Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128Q).with_funct5(0b11110).with_rs2(0b00000).with_funct3(0b000).with_float_format(FloatFormat::Quad).with_operands(FRD_RS1));
+
+        map.insert("fcvt.t.q",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128Q).with_funct5(0b11000).with_rs2(0b00100).with_float_format(FloatFormat::Quad).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.tu.q", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128Q).with_funct5(0b11000).with_rs2(0b00101).with_float_format(FloatFormat::Quad).with_operands(RD_FRS1_RM));
+        map.insert("fcvt.q.t",  Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128Q).with_funct5(0b11010).with_rs2(0b00100).with_float_format(FloatFormat::Quad).with_operands(FRD_RS1_RM));
+        map.insert("fcvt.q.tu", Instruction::new(Opcode::OpFp,    Format::RType, ISA::RV128Q).with_funct5(0b11010).with_rs2(0b00101).with_float_format(FloatFormat::Quad).with_operands(FRD_RS1_RM));
 
         map
     };
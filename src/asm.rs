@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 use lazy_static::lazy_static;
+use num_traits::Num;
 
 use crate::{
-    lexer::*, lex, arch::*,
+    lexer::*, lex, arch::*, mem::*,
     codec::enc::*, encode
 };
 
@@ -10,7 +11,16 @@ pub struct Object
 {
     pub binary: Vec<u8>,
     pub relocations: Vec<(Emittable /* Instruction */, usize /* Start address */)>,
-    pub symbols: HashMap<String /* Identifier */, usize /* Start address */>
+    pub symbols: HashMap<String /* Identifier */, usize /* Start address */>,
+    pub sections: Vec<Section>
+}
+
+impl Default for Object
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
 }
 
 impl Object
@@ -21,7 +31,8 @@ impl Object
         {
             binary: Vec::new(),
             relocations: Vec::new(),
-            symbols: HashMap::new()
+            symbols: HashMap::new(),
+            sections: Vec::new()
         }
     }
 }
@@ -57,9 +68,9 @@ lazy_static!
         m.insert("bgt",    (vec!["rs", "rt", "offset"], lex!("blt rt, rs, offset").unwrap()));
         m.insert("ble",    (vec!["rs", "rt", "offset"], lex!("bge rt, rs, offset").unwrap()));
         m.insert("bgtu",   (vec!["rs", "rt", "offset"], lex!("bltu rt, rs, offset").unwrap()));
-        m.insert("bleu",   (vec!["rt", "rs", "offset"], lex!("bltu rt, rs, offset").unwrap()));
+        m.insert("bleu",   (vec!["rs", "rt", "offset"], lex!("bgeu rt, rs, offset").unwrap()));
         m.insert("j",      (vec!["offset"],             lex!("jal x0, offset").unwrap()));
-        m.insert("jr",     (vec!["offset"],             lex!("jal x1, offset").unwrap()));
+        m.insert("jr",     (vec!["rs1"],                lex!("jalr x0, rs1, 0").unwrap()));
         m.insert("ret",    (vec![],                     lex!("jalr x0, x1, 0").unwrap()));
         // li variations.
         m.insert("li.16",  (vec!["rd", "imm"],          lex!("addi rd, x0, imm").unwrap()));
@@ -86,6 +97,9 @@ lazy_static!
 }
 
 
+// Macro names mapped to their arguments and body.
+type Macros = HashMap<String, (Vec<String>, Vec<Token>)>;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum AssemblerErr
 {
@@ -103,6 +117,12 @@ pub struct Assembler
 impl Assembler
 {
     pub fn new(code: &str) -> Result<Self, AssemblerErr>
+    {
+        Self::with_xlen(code, Xlen::X64)
+    }
+
+    // Assembles for an `xlen` wide target, which bounds the shift amounts of immediate shifts.
+    pub fn with_xlen(code: &str, xlen: Xlen) -> Result<Self, AssemblerErr>
     {
         match lex!(code)
         {
@@ -115,7 +135,7 @@ impl Assembler
 
                 Ok(Assembler
                 { // Convert tokens into binary.
-                    object: Self::process_binary(&t)?
+                    object: Self::process_binary(t, xlen)?
                 })
             },
             // Propagate lexer errors.
@@ -123,37 +143,33 @@ impl Assembler
         }
     }
 
-    fn drain_macros(tokens: &mut Vec<Token>) -> Result<HashMap<String, (Vec<String>, Vec<Token>)>, AssemblerErr>
+    fn drain_macros(tokens: &mut Vec<Token>) -> Result<Macros, AssemblerErr>
     {
         let mut to_drain = Vec::new();
 
         // Identify macro directives and their ranges.
         for (index, token) in tokens.iter().enumerate()
         {
-            match token
-            {
-                Token::Directive(Directive::Macro(name_str, args)) =>
-                { // Check if macro name is a reserved keyword.
-                    if RV_ISA.contains_key(name_str.as_str()) || PSEUDO_INSTRUCTIONS.contains_key(name_str.as_str())
-                    {
-                        return Err(AssemblerErr::Syntax(
-                            format!(r#""{}" is a reserved keyword."#, name_str)
-                        ))
-                    }
+            if let Token::Directive(Directive::Macro(name_str, args)) = token
+            { // Check if macro name is a reserved keyword.
+                if RV_ISA.contains_key(name_str.as_str()) || PSEUDO_INSTRUCTIONS.contains_key(name_str.as_str())
+                {
+                    return Err(AssemblerErr::Syntax(
+                        format!(r#""{}" is a reserved keyword."#, name_str)
+                    ))
+                }
 
-                    let end_index = tokens[index+1..].iter()
-                        .position(|t| matches!(t, Token::Directive(Directive::Marker(m)) if m == "endm"))
-                        .ok_or_else(|| AssemblerErr::Syntax(
-                            format!(r#""{}" expected an end marker."#, name_str)
-                        ))? + index + 1;
+                let end_index = tokens[index+1..].iter()
+                    .position(|t| matches!(t, Token::Directive(Directive::Marker(m)) if m == "endm"))
+                    .ok_or_else(|| AssemblerErr::Syntax(
+                        format!(r#""{}" expected an end marker."#, name_str)
+                    ))? + index + 1;
 
-                    to_drain.push((name_str.clone(), args.clone(), index, end_index));
-                },
-                _ => {}
+                to_drain.push((name_str.clone(), args.clone(), index, end_index));
             }
         }
         // Sort ranges in reverse order to avoid index shifting during draining.
-        to_drain.sort_by(|a, b| b.2.cmp(&a.2));
+        to_drain.sort_by_key(|range| std::cmp::Reverse(range.2));
 
         let mut macros = HashMap::new();
 
@@ -181,59 +197,55 @@ impl Assembler
         }
 
         exp_details.1.iter_mut()
-            .for_each(|token| match token
-        {
-            Token::Emittable(Emittable::Instruction(_, operands)) =>
-            { // Map placeholder identifiers to actual arguments.
-                let find_fn = |identifier: &str| -> Option<Operand>
+            .for_each(|token| if let Token::Emittable(Emittable::Instruction(_, operands)) = token
+        { // Map placeholder identifiers to actual arguments.
+            let find_fn = |identifier: &str| -> Option<Operand>
+            {
+                if let Some(index) = exp_details.0.iter().position(|s: &String| s == identifier)
                 {
-                    if let Some(index) = exp_details.0.iter().position(|s: &String| s == identifier)
-                    {
-                        return Some(arguments[index].clone())
-                    }
-                    None
-                };
+                    return Some(arguments[index].clone())
+                }
+                None
+            };
 
-                // Match possible operands and replace them with actual argument.
-                operands.iter_mut().for_each(|operand| match operand
+            // Match possible operands and replace them with actual argument.
+            operands.iter_mut().for_each(|operand| match operand
+            {
+                Operand::RValue(RValue::Identifier(ident_str)) =>
                 {
-                    Operand::RValue(RValue::Identifier(ident_str)) =>
+                    if let Some(Operand::RValue(new_val)) = find_fn(ident_str)
                     {
-                        if let Some(Operand::RValue(new_val)) = find_fn(ident_str)
-                        {
-                            *operand = Operand::RValue(new_val);
-                        }
-                    },
-                    Operand::Address(a, b) =>
+                        *operand = Operand::RValue(new_val);
+                    }
+                },
+                Operand::Address(a, b) =>
+                {
+                    for val in [a, b]
                     {
-                        for val in [a, b]
+                        if let RValue::Identifier(ident_str) = &val
                         {
-                            if let RValue::Identifier(ident_str) = &val
+                            if let Some(Operand::RValue(new_val)) = find_fn(ident_str)
                             {
-                                if let Some(Operand::RValue(new_val)) = find_fn(ident_str)
-                                {
-                                    *val = new_val;
-                                }
+                                *val = new_val;
                             }
                         }
-                    },
-                    Operand::RelocationFn(func_str, RValue::Identifier(ident_str)) =>
+                    }
+                },
+                Operand::RelocationFn(func_str, RValue::Identifier(ident_str)) =>
+                {
+                    if let Some(Operand::RValue(new_val)) = find_fn(ident_str)
                     {
-                        if let Some(Operand::RValue(new_val)) = find_fn(ident_str)
-                        {
-                            *operand = Operand::RelocationFn(func_str.clone(), new_val);
-                        }
-                    },
-                    _ => {}
-                })
-            },
-            _ => {}
+                        *operand = Operand::RelocationFn(func_str.clone(), new_val);
+                    }
+                },
+                _ => {}
+            })
         });
 
         Ok(&exp_details.1)
     }
 
-    fn process_expansions<'a>(tokens: &'a mut Vec<Token>, macros: &'a HashMap<String, (Vec<String>, Vec<Token>)>) -> Result<&'a Vec<Token>, AssemblerErr>
+    fn process_expansions<'a>(tokens: &'a mut Vec<Token>, macros: &'a Macros) -> Result<&'a Vec<Token>, AssemblerErr>
     {
         let mut indices_to_expand = Vec::new();
 
@@ -248,7 +260,7 @@ impl Assembler
                     {
                         let width = match imm
                         {
-                            -32768..=32767 => "16",
+                            -2048..=2047 => "16",
                             -2147483648..=2147483647 => "32"
                         };
 
@@ -269,7 +281,7 @@ impl Assembler
             }
         }
         // Sort indices in reverse order to avoid index shifting during expansion.
-        indices_to_expand.sort_by(|a, b| b.cmp(&a));
+        indices_to_expand.sort_by_key(|index| std::cmp::Reverse(*index));
 
         // Expand tokens at the indices.
         for index in indices_to_expand
@@ -283,7 +295,7 @@ impl Assembler
                     }
                     else if let Some(details) = macros.get(mnemonic.as_str())
                     {
-                        (details.0.clone(), Self::process_expansions(&mut details.1.clone(), &macros)?.clone())
+                        (details.0.clone(), Self::process_expansions(&mut details.1.clone(), macros)?.clone())
                     }
                     else
                     {
@@ -300,24 +312,168 @@ impl Assembler
     }
 
 
-    fn process_binary(tokens: &Vec<Token>) -> Result<Object, AssemblerErr>
+    fn process_binary(tokens: &[Token], xlen: Xlen) -> Result<Object, AssemblerErr>
     {
         let mut object = Object::new();
 
+        // Size sections and record label offsets up front so forward references can be resolved.
+        let (contents, labels, alignments) = Self::emit_sections(tokens, &mut object.sections, None, xlen)?;
+
+        // Sections are laid out consecutively in order of their first appearance.
+        let mut address = 0;
+        for (section, (content, alignment)) in object.sections.iter_mut().zip(contents.iter().zip(alignments))
+        {
+            section.address = align_address(address, alignment);
+            section.length = content.len();
+            address = section.address + section.length;
+        }
+
+        for (name, index, offset) in labels
+        {
+            if object.symbols.contains_key(&name)
+            {
+                return Err(AssemblerErr::Syntax(
+                    format!(r#""{}" is already defined."#, name)
+                ))
+            }
+            object.symbols.insert(name, object.sections[index].address + offset);
+        }
+
+        let (contents, ..) = Self::emit_sections(tokens, &mut object.sections, Some(&object.symbols), xlen)?;
+
+        object.binary = vec![0; address];
+        for (section, content) in object.sections.iter().zip(contents)
+        {
+            object.binary[section.address..section.address + section.length].copy_from_slice(&content);
+        }
+        Ok(object)
+    }
+
+    // Emits the contents of each section along with labels as (name, section index, offset) and the largest alignment
+    // of each section. Without `symbols` instructions and symbolic data are sized but left zeroed.
+    #[allow(clippy::type_complexity)]
+    fn emit_sections(tokens: &[Token], sections: &mut Vec<Section>, symbols: Option<&HashMap<String, usize>>, xlen: Xlen)
+        -> Result<(Vec<Vec<u8>>, Vec<(String, usize, usize)>, Vec<usize>), AssemblerErr>
+    {
+        let mut contents: Vec<Vec<u8>> = vec![Vec::new(); sections.len()];
+        let mut alignments: Vec<usize> = vec![4; sections.len()];
+        let mut labels = Vec::new();
+        let mut current = None;
+
         for token in tokens
         {
+            let index = match (token, current)
+            {
+                (Token::Directive(Directive::Section(name, attributes, alignment)), _) =>
+                {
+                    current = Some(Self::section(sections, &mut contents, &mut alignments, name, attributes));
+                    alignments[current.unwrap()] = alignments[current.unwrap()].max(1 << alignment);
+                    continue
+                },
+                (_, Some(index)) => index,
+                // Code preceding any section directive is placed in "text".
+                (_, None) => *current.insert(Self::section(sections, &mut contents, &mut alignments, "text", &SectionFlags::EXECUTE))
+            };
+
+            let address = sections[index].address + contents[index].len();
+            let bytes = &mut contents[index];
+
             match token
             {
-                Token::Emittable(Emittable::Instruction(mnemonic, operands)) =>
+                Token::Label(name) => labels.push((name.clone(), index, bytes.len())),
+                Token::Directive(Directive::Alignment(align)) =>
                 {
-                    let bytes = &encode!(&mnemonic, &operands).map_err(AssemblerErr::Encoder)?;
+                    let (alignment, fill, max) = match align
+                    {
+                        Align::AsPow(power, fill, max) => (1 << power, *fill, *max as usize),
+                        Align::AsBytes(alignment, fill) => ((*alignment).max(1) as usize, *fill, 0)
+                    };
+                    alignments[index] = alignments[index].max(alignment);
 
-                    object.binary.extend_from_slice(bytes);
+                    // Padding beyond the maximum skips the alignment altogether.
+                    let padding = align_address(address, alignment) - address;
+                    if max == 0 || padding <= max
+                    {
+                        bytes.resize(bytes.len() + padding, fill as u8);
+                    }
+                },
+                Token::Emittable(Emittable::Instruction(mnemonic, operands)) => match symbols
+                {
+                    Some(symbols) =>
+                    {
+                        let operands = Self::resolve_symbols(mnemonic, operands, address, symbols);
+                        bytes.extend_from_slice(&encode!(mnemonic, &operands, xlen).map_err(AssemblerErr::Encoder)?);
+                    },
+                    None => bytes.extend_from_slice(&[0; 4])
                 },
+                Token::Emittable(emittable) => bytes.extend(Self::emit_data(emittable, symbols)?),
                 _ => {}
             }
         }
-        Ok(object)
+        Ok((contents, labels, alignments))
+    }
+
+    // Index of the section named `name`, created on first use.
+    fn section(sections: &mut Vec<Section>, contents: &mut Vec<Vec<u8>>, alignments: &mut Vec<usize>, name: &str, attributes: &SectionFlags) -> usize
+    {
+        sections.iter().position(|section| section.name == name).unwrap_or_else(||
+        {
+            sections.push(Section{ name: name.into(), address: 0, length: 0, attributes: attributes.clone() });
+            contents.push(Vec::new());
+            alignments.push(4);
+            sections.len() - 1
+        })
+    }
+
+    // Little endian bytes of a data directive, symbols are replaced by their address.
+    fn emit_data(emittable: &Emittable, symbols: Option<&HashMap<String, usize>>) -> Result<Vec<u8>, AssemblerErr>
+    {
+        fn values<T: Num + Copy + Into<i64> + std::fmt::Display>(values: &[RValue<T>], symbols: Option<&HashMap<String, usize>>) -> Result<Vec<i64>, AssemblerErr>
+        {
+            values.iter().map(|value| match (value, symbols)
+            {
+                (RValue::Immediate(value), _) => Ok((*value).into()),
+                (RValue::Identifier(_), None) => Ok(0),
+                (RValue::Identifier(name), Some(symbols)) => symbols.get(name)
+                    .map(|address| *address as i64)
+                    .ok_or_else(|| AssemblerErr::Syntax(
+                        format!(r#""{}" is not defined."#, name)
+                    )),
+                (RValue::Register(..), _) => Err(AssemblerErr::Syntax(
+                    format!(r#"Expected data value, found "{}"."#, value)
+                ))
+            }).collect()
+        }
+
+        Ok(match emittable
+        {
+            Emittable::Byte(bytes) => values(bytes, symbols)?.iter().flat_map(|value| (*value as i8).to_le_bytes()).collect(),
+            Emittable::Half(halves) => values(halves, symbols)?.iter().flat_map(|value| (*value as i16).to_le_bytes()).collect(),
+            Emittable::Word(words) => values(words, symbols)?.iter().flat_map(|value| (*value as i32).to_le_bytes()).collect(),
+            Emittable::Dword(dwords) => values(dwords, symbols)?.iter().flat_map(|value| value.to_le_bytes()).collect(),
+            Emittable::String(string) => string.bytes().chain([0]).collect(),
+            Emittable::Instruction(..) => Vec::new()
+        })
+    }
+
+    // Replace label operands of branches and jumps with offsets relative to `address`.
+    fn resolve_symbols(mnemonic: &str, operands: &[Operand], address: usize, symbols: &HashMap<String, usize>) -> Vec<Operand>
+    {
+        match RV_ISA.get(mnemonic)
+        {
+            Some(instruction) if matches!(instruction.opcode, Opcode::Branch | Opcode::Jal) =>
+            {
+                operands.iter().map(|operand| match operand
+                {
+                    Operand::RValue(RValue::Identifier(name)) if symbols.contains_key(name) =>
+                    {
+                        RValue::Immediate(symbols[name] as i32 - address as i32).into()
+                    },
+                    _ => operand.clone()
+                }).collect()
+            },
+            _ => operands.to_vec()
+        }
     }
 }
 
@@ -326,7 +482,11 @@ macro_rules! assemble
 {
     ($code: expr) =>
     {
-        match Assembler::new($code)
+        $crate::assemble!($code, $crate::arch::Xlen::X64)
+    };
+    ($code: expr, $xlen: expr) =>
+    {
+        match Assembler::with_xlen($code, $xlen)
         {
             Ok(assembler) => Ok(assembler.object),
             Err(assembler_err) => Err(assembler_err)
@@ -0,0 +1,60 @@
+use std::{
+    fs::File, io::BufReader, process::ExitCode
+};
+use aem::emu::trace::*;
+
+const USAGE: &str = "usage: tracediff [--context <instructions>] [--csrs] <first trace> <second trace>";
+
+// Compares two commit traces, exiting with 1 and a report of the first divergence when they differ.
+fn main() -> ExitCode
+{
+    let mut diff = TraceDiff::new(10);
+    let mut paths = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next()
+    {
+        match arg.as_str()
+        {
+            "--context" => match args.next().and_then(|context| context.parse().ok())
+            {
+                Some(context) => diff.context = context,
+                None =>
+                {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(2)
+                }
+            },
+            "--csrs" => diff.csrs = true,
+            _ => paths.push(arg)
+        }
+    }
+
+    let [first, second] = paths.as_slice() else
+    {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2)
+    };
+
+    let open = |path: &String| File::open(path).map(BufReader::new)
+        .map_err(|io_err| eprintln!(r#"Unable to open "{}": {}"#, path, io_err));
+    let (Ok(first), Ok(second)) = (open(first), open(second)) else
+    {
+        return ExitCode::from(2)
+    };
+
+    match diff.compare(first, second)
+    {
+        Ok(None) => ExitCode::SUCCESS,
+        Ok(Some(divergence)) =>
+        {
+            print!("{}", divergence);
+            ExitCode::from(1)
+        },
+        Err(io_err) =>
+        {
+            eprintln!("Unable to read the traces: {}", io_err);
+            ExitCode::from(2)
+        }
+    }
+}
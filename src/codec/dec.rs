@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use lazy_static::lazy_static;
+
+use crate::{
+    lexer::*,
+    arch::*
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecoderErr
+{
+    Opcode(String),
+    Encoding(String),
+    FloatRounding(String)
+}
+
+lazy_static!
+{ // RV_ISA entries grouped by their major opcode, sorted by mnemonic for deterministic matching.
+    static ref OPCODE_TABLE: HashMap<u8, Vec<(&'static str, &'static Instruction)>> =
+    {
+        let mut map: HashMap<u8, Vec<(&'static str, &'static Instruction)>> = HashMap::new();
+
+        for (mnemonic, instruction) in RV_ISA.iter()
+        {
+            map.entry(instruction.opcode as u8).or_default().push((mnemonic, instruction));
+        }
+
+        map.values_mut().for_each(|entries| entries.sort_by_key(|(mnemonic, _)| *mnemonic));
+        map
+    };
+}
+
+pub struct Decoder
+{
+    pub mnemonic: String,
+    pub operands: Vec<Operand>
+}
+
+impl Decoder
+{
+    pub fn new(binary: u32) -> Result<Self, DecoderErr>
+    {
+        let opcode = Opcode::try_from((binary & 0x7F) as u8)
+            .map_err(|bits| DecoderErr::Opcode(
+                format!(r#"Unsupported instruction opcode: "0b{:07b}""#, bits)
+            ))?;
+
+        let (mnemonic, instruction) = OPCODE_TABLE.get(&(opcode as u8))
+            .and_then(|entries| entries.iter().find(|(_, instruction)| Self::matches(instruction, binary)))
+            .ok_or_else(|| DecoderErr::Encoding(
+                format!(r#"Unknown instruction encoding: "0x{:08x}""#, binary)
+            ))?;
+
+        let mut operands = Vec::new();
+        let mut fences = 0;
+
+        for operand_type in instruction.operands
+        {
+            operands.extend(Self::decode_operand(instruction, operand_type, binary, &mut fences)?);
+        }
+
+        Ok(Decoder{
+            mnemonic: mnemonic.to_string(),
+            operands
+        })
+    }
+
+    // Compares the fixed fields of an RV_ISA entry against the instruction word.
+    fn matches(instruction: &Instruction, binary: u32) -> bool
+    {
+        let funct3 = (binary >> 12) & 0b111;
+        let funct5 = binary >> 27;
+        let funct7 = binary >> 25;
+        let funct12 = binary >> 20;
+        let fmt = (binary >> 25) & 0b11;
+        let rs2 = (binary >> 20) & 0b11111;
+
+        let field_eq = |field: Option<u32>, value: u32| field.is_none_or(|f| f == value);
+
+        if !field_eq(instruction.funct3.map(u32::from), funct3)
+            || !field_eq(instruction.funct5.map(u32::from), funct5)
+            || !field_eq(instruction.funct7.map(u32::from), funct7)
+            || !field_eq(instruction.funct12.map(u32::from), funct12)
+            || !field_eq(instruction.rs2.map(u32::from), rs2)
+            || !field_eq(instruction.float_format.clone().map(|f| f as u32), fmt)
+        {
+            return false
+        }
+
+        // Shift immediates carry the arithmetic flag (bit 30) above the shift amount.
+        match instruction.shift
+        {
+            Some(shift_type) =>
+            {
+                let (upper, arithmetic) = match instruction.opcode
+                {
+                    Opcode::OpImm32 => (binary >> 25, 0b0100000),
+                    Opcode::OpImm64 => (binary >> 27, 0b01000),
+                    _               => (binary >> 26, 0b010000)
+                };
+
+                match shift_type
+                {
+                    ShiftType::SRA | ShiftType::SRAW | ShiftType::SRAD => upper == arithmetic,
+                    _ => upper == 0
+                }
+            },
+            None => true
+        }
+    }
+
+    fn decode_operand(instruction: &Instruction, operand_type: &OperandType, binary: u32, fences: &mut u32) -> Result<Option<Operand>, DecoderErr>
+    {
+        let register = |file: &RegisterFile, index: u32| -> Operand
+        {
+            let prefix = if *file == RegisterFile::Float { 'f' } else { 'x' };
+            RValue::Register(prefix, index & 0b11111).into()
+        };
+
+        let operand = match operand_type
+        {
+            OperandType::Rd(file)  => register(file, binary >> 7),
+            OperandType::Rs1(file) => register(file, binary >> 15),
+            OperandType::Rs2(file) => register(file, binary >> 20),
+            OperandType::Rs3(file) => register(file, binary >> 27),
+            OperandType::Immediate(_, signed) =>
+            {
+                RValue::Immediate(match instruction.format
+                {
+                    Format::UType => (binary >> 12) as i32,
+                    _ if *signed  => (binary as i32) >> 20,
+                    _             => (binary >> 20) as i32
+                }).into()
+            },
+            OperandType::Shamt(width) => RValue::Immediate(((binary >> 20) & ((1 << width) - 1)) as i32).into(),
+            OperandType::Offset(_) =>
+            {
+                RValue::Immediate(match instruction.format
+                {
+                    Format::SBType => Self::branch_offset(binary),
+                    _ => Self::jump_offset(binary)
+                }).into()
+            },
+            OperandType::Address =>
+            {
+                let offset = match instruction.format
+                {
+                    Format::SType => (((binary as i32) >> 25) << 5) | ((binary >> 7) & 0x1F) as i32,
+                    _ => (binary as i32) >> 20
+                };
+                Operand::Address(RValue::Register('x', (binary >> 15) & 0b11111), RValue::Immediate(offset))
+            },
+            OperandType::Csr => RValue::Immediate((binary >> 20) as i32).into(),
+            OperandType::CsrImmediate => RValue::Immediate(((binary >> 15) & 0b11111) as i32).into(),
+            OperandType::Fence =>
+            { // The predecessor set precedes the successor set.
+                *fences += 1;
+                let shift = if *fences == 1 { 24 } else { 20 };
+                RValue::Immediate(((binary >> shift) & 0xF) as i32).into()
+            },
+            OperandType::RoundingMode => return Self::rounding_mode(binary)
+        };
+        Ok(Some(operand))
+    }
+
+    fn rounding_mode(binary: u32) -> Result<Option<Operand>, DecoderErr>
+    { // Dynamic rounding is implied when no rounding mode operand is present.
+        match (binary >> 12) & 0b111
+        {
+            0b111 => Ok(None),
+            0b101 | 0b110 => Err(DecoderErr::FloatRounding(
+                format!(r#"Reserved floating point rounding mode: "0x{:08x}""#, binary)
+            )),
+            rm => Ok(Some(RValue::Identifier(ROUNDING_MODES[rm as usize].into()).into()))
+        }
+    }
+
+    fn branch_offset(binary: u32) -> i32
+    {
+        let imm_12 = ((binary as i32) >> 31) << 12;
+        let imm_11 = ((binary >> 7) & 0x1) << 11;
+        let imm_10_5 = ((binary >> 25) & 0x3F) << 5;
+        let imm_4_1 = ((binary >> 8) & 0xF) << 1;
+
+        imm_12 | (imm_11 | imm_10_5 | imm_4_1) as i32
+    }
+
+    fn jump_offset(binary: u32) -> i32
+    {
+        let imm_20 = ((binary as i32) >> 31) << 20;
+        let imm_19_12 = ((binary >> 12) & 0xFF) << 12;
+        let imm_11 = ((binary >> 20) & 0x1) << 11;
+        let imm_10_1 = ((binary >> 21) & 0x3FF) << 1;
+
+        imm_20 | (imm_19_12 | imm_11 | imm_10_1) as i32
+    }
+}
+
+#[macro_export]
+macro_rules! decode
+{
+    ($binary:expr) =>
+    {
+        match Decoder::new($binary)
+        {
+            Ok(decoder) => Ok((decoder.mnemonic, decoder.operands)),
+            Err(decoder_err) => Err(decoder_err)
+        }
+    }
+}
@@ -1,11 +1,11 @@
 use crate::{
-    lexer::*, 
+    lexer::*,
     arch::*
 };
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum EncoderErr
-{ 
+{
     Token(String),
     Mnemonic(String),
     Format(String),
@@ -18,426 +18,234 @@ pub struct Encoder
     pub binary: u32
 }
 
-impl Encoder 
+impl Encoder
 {
-    pub fn new(mnemonic: &String, operands: &Vec<Operand>) -> Result<Self, EncoderErr> 
+    pub fn new(mnemonic: &str, operands: &[Operand]) -> Result<Self, EncoderErr>
     {
-        if !RV_ISA.contains_key(mnemonic.as_str())
-        {
-            return Err(EncoderErr::Mnemonic(
-                format!(r#"Unsupported instruction mnemonic: "{}""#, mnemonic)
-            ))
-        }        
-        
-        let instruction = &RV_ISA[mnemonic.as_str()];
-        match instruction.opcode
-        {                    
-            Opcode::Op | Opcode::Op32 | Opcode::Op64 => 
-            {
-                Ok(Encoder{
-                    binary: Self::encode_op(instruction, operands)?
-                })
-            }
-            Opcode::OpFp =>
-            {
-                Ok(Encoder{
-                    binary: Self::encode_fp(instruction, operands)?
-                })
-            }
-            Opcode::Amo => 
-            {
-                Ok(Encoder{
-                    binary: Self::encode_amo(instruction, operands)?
-                })
-            }
-            Opcode::Jalr =>
-            {
-                Ok(Encoder{
-                    binary: Self::encode_jalr(instruction, operands)?
-                })            }
-            Opcode::Load | Opcode::LoadFp =>
-            {
-                Ok(Encoder{
-                    binary: Self::encode_load(instruction, operands)?
-                })     
-            }
-            Opcode::OpImm | Opcode::OpImm32 | Opcode::OpImm64 =>
-            {
-                Ok(Encoder{
-                    binary: Self::encode_op_imm(instruction, operands)?
-                })                 
-            }
-            Opcode::MiscMem =>
-            {
-                Ok(Encoder{
-                    binary: Self::encode_misc_mem(mnemonic, instruction, operands)?
-                })               
-            }
-            Opcode::System => 
-            {
-                Ok(Encoder{
-                    binary: Self::encode_system(instruction, operands)?
-                })     
-            }            
-            Opcode::Store | Opcode::StoreFp =>
-            {
-                Ok(Encoder{
-                    binary: Self::encode_store(instruction, operands)?
-                })     
-            }
-            Opcode::Branch => 
-            {
-                Ok(Encoder{
-                    binary: Self::encode_branch(instruction, operands)?
-                })                 
-            }
-            Opcode::Lui | Opcode::AuiPC =>
-            {
-                Ok(Encoder{
-                    binary: Self::encode_u_type(instruction, operands)?
-                })     
-            }
-            Opcode::Jal =>
-            {
-                Ok(Encoder{
-                    binary: Self::encode_jal(instruction, operands)?
-                })     
-            }
-            Opcode::MAdd | Opcode::MSub | 
-            Opcode::NmAdd | Opcode::NmSub =>
-            { // todo: add support for FMA/R4 opcode instructions.
-                Err(EncoderErr::Format(r#"Unsupported FMA/R4 opcode instruction."#.to_string()))     
-            }
-        }
+        Self::with_xlen(mnemonic, operands, Xlen::X64)
     }
 
-    fn encode_op(instruction: &Instruction, operands: &Vec<Operand>) -> Result<u32, EncoderErr> 
+    // Encodes for an `xlen` wide base ISA, RV32I takes 5-bit shift amounts where RV64I takes 6.
+    pub fn with_xlen(mnemonic: &str, operands: &[Operand], xlen: Xlen) -> Result<Self, EncoderErr>
     {
-        if let (Operand::RValue(RValue::Register(_, rd)), Operand::RValue(RValue::Register(_, rs1)), Operand::RValue(RValue::Register(_, rs2))) = (&operands[0], &operands[1], &operands[2]) 
-        {
-            let funct7 = instruction.funct7.unwrap() as u32;
-            let funct3 = instruction.funct3.unwrap() as u32;
-            let opcode = instruction.opcode as u32;
-        
-            return Ok((funct7 << 25) | (rs2 << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode)
-        } 
-        else 
-        {
-           return Err(EncoderErr::Operands(
-                r#"Invalid operands."#.to_string()
-            ))
-        }
-    }
+        let instruction = RV_ISA.get(mnemonic).ok_or_else(|| EncoderErr::Mnemonic(
+            format!(r#"Unsupported instruction mnemonic: "{}""#, mnemonic)
+        ))?;
 
-    fn encode_fp(instruction: &Instruction, operands: &Vec<Operand>) -> Result<u32, EncoderErr> 
-    { // Todo: Add support for float rounding modes.
-        const FRM: u32 = 0b000;
+        let operands = Self::fold_address(instruction, operands);
 
-        if let(Operand::RValue(RValue::Register(_, rd)), Operand::RValue(RValue::Register(_, rs1)), Operand::RValue(RValue::Register(_, rs2))) = 
-            (&operands[0], &operands[1], &operands[2]) 
-            {    
-            let funct5 = instruction.funct5.unwrap() as u32;
-            let opcode = instruction.opcode as u32;
-    
-            let float_rd = funct5 & 0b10000 != 0;
-            let float_rs1 = if funct5 & 0b1000 != 0 {
-                funct5 & 0b1000 != 0
-            } else {
-                !float_rd
-            };
-    
-            let rd = if float_rd { *rd } else { rd & 0b11111 };
-            let rs1 = if float_rs1 { *rs1 } else { rs1 & 0b11111 };
-            let rs2 = if let Some(rs2_val) = instruction.rs2 {
-                rs2_val as u32
-            } else {
-                rs2 & 0b11111
-            };
-    
-            Ok((funct5 << 25) | (rs2 << 20) | (rs1 << 15) | (FRM << 12) | (rd << 7) | opcode)
-        } 
-        else 
-        {
-            Err(EncoderErr::Operands(
-                r#"Invalid operands."#.to_string()
-            ))
-        }
-    } 
-    
-    fn encode_amo(instruction: &Instruction, operands: &Vec<Operand>) -> Result<u32, EncoderErr> 
-    {
-        if let(Operand::RValue(RValue::Register(_, rd)), Operand::RValue(RValue::Register(_, rs1)), Operand::RValue(RValue::Register(_, rs2))) = 
-            (&operands[0], &operands[1], &operands[2]) 
-        {
-            let funct5 = instruction.funct5.unwrap() as u32;
-            let funct3 = instruction.funct3.unwrap() as u32;
-            let opcode = instruction.opcode as u32;
-        
-            const AQ: u32 = 0;
-            const RL: u32 = 0;
+        // Trailing rounding modes are optional.
+        let required = instruction.operands.iter()
+            .filter(|operand_type| **operand_type != OperandType::RoundingMode)
+            .count();
 
-            Ok((funct5 << 27) | (AQ << 26) | (RL << 25) | (rs2 << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode)
-        } 
-        else 
+        if operands.len() < required || operands.len() > instruction.operands.len()
         {
-            Err(EncoderErr::Operands(
-                r#"Invalid operands."#.to_string()
-            ))
+            return Err(Self::invalid_operands(mnemonic, instruction))
         }
-    }
-  
-    fn encode_jalr(instruction: &Instruction, operands: &Vec<Operand>) -> Result<u32, EncoderErr> 
-    {
-        if let(Operand::RValue(RValue::Register(_, rd)), Operand::Address(RValue::Register(_, rs1), RValue::Immediate(offset))) = (&operands[0], &operands[1]) 
-        {
-            let funct3 = instruction.funct3.unwrap() as u32;
-            let opcode = instruction.opcode as u32;
-            let imm: u32 = (*offset as u32) & 0xFFF;
-            
-            Ok((imm << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode)
-        } 
-        else 
+
+        let mut binary = Self::encode_fixed(instruction);
+        let mut fences = 0;
+
+        for (operand_type, operand) in instruction.operands.iter().zip(&operands)
         {
-            Err(EncoderErr::Operands(
-                r#"Invalid operands."#.to_string()
-            ))
+            let operand_type = match operand_type
+            {
+                OperandType::Shamt(6) if xlen == Xlen::X32 => &OperandType::Shamt(5),
+                _ => operand_type
+            };
+            binary |= Self::encode_operand(mnemonic, instruction, operand_type, operand, &mut fences)?;
         }
-    }
 
-    fn encode_load(instruction: &Instruction, operands: &Vec<Operand>) -> Result<u32, EncoderErr> 
-    {
-        if let(Operand::RValue(RValue::Register(_, rd)), Operand::Address(RValue::Register(_, rs1), RValue::Immediate(offset))) = (&operands[0], &operands[1])
-        {
-            let funct3 = instruction.funct3.unwrap() as u32;
-            let opcode = instruction.opcode as u32;
-            let imm: u32 = (*offset as u32) & 0xFFF;
-            
-            Ok((imm << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode)
-        } 
-        else 
+        // Omitted rounding modes select the dynamic rounding mode (frm).
+        if operands.len() < instruction.operands.len()
         {
-            Err(EncoderErr::Operands(
-                r#"Invalid operands."#.to_string()
-            ))
+            binary |= 0b111 << 12;
         }
+
+        Ok(Encoder{ binary })
     }
 
-    fn encode_op_imm(instruction: &Instruction, operands: &Vec<Operand>) -> Result<u32, EncoderErr> 
+    // Accepts "rd, rs1, offset" where the signature expects "rd, offset(rs1)" (e.g. "jalr x0, x1, 0").
+    fn fold_address(instruction: &Instruction, operands: &[Operand]) -> Vec<Operand>
     {
-        if let(Operand::RValue(RValue::Register(_, rd)), Operand::RValue(RValue::Register(_, rs1)), Operand::RValue(RValue::Immediate(immediate))) = (&operands[0], &operands[1], &operands[2])
+        if let Some(index) = instruction.operands.iter().position(|operand_type| *operand_type == OperandType::Address)
         {
-            let funct3 = instruction.funct3.unwrap() as u32;
-            let opcode = instruction.opcode as u32;
-    
-            let imm: u32;
-
-            if let Some(shift_type) = &instruction.shift 
+            if operands.len() == instruction.operands.len() + 1
             {
-                let shamt_width: u32 = match instruction.opcode 
+                if let (Operand::RValue(base @ RValue::Register(..)), Operand::RValue(offset @ RValue::Immediate(_))) = (&operands[index], &operands[index + 1])
                 {
-                    Opcode::OpImm32 => 5,  
-                    Opcode::OpImm64 => 6,
-                    _ => 7
-                };
-    
-                if *immediate < 0 || *immediate >= (1 << shamt_width)
-                {
-                    return Err(EncoderErr::Operands(format!(
-                        r#"Invalid shamt field (out of range): "{}""#, immediate
-                    )));
+                    let mut folded = operands.to_vec();
+                    folded.splice(index..=index + 1, [Operand::Address(base.clone(), offset.clone())]);
+                    return folded
                 }
-                let imm_11_7: u32 = (0b0 << 4) | (*shift_type as u32);
-                let imm_6_0: u32 = (*immediate as u32) & ((1 << shamt_width) - 1);
-                imm = (imm_11_7 << 6) | imm_6_0;
-            } 
-            else 
-            {
-                imm = (*immediate as u32) & 0xFFF;
             }
-    
-            Ok((imm << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode)  
-        } 
-        else 
-        {
-            Err(EncoderErr::Operands(
-                r#"Invalid operands."#.to_string()
-            ))
         }
+        operands.to_vec()
     }
 
-    fn encode_misc_mem(mnemonic: &String, instruction: &Instruction, operands: &Vec<Operand>) -> Result<u32, EncoderErr> 
+    // Fields that are fixed by the RV_ISA entry itself.
+    fn encode_fixed(instruction: &Instruction) -> u32
     {
-        let mut imm: u32 = 0;
-        let mut rs1: u32 = 0;
-        let mut rd: u32 = 0;
-    
-        if mnemonic == "lq"
-        {
-            if let (Operand::RValue(RValue::Register(_, rd_val)), Operand::Address(RValue::Register(_, rs1_val), RValue::Immediate(offset))) = 
-                (&operands[0], &operands[1])
-            {
-                rd = *rd_val;
-                rs1 = *rs1_val;
-                imm = (*offset as u32) & 0xFFF;
-            } 
-            else 
-            {
-                return Err(EncoderErr::Operands(
-                    "Invalid operands.".to_string()
-                ))
-            }
-        } 
-        else if mnemonic == "fence"
+        let mut binary = instruction.opcode as u32;
+
+        binary |= instruction.funct3.map_or(0, |funct3| (funct3 as u32) << 12);
+        binary |= instruction.funct5.map_or(0, |funct5| (funct5 as u32) << 27);
+        binary |= instruction.float_format.clone().map_or(0, |fformat| (fformat as u32) << 25);
+        binary |= instruction.funct7.map_or(0, |funct7| (funct7 as u32) << 25);
+        binary |= instruction.funct12.map_or(0, |funct12| (funct12 as u32) << 20);
+        binary |= instruction.rs2.map_or(0, |rs2| (rs2 as u32) << 20);
+
+        // Arithmetic shifts are distinguished by bit 30.
+        if let Some(ShiftType::SRA | ShiftType::SRAW | ShiftType::SRAD) = instruction.shift
         {
-            if let (Operand::RValue(RValue::Immediate(pred)), Operand::RValue(RValue::Immediate(succ))) = (&operands[0], &operands[1])
-            {
-                imm = ((*pred as u32) << 4) | (*succ as u32);
-            }
+            binary |= 1 << 30;
         }
-    
-        let funct3 = instruction.funct3.unwrap() as u32;
-        let opcode = instruction.opcode as u32;
-        Ok((imm << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode)
+        binary
     }
 
-    fn encode_system(instruction: &Instruction, operands: &Vec<Operand>) -> Result<u32, EncoderErr> 
+    fn encode_operand(mnemonic: &str, instruction: &Instruction, operand_type: &OperandType, operand: &Operand, fences: &mut u32) -> Result<u32, EncoderErr>
     {
-        let mut rs1: u32 = 0;
-        let mut rd: u32 = 0;
-        let mut imm: u32 = instruction.funct12.unwrap() as u32;
+        let register = |file: &RegisterFile, shift: u32| -> Result<u32, EncoderErr>
+        {
+            match operand
+            {
+                Operand::RValue(RValue::Register(prefix, index)) if Self::is_register(file, *prefix, *index) => Ok(index << shift),
+                _ => Err(Self::invalid_operands(mnemonic, instruction))
+            }
+        };
 
-        if instruction.isa == ISA::Zicsr 
+        match operand_type
         {
-            if let (Operand::RValue(RValue::Register(_, dest)), Operand::RValue(RValue::Immediate(csr)), Ok(src)) = (
-                &operands[0], &operands[1],
-                if instruction.funct3.unwrap() & 0b1000 == 0 
-                { 
-                    match operands[2]
+            OperandType::Rd(file)  => register(file, 7),
+            OperandType::Rs1(file) => register(file, 15),
+            OperandType::Rs2(file) => register(file, 20),
+            OperandType::Rs3(file) => register(file, 27),
+            OperandType::Address =>
+            {
+                match operand
+                {
+                    Operand::Address(RValue::Register(prefix, rs1), RValue::Immediate(offset)) if Self::is_register(&RegisterFile::Int, *prefix, *rs1) =>
                     {
-                        Operand::RValue(RValue::Register(_, src)) => Ok::<u32, EncoderErr>(src), // specify the types here
-                        _ => return Err(EncoderErr::Operands(
-                            "Invalid operands.".to_string()
-                        ))
-                    }
-                } 
-                else 
+                        let imm = Self::checked(mnemonic, &OperandType::Immediate(12, true), *offset)?;
+
+                        Ok(match instruction.format
+                        {
+                            Format::SType => (((imm >> 5) & 0x7F) << 25) | ((imm & 0x1F) << 7),
+                            _ => (imm & 0xFFF) << 20
+                        } | (rs1 << 15))
+                    },
+                    _ => Err(Self::invalid_operands(mnemonic, instruction))
+                }
+            },
+            OperandType::RoundingMode =>
+            {
+                match operand
                 {
-                    match operands[2]
+                    Operand::RValue(RValue::Identifier(name)) =>
                     {
-                        Operand::RValue(RValue::Immediate(src)) => Ok::<u32, EncoderErr>(src as u32), // specify the types here
-                        _ => return Err(EncoderErr::Operands(
-                            "Invalid operands.".to_string()
-                        ))
-                    }
+                        ROUNDING_MODES.iter().position(|mode| !mode.is_empty() && mode == name)
+                            .map(|rm| (rm as u32) << 12)
+                            .ok_or_else(|| EncoderErr::FloatRounding(
+                                format!(r#"Unsupported floating point rounding mode: "{}""#, name)
+                            ))
+                    },
+                    _ => Err(Self::invalid_operands(mnemonic, instruction))
                 }
-            ) {
-                rd = *dest;
-                imm = *csr as u32;
-                rs1 = src.into();
-            }            
-        }
+            },
+            _ =>
+            {
+                let value = match operand
+                {
+                    Operand::RValue(RValue::Immediate(value)) => Self::checked(mnemonic, operand_type, *value)?,
+                    _ => return Err(Self::invalid_operands(mnemonic, instruction))
+                };
 
-        let funct3 = instruction.funct3.unwrap() as u32;
-        let opcode = instruction.opcode as u32;
-        Ok((imm << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode)
+                Ok(match operand_type
+                {
+                    OperandType::Immediate(..) if instruction.format == Format::UType => (value & 0xFFFFF) << 12,
+                    OperandType::Immediate(..) => (value & 0xFFF) << 20,
+                    OperandType::Shamt(_) | OperandType::Csr => value << 20,
+                    OperandType::CsrImmediate => value << 15,
+                    OperandType::Fence =>
+                    { // The predecessor set precedes the successor set.
+                        *fences += 1;
+                        if *fences == 1 { value << 24 } else { value << 20 }
+                    },
+                    OperandType::Offset(_) if instruction.format == Format::SBType =>
+                    {
+                        let imm_12 = (value >> 12) & 0x1;
+                        let imm_11 = (value >> 11) & 0x1;
+                        let imm_10_5 = (value >> 5) & 0x3F;
+                        let imm_4_1 = (value >> 1) & 0xF;
+
+                        (imm_12 << 31) | (imm_10_5 << 25) | (imm_4_1 << 8) | (imm_11 << 7)
+                    },
+                    _ =>
+                    {
+                        let imm_20 = (value >> 20) & 0x1;
+                        let imm_19_12 = (value >> 12) & 0xFF;
+                        let imm_11 = (value >> 11) & 0x1;
+                        let imm_10_1 = (value >> 1) & 0x3FF;
+
+                        (imm_20 << 31) | (imm_10_1 << 21) | (imm_11 << 20) | (imm_19_12 << 12)
+                    }
+                })
+            }
+        }
     }
 
-    fn encode_store(instruction: &Instruction, operands: &Vec<Operand>) -> Result<u32, EncoderErr> 
+    fn is_register(file: &RegisterFile, prefix: char, index: u32) -> bool
     {
-        if let(Operand::RValue(RValue::Register(_, rs2)), Operand::Address(RValue::Register(_, rs1), RValue::Immediate(offset))) = 
-            (&operands[0], &operands[1])
+        index < 32 && match file
         {
-            let funct3 = instruction.funct3.unwrap() as u32;
-            let opcode = instruction.opcode as u32;
-            let imm: u32 = (*offset as u32) & 0xFFF;
-            let imm_11_5 = (imm >> 5) & 0x7F;
-            let imm_4_0 = imm & 0x1F;
-
-            Ok((imm_11_5 << 25) | (rs2 << 20) | (rs1 << 15) | (funct3 << 12) | (imm_4_0 << 7) | opcode)
-        } 
-        else 
-        {
-            Err(EncoderErr::Operands(
-                "Invalid operands.".to_string()
-            ))
+            RegisterFile::Int   => prefix == 'x',
+            RegisterFile::Float => prefix == 'f'
         }
     }
 
-    fn encode_branch(instruction: &Instruction, operands: &Vec<Operand>) -> Result<u32, EncoderErr> 
+    // Range checks an immediate against it's operand type.
+    fn checked(mnemonic: &str, operand_type: &OperandType, value: i32) -> Result<u32, EncoderErr>
     {
-        if let(Operand::RValue(RValue::Register(_, rs1)), Operand::RValue(RValue::Register(_, rs2)), Operand::RValue(RValue::Immediate(imm))) = 
-            (&operands[0], &operands[1], &operands[2])
-        {
-            let funct3 = instruction.funct3.unwrap() as u32;
-            let opcode = instruction.opcode as u32;
-            let imm_val = *imm as u32;
+        let (min, max) = operand_type.range().unwrap_or((i32::MIN as i64, i32::MAX as i64));
 
-            let imm_12 = (imm_val >> 12) & 0x1;
-            let imm_11 = (imm_val >> 11) & 0x1;
-            let imm_10_5 = (imm_val >> 5) & 0x3F;
-            let imm_4_1 = imm_val & 0x1F;
-
-            Ok((imm_12 << 31) | (imm_10_5 << 25) | (rs2 << 20) | (rs1 << 15) | (funct3 << 12) | (imm_4_1 << 7) | (imm_11 << 7) | opcode)
-        } 
-        else 
+        if (value as i64) < min || (value as i64) > max
         {
-            Err(EncoderErr::Operands(
-                "Invalid operands.".to_string()
+            return Err(EncoderErr::Operands(
+                format!(r#"Operand "{}" of "{}" is out of range for "{}"."#, value, mnemonic, operand_type)
             ))
         }
-    }
 
-    fn encode_u_type(instruction: &Instruction, operands: &Vec<Operand>) -> Result<u32, EncoderErr> 
-    {
-        if let(Operand::RValue(RValue::Register(_, rd)), Operand::RValue(RValue::Immediate(imm))) = 
-            (&operands[0], &operands[1])
+        if matches!(operand_type, OperandType::Offset(_)) && value % 2 != 0
         {
-            let opcode = instruction.opcode as u32;
-            let imm_val = ((*imm as u32) & 0xFFFFF) << 12;
-
-            Ok(imm_val | (rd << 7) | opcode)
-        } 
-        else 
-        {
-            Err(EncoderErr::Operands(
-                "Invalid operands.".to_string()
+            return Err(EncoderErr::Operands(
+                format!(r#"Operand "{}" of "{}" must be a multiple of 2."#, value, mnemonic)
             ))
         }
+        Ok(value as u32)
     }
 
-    fn encode_jal(instruction: &Instruction, operands: &Vec<Operand>) -> Result<u32, EncoderErr> 
+    fn invalid_operands(mnemonic: &str, instruction: &Instruction) -> EncoderErr
     {
-        if let(Operand::RValue(RValue::Register(_, rd)), Operand::RValue(RValue::Immediate(imm))) =
-            (&operands[0], &operands[1])
-        {
-            let opcode = instruction.opcode as u32;
-            let imm_val = *imm as u32;
-
-            let imm_20 = (imm_val >> 20) & 0x1;
-            let imm_19_12 = (imm_val >> 12) & 0xFF;
-            let imm_11 = (imm_val >> 11) & 0x1;
-            let imm_10_1 = (imm_val >> 1) & 0x3FF;
-
-            Ok((imm_20 << 31) | (imm_19_12 << 20) | (imm_11 << 20) | (imm_10_1 << 1) | (rd << 7) | opcode)
-        } 
-        else 
-        {
-            Err(EncoderErr::Operands(
-                "Invalid operands.".to_string()
-            ))
-        }
+        EncoderErr::Operands(
+            format!(r#"Invalid operands, expected: "{} {}""#, mnemonic, instruction.syntax())
+        )
     }
 }
 
 #[macro_export]
 macro_rules! encode
 {
-    ($mnemonic:expr, $operands:expr) => 
+    ($mnemonic:expr, $operands:expr) =>
+    {
+        $crate::encode!($mnemonic, $operands, $crate::arch::Xlen::X64)
+    };
+    ($mnemonic:expr, $operands:expr, $xlen:expr) =>
     {
-        match Encoder::new($mnemonic, $operands)
+        match Encoder::with_xlen($mnemonic, $operands, $xlen)
         {
             Ok(encoder) => Ok(encoder.binary.to_le_bytes()),
             Err(encoder_err) => Err(encoder_err)
         }
     }
-}
\ No newline at end of file
+}
@@ -1,2 +1,2 @@
 pub mod enc;
-// pub mod dec;
\ No newline at end of file
+pub mod dec;
\ No newline at end of file
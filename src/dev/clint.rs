@@ -0,0 +1,140 @@
+use std::time::Instant;
+
+use crate::{
+    mem::*, mmu::{MMUErr, PhysicalMemory}
+};
+use super::Device;
+
+// Conventional base address and size of the CLINT block.
+pub const CLINT_BASE: Address = 0x0200_0000;
+pub const CLINT_SIZE: usize   = 0x1_0000;
+
+// Register offsets, msip and mtimecmp are indexed by hart.
+pub const CLINT_MSIP: Address     = 0x0000;
+pub const CLINT_MTIMECMP: Address = 0x4000;
+pub const CLINT_MTIME: Address    = 0xBFF8;
+
+// Interrupt pending bits of mip driven by the CLINT.
+const MSIP: u64 = 1 << 3;
+const MTIP: u64 = 1 << 7;
+
+// Source of mtime increments.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Timebase
+{
+    Instructions(u64 /* Ticks per increment */),
+    Host(u64 /* Frequency in Hz */)
+}
+
+// Core local interruptor, raising machine software interrupts through msip and timer interrupts once mtime reaches mtimecmp.
+pub struct Clint
+{
+    pub msip: Vec<bool>,
+    pub mtimecmp: Vec<u64>,
+    pub mtime: u64,
+    pub timebase: Timebase,
+    ticks: u64,
+    origin: (Instant, u64 /* mtime at the instant */)
+}
+
+impl Clint
+{
+    pub fn new(harts: usize, timebase: Timebase) -> Self
+    {
+        Clint{ msip: vec![false; harts], mtimecmp: vec![u64::MAX; harts], mtime: 0, timebase, ticks: 0, origin: (Instant::now(), 0) }
+    }
+
+    // Sets mtime, host time keeps counting from the new value.
+    fn set_mtime(&mut self, mtime: u64)
+    {
+        self.mtime = mtime;
+        self.origin = (Instant::now(), mtime);
+    }
+
+    fn bus_error(offset: Address) -> MMUErr
+    {
+        MMUErr::BusError(format!("No CLINT register at offset: 0x{:x}", offset))
+    }
+}
+
+// Bits of a naturally aligned `width` byte access at `offset` into a 64-bit register.
+fn field(offset: Address, width: usize) -> (u32, u64)
+{
+    ((offset & 0b111) as u32 * 8, u64::MAX >> (64 - width * 8))
+}
+
+impl Device for Clint
+{
+    fn read(&mut self, offset: Address, width: usize) -> Result<u64, MMUErr>
+    {
+        let harts = self.msip.len();
+        let (shift, mask) = field(offset, width);
+
+        match offset
+        {
+            CLINT_MSIP..CLINT_MTIMECMP if (offset - CLINT_MSIP) / 4 < harts && width <= 4 =>
+                Ok(self.msip[(offset - CLINT_MSIP) / 4] as u64 & mask),
+            CLINT_MTIMECMP..CLINT_MTIME if (offset - CLINT_MTIMECMP) / 8 < harts =>
+                Ok((self.mtimecmp[(offset - CLINT_MTIMECMP) / 8] >> shift) & mask),
+            CLINT_MTIME.. if offset < CLINT_MTIME + 8 => Ok((self.mtime >> shift) & mask),
+            _ => Err(Self::bus_error(offset))
+        }
+    }
+
+    // Partial writes replace the addressed bytes, RV32 harts update 64-bit registers one half at a time.
+    fn write(&mut self, offset: Address, width: usize, value: u64) -> Result<(), MMUErr>
+    {
+        let harts = self.msip.len();
+        let (shift, mask) = field(offset, width);
+        let merge = |register: u64| (register & !(mask << shift)) | ((value & mask) << shift);
+
+        match offset
+        {
+            CLINT_MSIP..CLINT_MTIMECMP if (offset - CLINT_MSIP) / 4 < harts && width <= 4 =>
+                self.msip[(offset - CLINT_MSIP) / 4] = value & 1 != 0,
+            CLINT_MTIMECMP..CLINT_MTIME if (offset - CLINT_MTIMECMP) / 8 < harts =>
+            {
+                let hart = (offset - CLINT_MTIMECMP) / 8;
+                self.mtimecmp[hart] = merge(self.mtimecmp[hart]);
+            },
+            CLINT_MTIME.. if offset < CLINT_MTIME + 8 => self.set_mtime(merge(self.mtime)),
+            _ => return Err(Self::bus_error(offset))
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, _memory: &mut PhysicalMemory)
+    {
+        match self.timebase
+        {
+            Timebase::Instructions(ticks) =>
+            {
+                self.ticks += 1;
+                if self.ticks >= ticks
+                {
+                    self.ticks = 0;
+                    self.mtime = self.mtime.wrapping_add(1);
+                }
+            },
+            Timebase::Host(frequency) =>
+            {
+                let elapsed = self.origin.0.elapsed().as_nanos() * frequency as u128 / 1_000_000_000;
+                self.mtime = self.origin.1.wrapping_add(elapsed as u64);
+            }
+        }
+    }
+
+    fn interrupts(&self, hart: usize) -> u64
+    {
+        match (self.msip.get(hart), self.mtimecmp.get(hart))
+        {
+            (Some(&msip), Some(&mtimecmp)) => (msip as u64 * MSIP) | ((self.mtime >= mtimecmp) as u64 * MTIP),
+            _ => 0
+        }
+    }
+
+    fn time(&self) -> Option<u64>
+    {
+        Some(self.mtime)
+    }
+}
@@ -0,0 +1,139 @@
+use std::io::{self, Write};
+
+use crate::{
+    mem::*, mmu::{MMUErr, PhysicalMemory}
+};
+use super::Device;
+
+// Devices and commands of a tohost request, in its upper 16 bits.
+pub const HTIF_DEVICE_SYSCALL: u64 = 0;
+pub const HTIF_DEVICE_CONSOLE: u64 = 1;
+pub const HTIF_CONSOLE_PUTCHAR: u64 = 1;
+
+// Proxied system calls of the syscall device.
+const SYS_WRITE: u64 = 64;
+const SYS_EXIT: u64  = 93;
+const ENOSYS: i64    = 38;
+
+// Host-target interface as used by riscv-tests and riscv-arch-test, mapped over the 8-byte "tohost" variable. A request
+// is issued once its upper word has been written and served on the next tick, replies are stored at "fromhost".
+pub struct Htif
+{
+    pub output: Box<dyn Write>,
+    pub fromhost: Option<Address>,
+    pub exit: Option<u64>, // Exit code of the target, zero passes and otherwise it numbers the failed test.
+    tohost: u64,
+    request: Option<u64>
+}
+
+impl Htif
+{
+    pub fn new(output: Box<dyn Write>, fromhost: Option<Address>) -> Self
+    {
+        Htif{ output, fromhost, exit: None, tohost: 0, request: None }
+    }
+
+    // Serves a system call proxied through the eight 64-bit words at `address`, the result replaces the number.
+    fn syscall(&mut self, memory: &mut PhysicalMemory, address: Address)
+    {
+        let Some(words) = memory.get(address..address + 64) else { return };
+        let word = |index: usize| u64::from_le_bytes(words[index * 8..index * 8 + 8].try_into().unwrap());
+        let (number, arguments) = (word(0), [word(1), word(2), word(3)]);
+
+        let result = match number
+        {
+            SYS_EXIT =>
+            {
+                self.exit = Some(arguments[0]);
+                return
+            },
+            SYS_WRITE if arguments[0] == 1 || arguments[0] == 2 =>
+            {
+                let (start, length) = (arguments[1] as usize, arguments[2] as usize);
+                match memory.get(start..start.saturating_add(length)).map(|bytes| self.output.write_all(bytes))
+                {
+                    Some(Ok(())) => length as i64,
+                    _ => -ENOSYS
+                }
+            },
+            _ => -ENOSYS
+        };
+
+        if let Some(number) = memory.get_mut(address..address + 8)
+        {
+            number.copy_from_slice(&result.to_le_bytes());
+        }
+        self.reply(memory, 1);
+    }
+
+    fn reply(&mut self, memory: &mut PhysicalMemory, value: u64)
+    {
+        if let Some(reply) = self.fromhost.and_then(|fromhost| memory.get_mut(fromhost..fromhost.checked_add(8)?))
+        {
+            reply.copy_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+impl Device for Htif
+{
+    fn read(&mut self, offset: Address, width: usize) -> Result<u64, MMUErr>
+    {
+        Ok((self.tohost >> (offset * 8)) & (u64::MAX >> (64 - width * 8)))
+    }
+
+    fn write(&mut self, offset: Address, width: usize, value: u64) -> Result<(), MMUErr>
+    {
+        if offset + width > 8
+        {
+            return Err(MMUErr::BusError(format!("No HTIF register at offset: 0x{:x}", offset)))
+        }
+
+        let mask = (u64::MAX >> (64 - width * 8)) << (offset * 8);
+        self.tohost = (self.tohost & !mask) | ((value << (offset * 8)) & mask);
+
+        // RV32 targets write the lower word first.
+        if offset + width == 8 && self.tohost != 0 && self.exit.is_none()
+        {
+            self.request = Some(self.tohost);
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, memory: &mut PhysicalMemory)
+    {
+        let Some(request) = self.request.take() else { return };
+        self.tohost = 0;
+
+        let (device, command, payload) = (request >> 56, (request >> 48) & 0xFF, request & 0xFFFF_FFFF_FFFF);
+        match (device, command)
+        {
+            (HTIF_DEVICE_SYSCALL, _) if payload & 1 == 1 => self.exit = Some(payload >> 1),
+            (HTIF_DEVICE_SYSCALL, _) => self.syscall(memory, payload as Address),
+            (HTIF_DEVICE_CONSOLE, HTIF_CONSOLE_PUTCHAR) =>
+            {
+                let _ = self.output.write_all(&[payload as u8]).and_then(|_| self.output.flush());
+                self.reply(memory, request & !0xFFFF_FFFF_FFFF);
+            },
+            _ => {}
+        }
+    }
+}
+
+// Writes `memory[start..end]` as hexadecimal values of `granularity` bytes, one per line with the lowest address first.
+pub fn write_signature(memory: &[u8], start: Address, end: Address, granularity: usize, output: &mut dyn Write) -> io::Result<()>
+{
+    let signature = memory.get(start..end)
+        .filter(|_| matches!(granularity, 1 | 2 | 4 | 8))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid signature region: 0x{:x}..0x{:x}", start, end)))?;
+
+    for chunk in signature.chunks(granularity)
+    {
+        for byte in chunk.iter().rev()
+        {
+            write!(output, "{:02x}", byte)?;
+        }
+        writeln!(output)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use crate::{
+    mem::*, mmu::{MMUErr, PhysicalMemory}
+};
+
+// Core local interruptor (timer and software interrupts).
+pub mod clint;
+
+// Platform-level interrupt controller (external interrupts).
+pub mod plic;
+
+// NS16550A compatible serial console.
+pub mod uart;
+
+// virtio-mmio block device.
+pub mod virtio;
+
+// Host-target interface of test suites.
+pub mod htif;
+
+// Memory-mapped peripheral. Accesses carry the offset into the mapped range and their width in bytes (1, 2, 4 or 8),
+// values are zero-extended and bus errors are reported to the hart as access faults.
+pub trait Device
+{
+    fn read(&mut self, offset: Address, width: usize) -> Result<u64, MMUErr>;
+
+    fn write(&mut self, offset: Address, width: usize, value: u64) -> Result<(), MMUErr>;
+
+    // Advances the device by one machine step, with direct access to physical memory for DMA.
+    fn tick(&mut self, _memory: &mut PhysicalMemory) {}
+
+    // Interrupt pending bits, in the layout of mip, the device raises for `hart`.
+    fn interrupts(&self, _hart: usize) -> u64
+    {
+        0
+    }
+
+    // Current value of the platform timer when the device provides it.
+    fn time(&self) -> Option<u64>
+    {
+        None
+    }
+}
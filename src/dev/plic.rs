@@ -0,0 +1,278 @@
+use std::{
+    cell::RefCell, rc::Rc
+};
+
+use crate::{
+    mem::*, mmu::MMUErr
+};
+use super::Device;
+
+// Conventional base address and size of the PLIC block.
+pub const PLIC_BASE: Address = 0x0C00_0000;
+pub const PLIC_SIZE: usize   = 0x0400_0000;
+
+// Register offsets, enables and the threshold/claim pairs are laid out per context.
+pub const PLIC_PRIORITY: Address  = 0x00_0000;
+pub const PLIC_PENDING: Address   = 0x00_1000;
+pub const PLIC_ENABLE: Address    = 0x00_2000;
+pub const PLIC_CONTEXT: Address   = 0x20_0000;
+const ENABLE_STRIDE: Address      = 0x80;
+const CONTEXT_STRIDE: Address     = 0x1000;
+
+// Sources, including the reserved source 0, and implemented priority levels.
+pub const PLIC_SOURCES: usize = 1024;
+const BANKS: usize = PLIC_SOURCES / 32;
+const PRIORITY_MASK: u32 = 0b111;
+
+// External interrupt pending bits of mip driven by the PLIC.
+const SEIP: u64 = 1 << 9;
+const MEIP: u64 = 1 << 11;
+
+// Platform-level interrupt controller with level-triggered gateways. Each hart has a machine context (2 * hart)
+// routed to MEIP and a supervisor context (2 * hart + 1) routed to SEIP. Pending and enable bits are kept as
+// banks of 32 sources, and the best source of each context is cached until any of its inputs change.
+pub struct Plic
+{
+    pub levels: Vec<bool>,
+    pub claimed: Vec<bool>,
+    priority: Vec<u32>,
+    pending: Vec<u32>,
+    enable: Vec<Vec<u32>>,
+    threshold: Vec<u32>,
+    best: Vec<Option<usize>>
+}
+
+// Interrupt line of a device, wired to one source of the PLIC.
+#[derive(Clone)]
+pub struct InterruptLine
+{
+    pub plic: Rc<RefCell<Plic>>,
+    pub source: usize
+}
+
+impl InterruptLine
+{
+    pub fn set(&self, level: bool)
+    {
+        self.plic.borrow_mut().set(self.source, level);
+    }
+}
+
+impl Plic
+{
+    pub fn new(harts: usize) -> Self
+    {
+        Plic{
+            levels: vec![false; PLIC_SOURCES],
+            claimed: vec![false; PLIC_SOURCES],
+            priority: vec![0; PLIC_SOURCES],
+            pending: vec![0; BANKS],
+            enable: vec![vec![0; BANKS]; harts * 2],
+            threshold: vec![0; harts * 2],
+            best: vec![None; harts * 2]
+        }
+    }
+
+    // Sets the priority of `source`, keeping the implemented bits. Source 0 has no priority.
+    pub fn set_priority(&mut self, source: usize, priority: u32)
+    {
+        if source != 0 && source < PLIC_SOURCES
+        {
+            self.priority[source] = priority & PRIORITY_MASK;
+            self.update_all();
+        }
+    }
+
+    // Enables or disables `source` for `context`, source 0 can't be enabled.
+    pub fn set_enable(&mut self, context: usize, source: usize, enable: bool)
+    {
+        if source != 0 && source < PLIC_SOURCES && context < self.enable.len()
+        {
+            let bits = &mut self.enable[context][source / 32];
+            *bits = *bits & !(1 << (source % 32)) | (enable as u32) << (source % 32);
+            self.update(context);
+        }
+    }
+
+    // Line raising `source`, source 0 is reserved and never interrupts.
+    pub fn line(plic: &Rc<RefCell<Self>>, source: usize) -> InterruptLine
+    {
+        InterruptLine{ plic: plic.clone(), source }
+    }
+
+    // Drives the level of `source`, the gateway forwards a new request unless one is still being serviced.
+    pub fn set(&mut self, source: usize, level: bool)
+    {
+        if source == 0 || source >= PLIC_SOURCES
+        {
+            return
+        }
+
+        self.levels[source] = level;
+        if level && !self.claimed[source]
+        {
+            self.set_pending(source, true);
+        }
+    }
+
+    fn is_set(bits: &[u32], source: usize) -> bool
+    {
+        bits[source / 32] >> (source % 32) & 1 == 1
+    }
+
+    // Changes the pending bit of `source`, pending bits are shared so every context is updated.
+    fn set_pending(&mut self, source: usize, pending: bool)
+    {
+        if Self::is_set(&self.pending, source) != pending
+        {
+            self.pending[source / 32] ^= 1 << (source % 32);
+            self.update_all();
+        }
+    }
+
+    // Recomputes the pending and enabled source of `context` with the highest priority above its threshold,
+    // ties go to the lowest id. Only the set bits of each bank are visited.
+    fn update(&mut self, context: usize)
+    {
+        let mut best: Option<usize> = None;
+        for (bank, (&pending, &enable)) in self.pending.iter().zip(&self.enable[context]).enumerate()
+        {
+            let mut bits = pending & enable;
+            while bits != 0
+            {
+                let source = bank * 32 + bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+
+                if self.priority[source] > self.threshold[context] && best.is_none_or(|best| self.priority[best] < self.priority[source])
+                {
+                    best = Some(source);
+                }
+            }
+        }
+        self.best[context] = best;
+    }
+
+    fn update_all(&mut self)
+    {
+        for context in 0..self.best.len()
+        {
+            self.update(context);
+        }
+    }
+
+    // Claims the best interrupt of `context`, returning 0 when none is pending.
+    fn claim(&mut self, context: usize) -> u64
+    {
+        match self.best[context]
+        {
+            Some(source) =>
+            {
+                self.claimed[source] = true;
+                self.set_pending(source, false);
+                source as u64
+            },
+            None => 0
+        }
+    }
+
+    // Completes `source`, completions of sources not enabled for `context` are ignored.
+    fn complete(&mut self, context: usize, source: usize)
+    {
+        if source < PLIC_SOURCES && Self::is_set(&self.enable[context], source) && self.claimed[source]
+        {
+            self.claimed[source] = false;
+            self.set_pending(source, self.levels[source]);
+        }
+    }
+
+    fn bus_error(offset: Address) -> MMUErr
+    {
+        MMUErr::BusError(format!("No PLIC register at offset: 0x{:x}", offset))
+    }
+
+    // Context and bank of 32 sources addressed by an enable register.
+    fn enable_bank(&self, offset: Address) -> Option<(usize, usize)>
+    {
+        let (context, bank) = ((offset - PLIC_ENABLE) / ENABLE_STRIDE, (offset - PLIC_ENABLE) % ENABLE_STRIDE / 4);
+        (context < self.threshold.len()).then_some((context, bank))
+    }
+}
+
+impl Device for Plic
+{
+    // All registers are 32 bits wide.
+    fn read(&mut self, offset: Address, width: usize) -> Result<u64, MMUErr>
+    {
+        if width != 4
+        {
+            return Err(Self::bus_error(offset))
+        }
+
+        let contexts = self.threshold.len();
+        match offset
+        {
+            PLIC_PRIORITY..PLIC_PENDING if offset / 4 < PLIC_SOURCES => Ok(self.priority[offset / 4] as u64),
+            PLIC_PENDING..PLIC_ENABLE if (offset - PLIC_PENDING) / 4 < BANKS => Ok(self.pending[(offset - PLIC_PENDING) / 4] as u64),
+            PLIC_ENABLE..PLIC_CONTEXT =>
+            {
+                let (context, bank) = self.enable_bank(offset).ok_or_else(|| Self::bus_error(offset))?;
+                Ok(self.enable[context][bank] as u64)
+            },
+            PLIC_CONTEXT.. if (offset - PLIC_CONTEXT) / CONTEXT_STRIDE < contexts =>
+            {
+                let context = (offset - PLIC_CONTEXT) / CONTEXT_STRIDE;
+                match offset % CONTEXT_STRIDE
+                {
+                    0 => Ok(self.threshold[context] as u64),
+                    4 => Ok(self.claim(context)),
+                    _ => Err(Self::bus_error(offset))
+                }
+            },
+            _ => Err(Self::bus_error(offset))
+        }
+    }
+
+    // Priorities and thresholds keep their implemented bits, pending bits are read-only and source 0 can't be enabled.
+    fn write(&mut self, offset: Address, width: usize, value: u64) -> Result<(), MMUErr>
+    {
+        if width != 4
+        {
+            return Err(Self::bus_error(offset))
+        }
+
+        let contexts = self.threshold.len();
+        match offset
+        {
+            PLIC_PRIORITY..PLIC_PENDING if offset / 4 < PLIC_SOURCES => self.set_priority(offset / 4, value as u32),
+            PLIC_PENDING..PLIC_ENABLE if (offset - PLIC_PENDING) / 4 < BANKS => (),
+            PLIC_ENABLE..PLIC_CONTEXT =>
+            {
+                let (context, bank) = self.enable_bank(offset).ok_or_else(|| Self::bus_error(offset))?;
+                self.enable[context][bank] = value as u32 & if bank == 0 { !1 } else { !0 };
+                self.update(context);
+            },
+            PLIC_CONTEXT.. if (offset - PLIC_CONTEXT) / CONTEXT_STRIDE < contexts =>
+            {
+                let context = (offset - PLIC_CONTEXT) / CONTEXT_STRIDE;
+                match offset % CONTEXT_STRIDE
+                {
+                    0 =>
+                    {
+                        self.threshold[context] = value as u32 & PRIORITY_MASK;
+                        self.update(context);
+                    },
+                    4 => self.complete(context, value as usize),
+                    _ => return Err(Self::bus_error(offset))
+                }
+            },
+            _ => return Err(Self::bus_error(offset))
+        }
+        Ok(())
+    }
+
+    fn interrupts(&self, hart: usize) -> u64
+    {
+        let active = |context: usize| self.best.get(context).is_some_and(Option::is_some);
+        (active(2 * hart) as u64 * MEIP) | (active(2 * hart + 1) as u64 * SEIP)
+    }
+}
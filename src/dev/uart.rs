@@ -0,0 +1,244 @@
+use std::{
+    collections::VecDeque, io::{Read, Write}, sync::mpsc::{self, Receiver}, thread
+};
+
+use crate::{
+    mem::*, mmu::{MMUErr, PhysicalMemory}
+};
+use super::{
+    Device, plic::InterruptLine
+};
+
+// Conventional base address and size of the UART.
+pub const UART_BASE: Address = 0x1000_0000;
+pub const UART_SIZE: usize   = 0x100;
+
+// Register offsets, the divisor latch replaces RBR/THR and IER while LCR.DLAB is set.
+pub const UART_RBR: Address = 0; // Receive buffer (read), transmit holding (write).
+pub const UART_IER: Address = 1;
+pub const UART_IIR: Address = 2; // Interrupt identification (read), FIFO control (write).
+pub const UART_LCR: Address = 3;
+pub const UART_MCR: Address = 4;
+pub const UART_LSR: Address = 5;
+pub const UART_MSR: Address = 6;
+pub const UART_SCR: Address = 7;
+
+// Register fields.
+const IER_RDA: u8    = 1 << 0; // Received data available.
+const IER_THRE: u8   = 1 << 1; // Transmit holding register empty.
+const IIR_NONE: u8   = 0x01;
+const IIR_THRE: u8   = 0x02;
+const IIR_RDA: u8    = 0x04;
+const IIR_FIFO: u8   = 0xC0;
+const FCR_ENABLE: u8 = 1 << 0;
+const FCR_CLEAR: u8  = 1 << 1;
+const LCR_DLAB: u8   = 1 << 7;
+const MCR_LOOP: u8   = 1 << 4;
+const LSR_DR: u8     = 1 << 0;
+const LSR_THRE: u8   = 1 << 5;
+const LSR_TEMT: u8   = 1 << 6;
+
+// NS16550A compatible UART with byte-wide registers. Transmitted bytes are written to `output` immediately,
+// received bytes are queued from a scripted buffer or a host reader such as stdin.
+pub struct Uart
+{
+    pub output: Box<dyn Write>,
+    pub input: Option<Receiver<u8>>,
+    pub buffer: VecDeque<u8>,
+    pub interrupt: Option<InterruptLine>,
+    pub ier: u8,
+    pub fcr: u8,
+    pub lcr: u8,
+    pub mcr: u8,
+    pub scr: u8,
+    pub divisor: u16,
+    thre_pending: bool
+}
+
+impl Uart
+{
+    pub fn new(output: Box<dyn Write>) -> Self
+    {
+        Uart{ output, input: None, buffer: VecDeque::new(), interrupt: None, ier: 0, fcr: 0, lcr: 0, mcr: 0, scr: 0, divisor: 0, thre_pending: false }
+    }
+
+    // Queues `bytes` as if they had been received.
+    pub fn with_input(mut self, bytes: &[u8]) -> Self
+    {
+        self.buffer.extend(bytes);
+        self
+    }
+
+    // Receives from `reader` on a host thread, bytes are queued as the device ticks.
+    pub fn with_reader(mut self, mut reader: impl Read + Send + 'static) -> Self
+    {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move ||
+        {
+            let mut byte = [0u8];
+            while let Ok(1) = reader.read(&mut byte)
+            {
+                if sender.send(byte[0]).is_err()
+                {
+                    break
+                }
+            }
+        });
+
+        self.input = Some(receiver);
+        self
+    }
+
+    // Raises `line` while an enabled interrupt is pending.
+    pub fn with_interrupt(mut self, line: InterruptLine) -> Self
+    {
+        self.interrupt = Some(line);
+        self
+    }
+
+    // Queues `bytes` at the end of the receive buffer.
+    pub fn receive(&mut self, bytes: &[u8])
+    {
+        self.buffer.extend(bytes);
+        self.update();
+    }
+
+    // Highest priority pending interrupt as reported by IIR, received data comes before an empty transmitter.
+    fn identification(&self) -> u8
+    {
+        let fifo = if self.fcr & FCR_ENABLE != 0 { IIR_FIFO } else { 0 };
+
+        if self.ier & IER_RDA != 0 && !self.buffer.is_empty()
+        {
+            fifo | IIR_RDA
+        }
+        else if self.ier & IER_THRE != 0 && self.thre_pending
+        {
+            fifo | IIR_THRE
+        }
+        else
+        {
+            fifo | IIR_NONE
+        }
+    }
+
+    fn update(&self)
+    {
+        if let Some(line) = &self.interrupt
+        {
+            line.set(self.identification() & IIR_NONE == 0);
+        }
+    }
+
+    // Transmits `byte`, looped back to the receiver in loopback mode. The transmitter empties immediately.
+    fn transmit(&mut self, byte: u8) -> Result<(), MMUErr>
+    {
+        if self.mcr & MCR_LOOP != 0
+        {
+            self.buffer.push_back(byte);
+        }
+        else
+        {
+            self.output.write_all(&[byte]).and_then(|_| self.output.flush())
+                .map_err(|io_err| MMUErr::BusError(format!("UART transmit failed: {}", io_err)))?;
+        }
+
+        self.thre_pending = true;
+        Ok(())
+    }
+
+    fn bus_error(offset: Address) -> MMUErr
+    {
+        MMUErr::BusError(format!("No UART register at offset: 0x{:x}", offset))
+    }
+}
+
+impl Device for Uart
+{
+    fn read(&mut self, offset: Address, width: usize) -> Result<u64, MMUErr>
+    {
+        if width != 1
+        {
+            return Err(Self::bus_error(offset))
+        }
+
+        let dlab = self.lcr & LCR_DLAB != 0;
+        let value = match offset
+        {
+            UART_RBR if dlab => self.divisor as u8,
+            UART_IER if dlab => (self.divisor >> 8) as u8,
+            UART_RBR => self.buffer.pop_front().unwrap_or(0),
+            UART_IER => self.ier,
+            UART_IIR =>
+            { // Reporting an empty transmitter acknowledges it.
+                let identification = self.identification();
+                if identification & 0x0F == IIR_THRE
+                {
+                    self.thre_pending = false;
+                }
+                identification
+            },
+            UART_LCR => self.lcr,
+            UART_MCR => self.mcr,
+            UART_LSR => LSR_THRE | LSR_TEMT | (!self.buffer.is_empty() as u8 * LSR_DR),
+            UART_MSR => 0,
+            UART_SCR => self.scr,
+            _ => return Err(Self::bus_error(offset))
+        };
+
+        self.update();
+        Ok(value as u64)
+    }
+
+    fn write(&mut self, offset: Address, width: usize, value: u64) -> Result<(), MMUErr>
+    {
+        if width != 1
+        {
+            return Err(Self::bus_error(offset))
+        }
+
+        let (dlab, value) = (self.lcr & LCR_DLAB != 0, value as u8);
+        match offset
+        {
+            UART_RBR if dlab => self.divisor = (self.divisor & 0xFF00) | value as u16,
+            UART_IER if dlab => self.divisor = (self.divisor & 0x00FF) | (value as u16) << 8,
+            UART_RBR => self.transmit(value)?,
+            UART_IER =>
+            { // Enabling the empty transmitter interrupt raises it right away.
+                if value & IER_THRE != 0 && self.ier & IER_THRE == 0
+                {
+                    self.thre_pending = true;
+                }
+                self.ier = value & 0x0F;
+            },
+            UART_IIR =>
+            {
+                if value & FCR_CLEAR != 0
+                {
+                    self.buffer.clear();
+                }
+                self.fcr = value & FCR_ENABLE;
+            },
+            UART_LCR => self.lcr = value,
+            UART_MCR => self.mcr = value & 0x1F,
+            UART_LSR | UART_MSR => (),
+            UART_SCR => self.scr = value,
+            _ => return Err(Self::bus_error(offset))
+        }
+
+        self.update();
+        Ok(())
+    }
+
+    fn tick(&mut self, _memory: &mut PhysicalMemory)
+    {
+        if let Some(input) = &self.input
+        {
+            let received = input.try_iter().collect::<Vec<_>>();
+            if !received.is_empty()
+            {
+                self.receive(&received);
+            }
+        }
+    }
+}
@@ -0,0 +1,548 @@
+use std::{
+    collections::HashMap, fs::{File, OpenOptions}, io::{self, Read, Seek, SeekFrom, Write}, path::Path
+};
+
+use crate::{
+    mem::*, mmu::{MMUErr, PhysicalMemory}
+};
+use super::{
+    Device, plic::InterruptLine
+};
+
+// Conventional base address and size of the first virtio-mmio transport.
+pub const VIRTIO_BASE: Address = 0x1000_1000;
+pub const VIRTIO_SIZE: usize   = 0x1000;
+
+// virtio-mmio register offsets, the legacy interface pages the queue while the modern one takes its addresses.
+pub const VIRTIO_MAGIC: Address             = 0x000;
+pub const VIRTIO_VERSION: Address           = 0x004;
+pub const VIRTIO_DEVICE_ID: Address         = 0x008;
+pub const VIRTIO_VENDOR_ID: Address         = 0x00C;
+pub const VIRTIO_DEVICE_FEATURES: Address   = 0x010;
+pub const VIRTIO_DEVICE_FEATURES_SEL: Address = 0x014;
+pub const VIRTIO_DRIVER_FEATURES: Address   = 0x020;
+pub const VIRTIO_DRIVER_FEATURES_SEL: Address = 0x024;
+pub const VIRTIO_GUEST_PAGE_SIZE: Address   = 0x028;
+pub const VIRTIO_QUEUE_SEL: Address         = 0x030;
+pub const VIRTIO_QUEUE_NUM_MAX: Address     = 0x034;
+pub const VIRTIO_QUEUE_NUM: Address         = 0x038;
+pub const VIRTIO_QUEUE_ALIGN: Address       = 0x03C;
+pub const VIRTIO_QUEUE_PFN: Address         = 0x040;
+pub const VIRTIO_QUEUE_READY: Address       = 0x044;
+pub const VIRTIO_QUEUE_NOTIFY: Address      = 0x050;
+pub const VIRTIO_INTERRUPT_STATUS: Address  = 0x060;
+pub const VIRTIO_INTERRUPT_ACK: Address     = 0x064;
+pub const VIRTIO_STATUS: Address            = 0x070;
+pub const VIRTIO_QUEUE_DESC: Address        = 0x080;
+pub const VIRTIO_QUEUE_DESC_HIGH: Address   = 0x084;
+pub const VIRTIO_QUEUE_DRIVER: Address      = 0x090;
+pub const VIRTIO_QUEUE_DRIVER_HIGH: Address = 0x094;
+pub const VIRTIO_QUEUE_DEVICE: Address      = 0x0A0;
+pub const VIRTIO_QUEUE_DEVICE_HIGH: Address = 0x0A4;
+pub const VIRTIO_CONFIG_GENERATION: Address = 0x0FC;
+pub const VIRTIO_CONFIG: Address            = 0x100;
+
+// "virt" in little-endian, the block device id and the vendor id ("aem").
+const MAGIC: u64     = 0x7472_6976;
+const BLOCK_ID: u64  = 2;
+const VENDOR_ID: u64 = 0x006D_6561;
+
+// Feature bits.
+pub const VIRTIO_BLK_F_RO: u64    = 1 << 5;
+pub const VIRTIO_BLK_F_FLUSH: u64 = 1 << 9;
+pub const VIRTIO_F_VERSION_1: u64 = 1 << 32;
+
+// Request types and status values.
+pub const VIRTIO_BLK_T_IN: u32     = 0;
+pub const VIRTIO_BLK_T_OUT: u32    = 1;
+pub const VIRTIO_BLK_T_FLUSH: u32  = 4;
+pub const VIRTIO_BLK_T_GET_ID: u32 = 8;
+pub const VIRTIO_BLK_S_OK: u8      = 0;
+pub const VIRTIO_BLK_S_IOERR: u8   = 1;
+pub const VIRTIO_BLK_S_UNSUPP: u8  = 2;
+
+// Descriptor flags.
+const DESC_NEXT: u16  = 1;
+const DESC_WRITE: u16 = 2;
+
+// Largest queue and the used buffer notification bit of InterruptStatus.
+const QUEUE_NUM_MAX: u64 = 256;
+const USED_BUFFER: u64 = 1;
+
+pub const SECTOR_SIZE: usize = 512;
+
+// Transport interface presented to the driver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interface
+{
+    Legacy, // Version 1, the queue is located by QueuePFN and GuestPageSize.
+    Modern  // Version 2, the descriptor, driver and device areas are set separately.
+}
+
+// Disk image backed by a host file. Read-only images reject writes, overlays keep written sectors in memory
+// and leave the file untouched.
+pub struct Image
+{
+    file: File,
+    pub sectors: u64,
+    pub read_only: bool,
+    pub overlay: Option<HashMap<u64 /* Sector */, Vec<u8>>>
+}
+
+impl Image
+{
+    pub fn open(path: impl AsRef<Path>, read_only: bool) -> io::Result<Self>
+    {
+        let file = OpenOptions::new().read(true).write(!read_only).open(path)?;
+        let sectors = file.metadata()?.len() / SECTOR_SIZE as u64;
+
+        Ok(Image{ file, sectors, read_only, overlay: None })
+    }
+
+    // Opens `path` read-only with a copy-on-write overlay taking the writes.
+    pub fn overlay(path: impl AsRef<Path>) -> io::Result<Self>
+    {
+        Ok(Image{ overlay: Some(HashMap::new()), ..Self::open(path, true)? })
+    }
+
+    pub fn read(&mut self, offset: u64, buffer: &mut [u8]) -> io::Result<()>
+    {
+        self.check(offset, buffer.len())?;
+
+        let mut done = 0;
+        while done < buffer.len()
+        {
+            let position = offset + done as u64;
+            let (sector, within) = (position / SECTOR_SIZE as u64, (position % SECTOR_SIZE as u64) as usize);
+            let length = (SECTOR_SIZE - within).min(buffer.len() - done);
+
+            let data = self.sector(sector)?;
+            buffer[done..done + length].copy_from_slice(&data[within..within + length]);
+            done += length;
+        }
+        Ok(())
+    }
+
+    pub fn write(&mut self, offset: u64, data: &[u8]) -> io::Result<()>
+    {
+        self.check(offset, data.len())?;
+
+        if self.overlay.is_none()
+        {
+            if self.read_only
+            {
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied, "Read-only disk image"))
+            }
+
+            self.file.seek(SeekFrom::Start(offset))?;
+            return self.file.write_all(data)
+        }
+
+        let mut done = 0;
+        while done < data.len()
+        {
+            let position = offset + done as u64;
+            let (sector, within) = (position / SECTOR_SIZE as u64, (position % SECTOR_SIZE as u64) as usize);
+            let length = (SECTOR_SIZE - within).min(data.len() - done);
+
+            let mut contents = self.sector(sector)?;
+            contents[within..within + length].copy_from_slice(&data[done..done + length]);
+            if let Some(overlay) = self.overlay.as_mut()
+            {
+                overlay.insert(sector, contents);
+            }
+            done += length;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()>
+    {
+        match self.overlay
+        {
+            Some(_) => Ok(()),
+            None => self.file.flush()
+        }
+    }
+
+    // Contents of `sector`, from the overlay when it has been written.
+    fn sector(&mut self, sector: u64) -> io::Result<Vec<u8>>
+    {
+        if let Some(contents) = self.overlay.as_ref().and_then(|overlay| overlay.get(&sector))
+        {
+            return Ok(contents.clone())
+        }
+
+        let start = sector.checked_mul(SECTOR_SIZE as u64).ok_or_else(Self::past_end)?;
+        let mut contents = vec![0; SECTOR_SIZE];
+        self.file.seek(SeekFrom::Start(start))?;
+        self.file.read_exact(&mut contents)?;
+        Ok(contents)
+    }
+
+    // Rejects accesses reaching past the last sector, including those whose end overflows.
+    fn check(&self, offset: u64, length: usize) -> io::Result<()>
+    {
+        match offset.checked_add(length as u64).is_some_and(|end| end <= self.sectors * SECTOR_SIZE as u64)
+        {
+            true => Ok(()),
+            false => Err(Self::past_end())
+        }
+    }
+
+    fn past_end() -> io::Error
+    {
+        io::Error::new(io::ErrorKind::UnexpectedEof, "Access past the end of the disk image")
+    }
+}
+
+// Split virtqueue located in guest memory.
+#[derive(Debug, Clone, Copy, Default)]
+struct Queue
+{
+    num: u64,
+    ready: bool,
+    align: u64,
+    pfn: u64,
+    desc: u64,
+    driver: u64,
+    device: u64,
+    last_avail: u16
+}
+
+// Block device on a virtio-mmio transport with a single request queue. Notified requests are served on the next tick.
+pub struct VirtioBlock
+{
+    pub image: Image,
+    pub interface: Interface,
+    pub interrupt: Option<InterruptLine>,
+    pub status: u64,
+    pub interrupt_status: u64,
+    pub driver_features: u64,
+    device_features_sel: u64,
+    driver_features_sel: u64,
+    guest_page_size: u64,
+    queue_sel: u64,
+    queue: Queue,
+    notified: bool
+}
+
+impl VirtioBlock
+{
+    pub fn new(image: Image, interface: Interface) -> Self
+    {
+        VirtioBlock{
+            image,
+            interface,
+            interrupt: None,
+            status: 0,
+            interrupt_status: 0,
+            driver_features: 0,
+            device_features_sel: 0,
+            driver_features_sel: 0,
+            guest_page_size: 0,
+            queue_sel: 0,
+            queue: Queue::default(),
+            notified: false
+        }
+    }
+
+    // Raises `line` while a used buffer notification is unacknowledged.
+    pub fn with_interrupt(mut self, line: InterruptLine) -> Self
+    {
+        self.interrupt = Some(line);
+        self
+    }
+
+    pub fn device_features(&self) -> u64
+    {
+        let read_only = self.image.read_only && self.image.overlay.is_none();
+        let version = if self.interface == Interface::Modern { VIRTIO_F_VERSION_1 } else { 0 };
+
+        VIRTIO_BLK_F_FLUSH | (read_only as u64 * VIRTIO_BLK_F_RO) | version
+    }
+
+    // Writing zero to Status returns the device and its queue to the initial state.
+    fn reset(&mut self)
+    {
+        self.status = 0;
+        self.interrupt_status = 0;
+        self.driver_features = 0;
+        self.device_features_sel = 0;
+        self.driver_features_sel = 0;
+        self.guest_page_size = 0;
+        self.queue_sel = 0;
+        self.queue = Queue::default();
+        self.notified = false;
+        self.update();
+    }
+
+    fn update(&self)
+    {
+        if let Some(line) = &self.interrupt
+        {
+            line.set(self.interrupt_status != 0);
+        }
+    }
+
+    // Physical addresses of the descriptor table, available ring and used ring, None when a legacy layout overflows.
+    fn areas(&self) -> Option<(u64, u64, u64)>
+    {
+        let queue = &self.queue;
+        match self.interface
+        {
+            Interface::Legacy if queue.pfn != 0 =>
+            {
+                let desc = queue.pfn.checked_mul(self.guest_page_size)?;
+                let driver = desc.checked_add(16 * queue.num)?;
+                let align = queue.align.max(1);
+                let device = driver.checked_add(6 + 2 * queue.num)?.div_ceil(align).checked_mul(align)?;
+                Some((desc, driver, device))
+            },
+            Interface::Modern if queue.ready => Some((queue.desc, queue.driver, queue.device)),
+            _ => None
+        }
+    }
+
+    // Serves every request made available since the last notification.
+    fn process(&mut self, memory: &mut PhysicalMemory)
+    {
+        let Some((desc, driver, device)) = self.areas() else { return };
+        let num = self.queue.num;
+        if num == 0
+        {
+            return
+        }
+
+        let Some(available) = driver.checked_add(2).and_then(|index| load::<2>(memory, index)).map(u16::from_le_bytes) else { return };
+        while self.queue.last_avail != available
+        {
+            let slot = driver.checked_add(4 + 2 * (self.queue.last_avail as u64 % num));
+            let Some(head) = slot.and_then(|slot| load::<2>(memory, slot)).map(u16::from_le_bytes) else { return };
+
+            let written = self.request(memory, desc, num, head).unwrap_or(0);
+
+            // Used ring element and index.
+            let Some(index) = device.checked_add(2) else { return };
+            let Some(used) = load::<2>(memory, index).map(u16::from_le_bytes) else { return };
+            let Some(element) = device.checked_add(4 + 8 * (used as u64 % num)) else { return };
+            let mut entry = [0; 8];
+            entry[..4].copy_from_slice(&(head as u32).to_le_bytes());
+            entry[4..].copy_from_slice(&written.to_le_bytes());
+            store(memory, element, &entry);
+            store(memory, index, &used.wrapping_add(1).to_le_bytes());
+
+            self.queue.last_avail = self.queue.last_avail.wrapping_add(1);
+            self.interrupt_status |= USED_BUFFER;
+        }
+        self.update();
+    }
+
+    // Serves the request starting at descriptor `head`, returning the number of bytes written to guest memory.
+    fn request(&mut self, memory: &mut PhysicalMemory, desc: u64, num: u64, head: u16) -> Option<u32>
+    {
+        // Descriptor chain as (address, length, device writable).
+        let mut chain = Vec::new();
+        let mut index = head as u64;
+        loop
+        {
+            if index >= num || chain.len() as u64 > num
+            {
+                return None
+            }
+
+            let entry = load::<16>(memory, desc.checked_add(16 * index)?)?;
+            let address = u64::from_le_bytes(entry[..8].try_into().unwrap());
+            let length = u32::from_le_bytes(entry[8..12].try_into().unwrap()) as usize;
+            let flags = u16::from_le_bytes(entry[12..14].try_into().unwrap());
+            chain.push((address, length, flags & DESC_WRITE != 0));
+
+            if flags & DESC_NEXT == 0
+            {
+                break
+            }
+            index = u16::from_le_bytes(entry[14..].try_into().unwrap()) as u64;
+        }
+
+        // Header, data buffers and a trailing status byte.
+        let (&(status_address, _, _), buffers) = chain.split_last()?;
+        let (&(header, _, _), buffers) = buffers.split_first()?;
+        let header = load::<16>(memory, header)?;
+        let kind = u32::from_le_bytes(header[..4].try_into().unwrap());
+
+        // Disk offset of the next buffer, None once it overflows so that the transfer fails with an I/O error.
+        let mut offset = u64::from_le_bytes(header[8..].try_into().unwrap()).checked_mul(SECTOR_SIZE as u64);
+
+        let mut written = 0;
+        let mut status = VIRTIO_BLK_S_OK;
+        for &(address, length, writable) in buffers
+        {
+            let range = usize::try_from(address).ok().and_then(|address| Some(address..address.checked_add(length)?));
+            let result = match (kind, writable)
+            {
+                (VIRTIO_BLK_T_IN, true) => match (offset, range.and_then(|range| memory.get_mut(range)))
+                {
+                    (Some(offset), Some(buffer)) => self.image.read(offset, buffer).map(|_| written += length),
+                    _ => Err(io::ErrorKind::InvalidInput.into())
+                },
+                (VIRTIO_BLK_T_OUT, false) => match (offset, range.and_then(|range| memory.get(range)))
+                {
+                    (Some(offset), Some(data)) => self.image.write(offset, data),
+                    _ => Err(io::ErrorKind::InvalidInput.into())
+                },
+                (VIRTIO_BLK_T_GET_ID, true) =>
+                {
+                    let id = b"aem-virtio-blk";
+                    let length = length.min(id.len());
+                    store(memory, address, &id[..length]);
+                    written += length;
+                    Ok(())
+                },
+                _ => Err(io::ErrorKind::Unsupported.into())
+            };
+
+            if let Err(io_err) = result
+            {
+                status = if io_err.kind() == io::ErrorKind::Unsupported { VIRTIO_BLK_S_UNSUPP } else { VIRTIO_BLK_S_IOERR };
+                break
+            }
+            offset = offset.and_then(|offset| offset.checked_add(length as u64));
+        }
+
+        if kind == VIRTIO_BLK_T_FLUSH && self.image.flush().is_err()
+        {
+            status = VIRTIO_BLK_S_IOERR;
+        }
+        if !matches!(kind, VIRTIO_BLK_T_IN | VIRTIO_BLK_T_OUT | VIRTIO_BLK_T_FLUSH | VIRTIO_BLK_T_GET_ID)
+        {
+            status = VIRTIO_BLK_S_UNSUPP;
+        }
+
+        store(memory, status_address, &[status]);
+        Some((written as u32).saturating_add(1))
+    }
+
+    fn bus_error(offset: Address) -> MMUErr
+    {
+        MMUErr::BusError(format!("No virtio register at offset: 0x{:x}", offset))
+    }
+}
+
+// `N` bytes of guest memory at `address`, None when out of bounds.
+fn load<const N: usize>(memory: &PhysicalMemory, address: u64) -> Option<[u8; N]>
+{
+    let start = usize::try_from(address).ok()?;
+    memory.get(start..start.checked_add(N)?)?.try_into().ok()
+}
+
+// Stores `bytes` to guest memory, out of bounds stores are dropped.
+fn store(memory: &mut PhysicalMemory, address: u64, bytes: &[u8])
+{
+    let target = usize::try_from(address).ok()
+        .and_then(|start| memory.get_mut(start..start.checked_add(bytes.len())?));
+    if let Some(target) = target
+    {
+        target.copy_from_slice(bytes);
+    }
+}
+
+impl Device for VirtioBlock
+{
+    // Registers are 32 bits wide, the configuration space (capacity in sectors) may be accessed with any width.
+    fn read(&mut self, offset: Address, width: usize) -> Result<u64, MMUErr>
+    {
+        if offset >= VIRTIO_CONFIG
+        {
+            let config = self.image.sectors.to_le_bytes();
+            let start = offset - VIRTIO_CONFIG;
+            return match config.get(start..start + width)
+            {
+                Some(bytes) => Ok(bytes.iter().rev().fold(0, |value, &byte| value << 8 | byte as u64)),
+                None => Ok(0)
+            }
+        }
+
+        if width != 4
+        {
+            return Err(Self::bus_error(offset))
+        }
+
+        let modern = self.interface == Interface::Modern;
+        let value = match offset
+        {
+            VIRTIO_MAGIC => MAGIC,
+            VIRTIO_VERSION => if modern { 2 } else { 1 },
+            VIRTIO_DEVICE_ID => BLOCK_ID,
+            VIRTIO_VENDOR_ID => VENDOR_ID,
+            VIRTIO_DEVICE_FEATURES => match self.device_features_sel
+            {
+                0 => self.device_features() & 0xFFFF_FFFF,
+                1 => self.device_features() >> 32,
+                _ => 0
+            },
+            VIRTIO_QUEUE_NUM_MAX if self.queue_sel == 0 => QUEUE_NUM_MAX,
+            VIRTIO_QUEUE_PFN if !modern => self.queue.pfn,
+            VIRTIO_QUEUE_READY if modern => self.queue.ready as u64,
+            VIRTIO_INTERRUPT_STATUS => self.interrupt_status,
+            VIRTIO_STATUS => self.status,
+            _ => 0 // Including the configuration generation, the configuration never changes.
+        };
+        Ok(value)
+    }
+
+    fn write(&mut self, offset: Address, width: usize, value: u64) -> Result<(), MMUErr>
+    {
+        if width != 4 || offset >= VIRTIO_CONFIG
+        {
+            return Err(Self::bus_error(offset))
+        }
+
+        let (modern, selected) = (self.interface == Interface::Modern, self.queue_sel == 0);
+        let queue = &mut self.queue;
+        let low = |register: u64| (register & !0xFFFF_FFFF) | value;
+        let high = |register: u64| (register & 0xFFFF_FFFF) | value << 32;
+
+        match offset
+        {
+            VIRTIO_DEVICE_FEATURES_SEL => self.device_features_sel = value,
+            VIRTIO_DRIVER_FEATURES => match self.driver_features_sel
+            {
+                0 => self.driver_features = low(self.driver_features),
+                1 => self.driver_features = high(self.driver_features),
+                _ => ()
+            },
+            VIRTIO_DRIVER_FEATURES_SEL => self.driver_features_sel = value,
+            VIRTIO_GUEST_PAGE_SIZE if !modern => self.guest_page_size = value,
+            VIRTIO_QUEUE_SEL => self.queue_sel = value,
+            VIRTIO_QUEUE_NUM if selected => queue.num = value.min(QUEUE_NUM_MAX),
+            VIRTIO_QUEUE_ALIGN if selected && !modern => queue.align = value,
+            VIRTIO_QUEUE_PFN if selected && !modern => queue.pfn = value,
+            VIRTIO_QUEUE_READY if selected && modern => queue.ready = value & 1 != 0,
+            VIRTIO_QUEUE_DESC if selected && modern => queue.desc = low(queue.desc),
+            VIRTIO_QUEUE_DESC_HIGH if selected && modern => queue.desc = high(queue.desc),
+            VIRTIO_QUEUE_DRIVER if selected && modern => queue.driver = low(queue.driver),
+            VIRTIO_QUEUE_DRIVER_HIGH if selected && modern => queue.driver = high(queue.driver),
+            VIRTIO_QUEUE_DEVICE if selected && modern => queue.device = low(queue.device),
+            VIRTIO_QUEUE_DEVICE_HIGH if selected && modern => queue.device = high(queue.device),
+            VIRTIO_QUEUE_NOTIFY => self.notified |= value == 0,
+            VIRTIO_INTERRUPT_ACK =>
+            {
+                self.interrupt_status &= !value;
+                self.update();
+            },
+            VIRTIO_STATUS if value == 0 => self.reset(),
+            VIRTIO_STATUS => self.status = value,
+            _ => ()
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, memory: &mut PhysicalMemory)
+    {
+        if self.notified
+        {
+            self.notified = false;
+            self.process(memory);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::fmt;
+use lazy_static::lazy_static;
+
+use crate::{
+    lexer::*, arch::*, asm::*,
+    codec::dec::*, decode
+};
+
+lazy_static!
+{ // Single instruction pseudo-instruction expansions that can be recovered from canonical instructions.
+    static ref ALIASES: Vec<(&'static str /* Pseudo mnemonic */, &'static Vec<&'static str> /* Arguments */, &'static String /* Mnemonic */, Vec<Operand> /* Operands */)> =
+    {
+        let mut aliases: Vec<_> = PSEUDO_INSTRUCTIONS.iter()
+            .filter_map(|(pseudo, (args, tokens))| match tokens.as_slice()
+            {
+                [Token::Emittable(Emittable::Instruction(mnemonic, operands))] =>
+                    Some((*pseudo, args, mnemonic, Disassembler::flatten(operands))),
+                _ => None
+            })
+            // Expansions that only reorder their arguments (e.g. "bgt") would replace every canonical instruction.
+            .filter(|alias| Disassembler::specificity(alias) > 0)
+            .collect();
+
+        // Prefer the most constrained expansion, e.g. "nop" over "mv" for "addi x0, x0, 0".
+        aliases.sort_by_key(|alias| (std::cmp::Reverse(Disassembler::specificity(alias)), alias.1.len(), alias.0));
+        aliases
+    };
+
+    // ABI names of the registers (e.g. "a0" for "x10"), "s0" rather than "fp" as Spike prints it.
+    static ref ABI_NAMES: HashMap<&'static str /* Register */, &'static str /* ABI name */> = CONVENTIONAL_TO_ABI.iter()
+        .filter(|(name, _)| **name != "fp")
+        .map(|(name, register)| (*register, *name))
+        .collect();
+}
+
+pub struct Line
+{
+    pub address: usize,
+    pub binary: u32,
+    pub labels: Vec<String>,
+    pub text: String
+}
+
+pub struct Disassembler
+{
+    pub lines: Vec<Line>
+}
+
+impl Disassembler
+{
+    pub fn new(object: &Object) -> Self
+    {
+        Self::with_aliases(object, true)
+    }
+
+    // Disassembles `object`, recovering pseudo-instructions when `aliases` is set.
+    pub fn with_aliases(object: &Object, aliases: bool) -> Self
+    { // Order symbols by address (then name) so lookups and listings are deterministic.
+        let mut symbols: Vec<(usize, &String)> = object.symbols.iter()
+            .map(|(name, address)| (*address, name))
+            .collect();
+        symbols.sort();
+
+        let lines = object.binary.chunks(4).enumerate().map(|(i, chunk)|
+        {
+            let mut bytes = [0u8; 4];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+
+            let address = i * 4;
+            let binary = u32::from_le_bytes(bytes);
+
+            Line
+            {
+                address,
+                binary,
+                labels: symbols.iter()
+                    .filter(|(symbol_address, _)| *symbol_address == address)
+                    .map(|(_, name)| name.to_string())
+                    .collect(),
+                text: Self::disassemble(address, binary, Some(&symbols), aliases, false)
+            }
+        }).collect();
+
+        Disassembler{ lines }
+    }
+
+    // Text of `binary` at `address`. Branch and jump targets are named after the closest preceding symbol of `symbols`,
+    // without symbols they're written relative to pc as Spike does (e.g. "pc + 0x8"). Registers are written by their
+    // ABI names (e.g. "a0") when `abi_names` is set.
+    pub fn disassemble(address: usize, binary: u32, symbols: Option<&[(usize, &String)]>, aliases: bool, abi_names: bool) -> String
+    {
+        match decode!(binary)
+        {
+            Ok((mnemonic, mut operands)) =>
+            { // Branch and jump offsets are shown as their target.
+                if matches!(RV_ISA[mnemonic.as_str()].opcode, Opcode::Branch | Opcode::Jal)
+                {
+                    if let Some(Operand::RValue(RValue::Immediate(offset))) = operands.last().cloned()
+                    {
+                        let target = match symbols
+                        {
+                            Some(symbols) => Self::symbolize((address as i64 + offset as i64) as usize, symbols),
+                            None if offset < 0 => format!("pc - 0x{:x}", -(offset as i64)),
+                            None => format!("pc + 0x{:x}", offset)
+                        };
+                        *operands.last_mut().unwrap() = RValue::Identifier(target).into();
+                    }
+                }
+
+                let (mnemonic, mut operands) = match Self::alias(&mnemonic, &operands)
+                {
+                    Some((pseudo, pseudo_operands)) if aliases => (pseudo, pseudo_operands),
+                    _ => (mnemonic.as_str(), operands)
+                };
+
+                if abi_names
+                {
+                    operands.iter_mut().for_each(|operand| match operand
+                    {
+                        Operand::RValue(register) | Operand::Address(register, _) => *register = Self::abi_name(register),
+                        _ => ()
+                    });
+                }
+                Self::format(mnemonic, &operands)
+            }, // Data or unsupported encodings are emitted as raw words.
+            Err(_) => format!(".word 0x{:08x}", binary)
+        }
+    }
+
+    // Names `target` relative to the closest preceding symbol (e.g. "loop+0x8").
+    pub fn symbolize(target: usize, symbols: &[(usize, &String)]) -> String
+    {
+        match symbols.iter().rev().find(|(address, _)| *address <= target)
+        {
+            Some((address, name)) if *address == target => name.to_string(),
+            Some((address, name)) => format!("{}+0x{:x}", name, target - address),
+            None => format!("0x{:x}", target)
+        }
+    }
+
+    // ABI name of a register, other values are kept as they are.
+    fn abi_name(value: &RValue<i32>) -> RValue<i32>
+    {
+        match value
+        {
+            RValue::Register(prefix, index) => ABI_NAMES.get(format!("{}{}", prefix, index).as_str())
+                .map_or_else(|| value.clone(), |name| RValue::Identifier(name.to_string())),
+            _ => value.clone()
+        }
+    }
+
+    // Finds the pseudo-instruction whose expansion produces `mnemonic` with `operands`.
+    pub fn alias(mnemonic: &str, operands: &[Operand]) -> Option<(&'static str, Vec<Operand>)>
+    {
+        let operands = Self::flatten(operands);
+
+        ALIASES.iter()
+            .filter(|(_, _, alias_mnemonic, alias_operands)| *alias_mnemonic == mnemonic && alias_operands.len() == operands.len())
+            .find_map(|(pseudo, args, _, alias_operands)|
+            {
+                let mut bindings: HashMap<&str, &Operand> = HashMap::new();
+
+                // Arguments bind to the decoded operand, repeated arguments must agree.
+                let matched = alias_operands.iter().zip(&operands).all(|(template, operand)| match template
+                {
+                    Operand::RValue(RValue::Identifier(arg)) if args.contains(&arg.as_str()) =>
+                        *bindings.entry(arg.as_str()).or_insert(operand) == operand,
+                    _ => template == operand
+                });
+
+                if !matched
+                {
+                    return None
+                }
+
+                // Width variants (e.g. "li.16") are written without their suffix.
+                let name = match pseudo.rsplit_once('.')
+                {
+                    Some((base, width)) if width.chars().all(|c| c.is_ascii_digit()) => base,
+                    _ => pseudo
+                };
+
+                Some((name, args.iter().map(|arg| bindings[arg].clone()).collect()))
+            })
+    }
+
+    // Splits addresses into their base and offset (e.g. "0(x1)" into "x1, 0").
+    fn flatten(operands: &[Operand]) -> Vec<Operand>
+    {
+        operands.iter().flat_map(|operand| match operand
+        {
+            Operand::Address(base, offset) => vec![base.clone().into(), offset.clone().into()],
+            _ => vec![operand.clone()]
+        }).collect()
+    }
+
+    // Number of operands an expansion pins beyond it's arguments.
+    fn specificity(alias: &(&str, &Vec<&str>, &String, Vec<Operand>)) -> usize
+    {
+        let (_, args, _, operands) = alias;
+        let placeholders = operands.iter()
+            .filter(|operand| matches!(operand, Operand::RValue(RValue::Identifier(arg)) if args.contains(&arg.as_str())))
+            .count();
+
+        operands.len() - placeholders + placeholders.saturating_sub(args.len())
+    }
+
+    pub fn format(mnemonic: &str, operands: &[Operand]) -> String
+    {
+        if operands.is_empty()
+        {
+            return mnemonic.to_string()
+        }
+
+        let operands_str: Vec<String> = operands.iter().map(|operand| operand.to_string()).collect();
+        format!("{} {}", mnemonic, operands_str.join(", "))
+    }
+}
+
+impl fmt::Display for Disassembler
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        for line in &self.lines
+        {
+            for label in &line.labels
+            {
+                writeln!(f, "{}:", label)?;
+            }
+            writeln!(f, "    {:04x}: 0x{:08x}    {}", line.address, line.binary, line.text)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+
+// Header constants of little-endian RISC-V executables.
+pub const ELF_MAGIC: &[u8; 4] = b"\x7FELF";
+pub const ELFCLASS32: u8      = 1;
+pub const ELFCLASS64: u8      = 2;
+pub const ELFDATA2LSB: u8     = 1;
+pub const ET_EXEC: u16        = 2;
+pub const EM_RISCV: u16       = 243;
+
+// Program header types and flags.
+pub const PT_LOAD: u32 = 1;
+pub const PT_PHDR: u32 = 6;
+pub const PF_X: u32    = 1;
+pub const PF_W: u32    = 2;
+pub const PF_R: u32    = 4;
+
+// Symbol table section type.
+const SHT_SYMTAB: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ElfErr
+{
+    Header(String),
+    Truncated(String)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ElfClass
+{
+    Elf32,
+    Elf64
+}
+
+impl ElfClass
+{
+    // Size of an address or native word in bytes.
+    pub fn word(self) -> usize
+    {
+        match self
+        {
+            ElfClass::Elf32 => 4,
+            ElfClass::Elf64 => 8
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramHeader
+{
+    pub kind: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub address: u64,
+    pub file_size: u64,
+    pub memory_size: u64
+}
+
+// Parsed executable, only the parts needed to load and run it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Elf
+{
+    pub class: ElfClass,
+    pub entry: u64,
+    pub header_offset: u64, // Offset of the program headers.
+    pub header_size: u16,   // Size of a program header.
+    pub program_headers: Vec<ProgramHeader>,
+    pub symbols: HashMap<String /* Identifier */, u64 /* Value */>
+}
+
+// Little-endian fields of the file, word sized fields follow the class.
+struct Reader<'a>
+{
+    bytes: &'a [u8],
+    class: ElfClass
+}
+
+impl<'a> Reader<'a>
+{
+    fn bytes(&self, offset: u64, length: usize) -> Result<&'a [u8], ElfErr>
+    {
+        usize::try_from(offset).ok()
+            .and_then(|offset| self.bytes.get(offset..offset.checked_add(length)?))
+            .ok_or_else(|| ElfErr::Truncated(format!("File truncated at offset: 0x{:x}", offset)))
+    }
+
+    // Reader over the `length` bytes at `offset`, so that the fields of a header or table entry can't overflow.
+    fn at(&self, offset: u64, length: usize) -> Result<Reader<'a>, ElfErr>
+    {
+        Ok(Reader{ bytes: self.bytes(offset, length)?, class: self.class })
+    }
+
+    fn u8(&self, offset: u64) -> Result<u8, ElfErr>
+    {
+        Ok(self.bytes(offset, 1)?[0])
+    }
+
+    fn u16(&self, offset: u64) -> Result<u16, ElfErr>
+    {
+        Ok(u16::from_le_bytes(self.bytes(offset, 2)?.try_into().unwrap()))
+    }
+
+    fn u32(&self, offset: u64) -> Result<u32, ElfErr>
+    {
+        Ok(u32::from_le_bytes(self.bytes(offset, 4)?.try_into().unwrap()))
+    }
+
+    fn word(&self, offset: u64) -> Result<u64, ElfErr>
+    {
+        match self.class
+        {
+            ElfClass::Elf32 => self.u32(offset).map(|word| word as u64),
+            ElfClass::Elf64 => Ok(u64::from_le_bytes(self.bytes(offset, 8)?.try_into().unwrap()))
+        }
+    }
+
+    // Null terminated string at `offset`.
+    fn string(&self, offset: u64) -> Result<String, ElfErr>
+    {
+        self.bytes(offset, 0)?;
+        let length = self.bytes[offset as usize..].iter().position(|&byte| byte == 0)
+            .ok_or_else(|| ElfErr::Truncated(format!("Unterminated string at offset: 0x{:x}", offset)))?;
+        Ok(String::from_utf8_lossy(self.bytes(offset, length)?).into_owned())
+    }
+}
+
+impl Elf
+{
+    // Parses a little-endian RISC-V executable of either class.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ElfErr>
+    {
+        if bytes.len() < 20 || &bytes[..4] != ELF_MAGIC
+        {
+            return Err(ElfErr::Header("Not an ELF file".to_string()))
+        }
+
+        let class = match bytes[4]
+        {
+            ELFCLASS32 => ElfClass::Elf32,
+            ELFCLASS64 => ElfClass::Elf64,
+            class => return Err(ElfErr::Header(format!(r#"Unsupported class: "{}""#, class)))
+        };
+        let reader = Reader{ bytes, class };
+
+        if reader.u8(5)? != ELFDATA2LSB
+        {
+            return Err(ElfErr::Header("Big-endian files are not supported".to_string()))
+        }
+        if reader.u16(18)? != EM_RISCV
+        {
+            return Err(ElfErr::Header(format!(r#"Not a RISC-V file, machine: "{}""#, reader.u16(18)?)))
+        }
+        if reader.u16(16)? != ET_EXEC
+        {
+            return Err(ElfErr::Header(format!(r#"Not a static executable, type: "{}""#, reader.u16(16)?)))
+        }
+
+        // Header fields after the entry point are shifted by the word size.
+        let word = class.word() as u64;
+        let entry = reader.word(24)?;
+        let header_offset = reader.word(24 + word)?;
+        let section_offset = reader.word(24 + 2 * word)?;
+        let fields = 24 + 3 * word + 4;
+        let (header_size, headers) = (reader.u16(fields + 2)?, reader.u16(fields + 4)?);
+        let (section_size, sections) = (reader.u16(fields + 6)?, reader.u16(fields + 8)?);
+
+        let program_headers = (0..headers as u64)
+            .map(|index| Self::program_header(&reader, offset(header_offset, index * header_size as u64)?))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut symbols = HashMap::new();
+        for index in 0..sections as u64
+        {
+            let section = reader.at(offset(section_offset, index * section_size as u64)?, 16 + 6 * word as usize)?;
+            if section.u32(4)? == SHT_SYMTAB
+            {
+                Self::symbols(&reader, &section, section_offset, section_size, &mut symbols)?;
+            }
+        }
+
+        Ok(Elf{ class, entry, header_offset, header_size, program_headers, symbols })
+    }
+
+    // Address the program headers are loaded at, from PT_PHDR or the segment containing them.
+    pub fn header_address(&self) -> Option<u64>
+    {
+        self.program_headers.iter().find(|header| header.kind == PT_PHDR).map(|header| header.address)
+            .or_else(|| self.program_headers.iter()
+                .filter(|header| header.kind == PT_LOAD)
+                .find(|header| header.offset.checked_add(header.file_size)
+                    .is_some_and(|end| (header.offset..end).contains(&self.header_offset)))
+                .and_then(|header| header.address.checked_add(self.header_offset - header.offset)))
+    }
+
+    fn program_header(reader: &Reader, offset: u64) -> Result<ProgramHeader, ElfErr>
+    {
+        match reader.class
+        {
+            ElfClass::Elf32 =>
+            {
+                let header = reader.at(offset, 32)?;
+                Ok(ProgramHeader{
+                    kind: header.u32(0)?,
+                    offset: header.word(4)?,
+                    address: header.word(8)?,
+                    file_size: header.word(16)?,
+                    memory_size: header.word(20)?,
+                    flags: header.u32(24)?
+                })
+            },
+            ElfClass::Elf64 =>
+            {
+                let header = reader.at(offset, 56)?;
+                Ok(ProgramHeader{
+                    kind: header.u32(0)?,
+                    flags: header.u32(4)?,
+                    offset: header.word(8)?,
+                    address: header.word(16)?,
+                    file_size: header.word(32)?,
+                    memory_size: header.word(40)?
+                })
+            }
+        }
+    }
+
+    // Adds the named symbols of the symbol table `section`, names come from the linked string table.
+    fn symbols(reader: &Reader, section: &Reader, section_offset: u64, section_size: u16, symbols: &mut HashMap<String, u64>) -> Result<(), ElfErr>
+    {
+        let word = reader.class.word() as u64;
+        let (start, size) = (section.word(8 + 2 * word)?, section.word(8 + 3 * word)?);
+        let link = section.u32(8 + 4 * word)? as u64;
+        let strings = reader.word(offset(offset(section_offset, link * section_size as u64)?, 8 + 2 * word)?)?;
+
+        let entry = if reader.class == ElfClass::Elf32 { 16 } else { 24 };
+        for symbol in (start..offset(start, size)?).step_by(entry)
+        {
+            let symbol = reader.at(symbol, entry)?;
+            let value = match reader.class
+            {
+                ElfClass::Elf32 => symbol.word(4)?,
+                ElfClass::Elf64 => symbol.word(8)?
+            };
+
+            let name = reader.string(offset(strings, symbol.u32(0)? as u64)?)?;
+            if !name.is_empty()
+            {
+                symbols.insert(name, value);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Sum of two file offsets, those that overflow lie past the end of any file.
+fn offset(base: u64, delta: u64) -> Result<u64, ElfErr>
+{
+    base.checked_add(delta).ok_or_else(|| ElfErr::Truncated(format!("Offset overflows: 0x{:x} + 0x{:x}", base, delta)))
+}
@@ -0,0 +1,26 @@
+// Value an atomic memory operation stores given the `loaded` value and `operand` (rs2). Word operations compare
+// and combine the sign-extended low 32 bits, which preserves both the signed and unsigned ordering.
+pub fn amo(mnemonic: &str, loaded: u64, operand: u64) -> Option<u64>
+{
+    let (operation, width) = mnemonic.split_once('.')?;
+
+    let (lhs, rhs) = match width
+    {
+        "w" => (loaded as i32 as i64 as u64, operand as i32 as i64 as u64),
+        _   => (loaded, operand)
+    };
+
+    Some(match operation
+    {
+        "amoswap" => rhs,
+        "amoadd"  => lhs.wrapping_add(rhs),
+        "amoxor"  => lhs ^ rhs,
+        "amoand"  => lhs & rhs,
+        "amoor"   => lhs | rhs,
+        "amomin"  => (lhs as i64).min(rhs as i64) as u64,
+        "amomax"  => (lhs as i64).max(rhs as i64) as u64,
+        "amominu" => lhs.min(rhs),
+        "amomaxu" => lhs.max(rhs),
+        _ => return None
+    })
+}
@@ -0,0 +1,441 @@
+use crate::{
+    mmu::*, mem::*, pmp::*
+};
+use super::hart::{Xlen, Privilege};
+
+// Unprivileged floating point CSRs.
+pub const FFLAGS: u16     = 0x001;
+pub const FRM: u16        = 0x002;
+pub const FCSR: u16       = 0x003;
+
+// Unprivileged counters, read-only shadows of the machine counters.
+pub const CYCLE: u16      = 0xC00;
+pub const TIME: u16       = 0xC01;
+pub const INSTRET: u16    = 0xC02;
+pub const CYCLEH: u16     = 0xC80;
+pub const TIMEH: u16      = 0xC81;
+pub const INSTRETH: u16   = 0xC82;
+
+// Supervisor trap setup, handling and address translation.
+pub const SSTATUS: u16    = 0x100;
+pub const SIE: u16        = 0x104;
+pub const STVEC: u16      = 0x105;
+pub const SCOUNTEREN: u16 = 0x106;
+pub const SSCRATCH: u16   = 0x140;
+pub const SEPC: u16       = 0x141;
+pub const SCAUSE: u16     = 0x142;
+pub const STVAL: u16      = 0x143;
+pub const SIP: u16        = 0x144;
+pub const SATP: u16       = 0x180;
+
+// Machine information registers.
+pub const MVENDORID: u16  = 0xF11;
+pub const MARCHID: u16    = 0xF12;
+pub const MIMPID: u16     = 0xF13;
+pub const MHARTID: u16    = 0xF14;
+pub const MCONFIGPTR: u16 = 0xF15;
+
+// Machine trap setup and handling.
+pub const MSTATUS: u16    = 0x300;
+pub const MISA: u16       = 0x301;
+pub const MEDELEG: u16    = 0x302;
+pub const MIDELEG: u16    = 0x303;
+pub const MIE: u16        = 0x304;
+pub const MTVEC: u16      = 0x305;
+pub const MCOUNTEREN: u16 = 0x306;
+pub const MSTATUSH: u16   = 0x310;
+pub const MSCRATCH: u16   = 0x340;
+pub const MEPC: u16       = 0x341;
+pub const MCAUSE: u16     = 0x342;
+pub const MTVAL: u16      = 0x343;
+pub const MIP: u16        = 0x344;
+
+// Machine memory protection, odd pmpcfg registers don't exist on RV64.
+pub const PMPCFG0: u16    = 0x3A0;
+pub const PMPCFG15: u16   = 0x3AF;
+pub const PMPADDR0: u16   = 0x3B0;
+pub const PMPADDR63: u16  = 0x3EF;
+
+// Machine counters.
+pub const MCYCLE: u16     = 0xB00;
+pub const MINSTRET: u16   = 0xB02;
+pub const MCYCLEH: u16    = 0xB80;
+pub const MINSTRETH: u16  = 0xB82;
+
+// mstatus fields, sstatus is a restricted view of the same register.
+pub const MSTATUS_SIE: u64  = 1 << 1;
+pub const MSTATUS_MIE: u64  = 1 << 3;
+pub const MSTATUS_SPIE: u64 = 1 << 5;
+pub const MSTATUS_MPIE: u64 = 1 << 7;
+pub const MSTATUS_SPP: u64  = 1 << 8;
+pub const MSTATUS_MPP: u64  = 0b11 << 11;
+pub const MSTATUS_FS: u64   = 0b11 << 13;
+pub const MSTATUS_MPRV: u64 = 1 << 17;
+pub const MSTATUS_SUM: u64  = 1 << 18;
+pub const MSTATUS_MXR: u64  = 1 << 19;
+pub const MSTATUS_TVM: u64  = 1 << 20;
+pub const MSTATUS_TW: u64   = 1 << 21;
+pub const MSTATUS_TSR: u64  = 1 << 22;
+
+const SSTATUS_MASK: u64 = MSTATUS_SIE | MSTATUS_SPIE | MSTATUS_SPP | MSTATUS_FS | MSTATUS_SUM | MSTATUS_MXR;
+const MSTATUS_MASK: u64 = SSTATUS_MASK | MSTATUS_MIE | MSTATUS_MPIE | MSTATUS_MPP | MSTATUS_MPRV
+    | MSTATUS_TVM | MSTATUS_TW | MSTATUS_TSR;
+
+// RV64 user and supervisor XLEN fields, fixed to 64 bits.
+const MSTATUS_UXL: u64 = 0b10 << 32;
+const MSTATUS_SXL: u64 = 0b10 << 34;
+
+// Interrupt enable and pending bits of mie/mip (software, timer and external).
+pub const SSI: u64 = 1 << 1;
+pub const MSI: u64 = 1 << 3;
+pub const STI: u64 = 1 << 5;
+pub const MTI: u64 = 1 << 7;
+pub const SEI: u64 = 1 << 9;
+pub const MEI: u64 = 1 << 11;
+
+// Exceptions that can be delegated to supervisor mode, all but environment calls from machine mode.
+const MEDELEG_MASK: u64 = 0xB3FF;
+
+// Extensions reported by misa, one bit per letter.
+const EXTENSIONS: &str = "IMAFDSU";
+
+// Control and status registers of a single hart, reads and writes follow the WARL rules of each field.
+pub struct CsrFile
+{
+    pub xlen: Xlen,
+    pub hart_id: u64,
+    pub fcsr: u32, // Rounding mode (frm) in bits 7:5, accrued exceptions (fflags) in bits 4:0.
+    pub mstatus: u64,
+    pub medeleg: u64,
+    pub mideleg: u64,
+    pub mie: u64,
+    pub mip: u64,
+    pub lines: u64, // Interrupt pending bits driven by devices, merged into mip on reads.
+    pub mtvec: u64,
+    pub mcounteren: u64,
+    pub mscratch: u64,
+    pub mepc: u64,
+    pub mcause: u64,
+    pub mtval: u64,
+    pub stvec: u64,
+    pub scounteren: u64,
+    pub sscratch: u64,
+    pub sepc: u64,
+    pub scause: u64,
+    pub stval: u64,
+    pub satp: u64,
+    pub pmp: Pmp,
+    pub cycle: u64,
+    pub time: u64,
+    pub instret: u64
+}
+
+impl CsrFile
+{
+    // Reset state, the hart starts in machine mode with the floating point unit in the initial state.
+    pub fn new(hart_id: u64, xlen: Xlen) -> Self
+    {
+        CsrFile{
+            xlen,
+            hart_id,
+            fcsr: 0,
+            mstatus: MSTATUS_MPP | (0b01 << 13),
+            medeleg: 0,
+            mideleg: 0,
+            mie: 0,
+            mip: 0,
+            lines: 0,
+            mtvec: 0,
+            mcounteren: 0,
+            mscratch: 0,
+            mepc: 0,
+            mcause: 0,
+            mtval: 0,
+            stvec: 0,
+            scounteren: 0,
+            sscratch: 0,
+            sepc: 0,
+            scause: 0,
+            stval: 0,
+            satp: 0,
+            pmp: Pmp::new(PMP_ENTRIES),
+            cycle: 0,
+            time: 0,
+            instret: 0
+        }
+    }
+
+    // Whether floating point instructions and CSRs are enabled (mstatus.FS is not Off).
+    pub fn float_enabled(&self) -> bool
+    {
+        self.mstatus & MSTATUS_FS != 0
+    }
+
+    // Marks the floating point state as modified.
+    pub fn float_dirty(&mut self)
+    {
+        self.mstatus |= MSTATUS_FS;
+    }
+
+    // Interrupts pending from software writes or device lines, external supervisor interrupts may be pending from both.
+    pub fn pending(&self) -> u64
+    {
+        self.mip | self.lines
+    }
+
+    // Address translation of accesses made with `privilege`, machine mode accesses are never translated.
+    pub fn translation(&self, privilege: Privilege) -> Translation
+    {
+        let (paging, asid, ppn) = match self.xlen
+        {
+            Xlen::X32 => (
+                if self.satp >> 31 & 1 == 1 { Paging::Sv32 } else { Paging::Bare },
+                (self.satp >> 22) & 0x1FF,
+                self.satp & 0x3F_FFFF
+            ),
+            Xlen::X64 => (
+                match self.satp >> 60 { 8 => Paging::Sv39, 9 => Paging::Sv48, _ => Paging::Bare },
+                (self.satp >> 44) & 0xFFFF,
+                self.satp & 0xFFF_FFFF_FFFF
+            )
+        };
+
+        Translation{
+            paging: if privilege == Privilege::Machine { Paging::Bare } else { paging },
+            root: ppn as Address * PAGE_SIZE,
+            asid: asid as u16,
+            user: privilege == Privilege::User,
+            sum: self.mstatus & MSTATUS_SUM != 0,
+            mxr: self.mstatus & MSTATUS_MXR != 0,
+            pmp: self.pmp
+        }
+    }
+
+    // Value of the CSR at `address` as read with `privilege`, None when it doesn't exist or isn't accessible.
+    pub fn read(&self, address: u16, privilege: Privilege) -> Option<u64>
+    {
+        if !self.accessible(address, privilege)
+        {
+            return None
+        }
+
+        let rv32 = self.xlen == Xlen::X32;
+
+        let value = match address
+        {
+            FFLAGS | FRM | FCSR if !self.float_enabled() => return None,
+            FFLAGS => self.fcsr as u64 & 0x1F,
+            FRM    => (self.fcsr as u64 >> 5) & 0b111,
+            FCSR   => self.fcsr as u64 & 0xFF,
+
+            CYCLE | MCYCLE     => self.cycle,
+            TIME               => self.time,
+            INSTRET | MINSTRET => self.instret,
+            CYCLEH | MCYCLEH if rv32     => self.cycle >> 32,
+            TIMEH if rv32                => self.time >> 32,
+            INSTRETH | MINSTRETH if rv32 => self.instret >> 32,
+
+            SSTATUS    => self.status() & (SSTATUS_MASK | MSTATUS_UXL | 1 << (self.width() - 1)),
+            SIE        => self.mie & self.mideleg,
+            SIP        => self.pending() & self.mideleg,
+            STVEC      => self.stvec,
+            SCOUNTEREN => self.scounteren,
+            SSCRATCH   => self.sscratch,
+            SEPC       => self.sepc,
+            SCAUSE     => self.scause,
+            STVAL      => self.stval,
+            SATP       => self.satp,
+
+            MVENDORID | MARCHID | MIMPID | MCONFIGPTR => 0,
+            MHARTID => self.hart_id,
+
+            MSTATUS => self.status(),
+            MSTATUSH if rv32 => 0,
+            MISA =>
+            {
+                let mxl: u64 = if rv32 { 1 } else { 2 };
+                let extensions = EXTENSIONS.bytes().fold(0, |bits, letter| bits | 1 << (letter - b'A'));
+                (mxl << (self.width() - 2)) | extensions
+            },
+            MEDELEG    => self.medeleg,
+            MIDELEG    => self.mideleg,
+            MIE        => self.mie,
+            MIP        => self.pending(),
+            MTVEC      => self.mtvec,
+            MCOUNTEREN => self.mcounteren,
+            MSCRATCH   => self.mscratch,
+            MEPC       => self.mepc,
+            MCAUSE     => self.mcause,
+            MTVAL      => self.mtval,
+            PMPCFG0..=PMPCFG15 if !rv32 && address & 1 == 1 => return None,
+            PMPCFG0..=PMPCFG15 => (0..self.width() as usize / 8)
+                .map(|byte| (self.pmp_config(address) + byte, byte))
+                .filter(|&(entry, _)| entry < PMP_ENTRIES)
+                .fold(0, |value, (entry, byte)| value | (self.pmp.config[entry] as u64) << (byte * 8)),
+            PMPADDR0..=PMPADDR63 => self.pmp.address.get((address - PMPADDR0) as usize).copied().unwrap_or(0),
+            _ => return None
+        };
+        Some(self.truncate(value))
+    }
+
+    // Writes the CSR at `address` with `privilege`, None when it doesn't exist, is read-only or isn't accessible.
+    pub fn write(&mut self, address: u16, value: u64, privilege: Privilege) -> Option<()>
+    {
+        // The top two address bits are set for read-only CSRs.
+        if address >> 10 == 0b11 || !self.accessible(address, privilege)
+        {
+            return None
+        }
+
+        let value = self.truncate(value);
+        let rv32 = self.xlen == Xlen::X32;
+
+        match address
+        {
+            FFLAGS | FRM | FCSR if !self.float_enabled() => return None,
+            FFLAGS => self.fcsr = (self.fcsr & !0x1F) | (value as u32 & 0x1F),
+            FRM    => self.fcsr = (self.fcsr & 0x1F) | ((value as u32 & 0b111) << 5),
+            FCSR   => self.fcsr = value as u32 & 0xFF,
+
+            MCYCLE if rv32    => self.cycle = (self.cycle & !0xFFFF_FFFF) | value,
+            MCYCLEH if rv32   => self.cycle = (self.cycle & 0xFFFF_FFFF) | (value << 32),
+            MINSTRET if rv32  => self.instret = (self.instret & !0xFFFF_FFFF) | value,
+            MINSTRETH if rv32 => self.instret = (self.instret & 0xFFFF_FFFF) | (value << 32),
+            MCYCLE   => self.cycle = value,
+            MINSTRET => self.instret = value,
+
+            SSTATUS => self.set_status(value, SSTATUS_MASK),
+            // Only delegated interrupts are visible to supervisor mode, of which software interrupts can be raised.
+            SIE => self.mie = (self.mie & !self.mideleg) | (value & self.mideleg),
+            SIP => self.mip = (self.mip & !(self.mideleg & SSI)) | (value & self.mideleg & SSI),
+            STVEC      => self.stvec = Self::trap_vector(self.stvec, value),
+            SCOUNTEREN => self.scounteren = value & 0b111,
+            SSCRATCH   => self.sscratch = value,
+            SEPC       => self.sepc = value & !0b11,
+            SCAUSE     => self.scause = value,
+            STVAL      => self.stval = value,
+            SATP =>
+            { // Writes selecting an unsupported scheme have no effect.
+                let supported = match self.xlen
+                {
+                    Xlen::X32 => true,
+                    Xlen::X64 => matches!(value >> 60, 0 | 8 | 9)
+                };
+
+                if supported
+                {
+                    self.satp = value;
+                }
+            },
+
+            MSTATUS => self.set_status(value, MSTATUS_MASK),
+            MSTATUSH if rv32 => (),
+            MISA => (), // Extensions can't be disabled.
+            MEDELEG => self.medeleg = value & MEDELEG_MASK,
+            MIDELEG => self.mideleg = value & (SSI | STI | SEI),
+            MIE => self.mie = value & (SSI | MSI | STI | MTI | SEI | MEI),
+            // Machine interrupts are pending until their source is cleared, supervisor interrupts can be injected.
+            MIP => self.mip = (self.mip & !(SSI | STI | SEI)) | (value & (SSI | STI | SEI)),
+            MTVEC      => self.mtvec = Self::trap_vector(self.mtvec, value),
+            MCOUNTEREN => self.mcounteren = value & 0b111,
+            MSCRATCH   => self.mscratch = value,
+            MEPC       => self.mepc = value & !0b11,
+            MCAUSE     => self.mcause = value,
+            MTVAL      => self.mtval = value,
+            PMPCFG0..=PMPCFG15 if !rv32 && address & 1 == 1 => return None,
+            PMPCFG0..=PMPCFG15 =>
+            { // Every byte configures one entry, locked entries keep their byte.
+                for byte in 0..self.width() as usize / 8
+                {
+                    self.pmp.write_config(self.pmp_config(address) + byte, (value >> (byte * 8)) as u8);
+                }
+            },
+            // Addresses are kept to bits 33:2 on RV32 and 55:2 on RV64.
+            PMPADDR0..=PMPADDR63 => self.pmp.write_address((address - PMPADDR0) as usize, value & ((1 << 54) - 1)),
+            _ => return None
+        }
+
+        if matches!(address, FFLAGS | FRM | FCSR)
+        {
+            self.float_dirty();
+        }
+        Some(())
+    }
+
+    // First entry configured by a pmpcfg register, four per register on RV32 and eight (even registers only) on RV64.
+    fn pmp_config(&self, address: u16) -> usize
+    {
+        (address - PMPCFG0) as usize * 4
+    }
+
+    // Privilege and counter enable checks, encoded in address bits 9:8 and in mcounteren/scounteren.
+    fn accessible(&self, address: u16, privilege: Privilege) -> bool
+    {
+        let required = (address >> 8) & 0b11;
+        if required > privilege as u16
+        {
+            return false
+        }
+
+        match address
+        {
+            CYCLE..=INSTRET | CYCLEH..=INSTRETH =>
+            {
+                let bit = 1 << (address & 0x1F);
+                match privilege
+                {
+                    Privilege::Machine => true,
+                    Privilege::Supervisor => self.mcounteren & bit != 0,
+                    Privilege::User => self.mcounteren & self.scounteren & bit != 0
+                }
+            },
+            SATP => !(privilege == Privilege::Supervisor && self.mstatus & MSTATUS_TVM != 0),
+            _ => true
+        }
+    }
+
+    // mstatus with the read-only fields, SD summarizes a dirty floating point state in the most significant bit.
+    fn status(&self) -> u64
+    {
+        let dirty = self.mstatus & MSTATUS_FS == MSTATUS_FS;
+        let xl = if self.xlen == Xlen::X64 { MSTATUS_UXL | MSTATUS_SXL } else { 0 };
+
+        self.mstatus | xl | if dirty { 1 << (self.width() - 1) } else { 0 }
+    }
+
+    // Writes the `mask` fields of mstatus, MPP keeps it's previous value when written with the reserved privilege.
+    fn set_status(&mut self, value: u64, mask: u64)
+    {
+        let value = match (value & MSTATUS_MPP) >> 11
+        {
+            0b10 => (value & !MSTATUS_MPP) | (self.mstatus & MSTATUS_MPP),
+            _ => value
+        };
+        self.mstatus = (self.mstatus & !mask) | (value & mask);
+    }
+
+    // Trap vector base and mode, reserved modes keep the previous mode.
+    fn trap_vector(previous: u64, value: u64) -> u64
+    {
+        let mode = if value & 0b11 < 2 { value & 0b11 } else { previous & 0b11 };
+        (value & !0b11) | mode
+    }
+
+    fn width(&self) -> u32
+    {
+        match self.xlen
+        {
+            Xlen::X32 => 32,
+            Xlen::X64 => 64
+        }
+    }
+
+    fn truncate(&self, value: u64) -> u64
+    {
+        match self.xlen
+        {
+            Xlen::X32 => value & 0xFFFF_FFFF,
+            Xlen::X64 => value
+        }
+    }
+}
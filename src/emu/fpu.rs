@@ -0,0 +1,646 @@
+use bitflags::bitflags;
+
+bitflags!
+{ // Accrued floating point exceptions (fflags).
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct FloatFlags: u32
+    {
+        const NX = 0b00001; // Inexact.
+        const UF = 0b00010; // Underflow.
+        const OF = 0b00100; // Overflow.
+        const DZ = 0b01000; // Divide by zero.
+        const NV = 0b10000; // Invalid operation.
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Precision
+{
+    Single,
+    Double
+}
+
+impl Precision
+{
+    fn exponent_bits(self) -> i32
+    {
+        match self
+        {
+            Precision::Single => 8,
+            Precision::Double => 11
+        }
+    }
+
+    fn fraction_bits(self) -> i32
+    {
+        match self
+        {
+            Precision::Single => 23,
+            Precision::Double => 52
+        }
+    }
+
+    fn bias(self) -> i32
+    {
+        (1 << (self.exponent_bits() - 1)) - 1
+    }
+
+    fn sign_bit(self) -> u64
+    {
+        1 << (self.exponent_bits() + self.fraction_bits())
+    }
+
+    // Single precision values are NaN-boxed in the upper 32 bits of 64-bit registers.
+    pub fn boxed(self, bits: u64) -> u64
+    {
+        match self
+        {
+            Precision::Single => bits | 0xFFFF_FFFF_0000_0000,
+            Precision::Double => bits
+        }
+    }
+
+    // Improperly NaN-boxed single precision values read as the canonical NaN.
+    pub fn unboxed(self, bits: u64) -> u64
+    {
+        match self
+        {
+            Precision::Single if bits >> 32 != 0xFFFF_FFFF => self.canonical_nan(),
+            Precision::Single => bits & 0xFFFF_FFFF,
+            Precision::Double => bits
+        }
+    }
+
+    pub fn canonical_nan(self) -> u64
+    {
+        match self
+        {
+            Precision::Single => 0x7FC0_0000,
+            Precision::Double => 0x7FF8_0000_0000_0000
+        }
+    }
+}
+
+// Rounding modes, encoded as in the instruction rm field and frm.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rounding
+{
+    NearestEven         = 0b000,
+    TowardZero          = 0b001,
+    Down                = 0b010,
+    Up                  = 0b011,
+    NearestMaxMagnitude = 0b100
+}
+
+impl TryFrom<u8> for Rounding
+{
+    type Error = u8;
+
+    fn try_from(rm: u8) -> Result<Self, Self::Error>
+    {
+        match rm
+        {
+            0b000 => Ok(Rounding::NearestEven),
+            0b001 => Ok(Rounding::TowardZero),
+            0b010 => Ok(Rounding::Down),
+            0b011 => Ok(Rounding::Up),
+            0b100 => Ok(Rounding::NearestMaxMagnitude),
+            _ => Err(rm)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value
+{
+    Zero(bool /* Sign */),
+    Finite(bool /* Sign */, i32 /* Exponent */, u128 /* Significand */), // (-1)^sign * significand * 2^exponent.
+    Infinity(bool /* Sign */),
+    NaN(bool /* Signaling */)
+}
+
+// Correctly rounded IEEE 754 arithmetic on register bit patterns, results use RISC-V's canonical NaN.
+pub struct Fpu
+{
+    pub precision: Precision,
+    pub rounding: Rounding,
+    pub flags: FloatFlags
+}
+
+impl Fpu
+{
+    pub fn new(precision: Precision, rounding: Rounding) -> Self
+    {
+        Fpu{ precision, rounding, flags: FloatFlags::empty() }
+    }
+
+    // Addition, subtraction, multiplication and division.
+    pub fn arithmetic(&mut self, operation: &str, a: u64, b: u64) -> u64
+    {
+        let (a, b) = (self.unpack(a), self.unpack(b));
+
+        match operation
+        {
+            "fadd" => self.add(a, b),
+            "fsub" => self.add(a, Self::negate(b)),
+            "fmul" => self.multiply(a, b),
+            _      => self.divide(a, b)
+        }
+    }
+
+    // Fused (-)(a * b) (-) c with a single rounding.
+    pub fn fused_multiply_add(&mut self, a: u64, b: u64, c: u64, negate_product: bool, negate_addend: bool) -> u64
+    {
+        let (a, b, mut c) = (self.unpack(a), self.unpack(b), self.unpack(c));
+        if negate_addend
+        {
+            c = Self::negate(c);
+        }
+
+        // Infinity times zero is invalid even when the addend is a quiet NaN.
+        let invalid_product = matches!((a, b), (Value::Infinity(_), Value::Zero(_)) | (Value::Zero(_), Value::Infinity(_)));
+
+        if let Some(nan) = self.propagate_nan(&[a, b, c], invalid_product)
+        {
+            return nan
+        }
+
+        let product = match (a, b)
+        {
+            (Value::Finite(s1, e1, m1), Value::Finite(s2, e2, m2)) => Value::Finite(s1 != s2, e1 + e2, m1 * m2),
+            (Value::Zero(s1) | Value::Finite(s1, ..), Value::Zero(s2) | Value::Finite(s2, ..)) => Value::Zero(s1 != s2),
+            (Value::Zero(s1) | Value::Finite(s1, ..) | Value::Infinity(s1), Value::Zero(s2) | Value::Finite(s2, ..) | Value::Infinity(s2)) =>
+                Value::Infinity(s1 != s2),
+            _ => unreachable!()
+        };
+
+        self.add(if negate_product { Self::negate(product) } else { product }, c)
+    }
+
+    pub fn square_root(&mut self, a: u64) -> u64
+    {
+        match self.unpack(a)
+        {
+            Value::NaN(signaling) => self.nan(signaling),
+            Value::Zero(sign) => self.zero(sign),
+            Value::Infinity(false) => self.infinity(false),
+            Value::Infinity(true) | Value::Finite(true, ..) => self.nan(true),
+            Value::Finite(false, exponent, significand) =>
+            { // Halve an even exponent, keeping enough root bits for rounding.
+                let (exponent, significand) = if exponent % 2 != 0 { (exponent - 1, significand << 1) } else { (exponent, significand) };
+                let shift = (126 - Self::width(significand)) & !1;
+
+                let (root, remainder) = Self::isqrt(significand << shift);
+                self.pack(false, (exponent - shift) / 2, root | remainder as u128)
+            }
+        }
+    }
+
+    // Sign injection ("fsgnj", "fsgnjn" or "fsgnjx"), the magnitude of `a` with a sign derived from `b`.
+    pub fn sign_inject(&self, operation: &str, a: u64, b: u64) -> u64
+    {
+        let (a, b, sign_bit) = (self.precision.unboxed(a), self.precision.unboxed(b), self.precision.sign_bit());
+
+        let sign = match operation
+        {
+            "fsgnj"  => b & sign_bit,
+            "fsgnjn" => !b & sign_bit,
+            _        => (a ^ b) & sign_bit
+        };
+        self.precision.boxed((a & !sign_bit) | sign)
+    }
+
+    // Minimum or maximum, quiet NaNs are ignored in favour of the other operand and -0 orders below +0.
+    pub fn min_max(&mut self, a: u64, b: u64, maximum: bool) -> u64
+    {
+        let (value_a, value_b) = (self.unpack(a), self.unpack(b));
+
+        if matches!(value_a, Value::NaN(true)) || matches!(value_b, Value::NaN(true))
+        {
+            self.flags |= FloatFlags::NV;
+        }
+
+        let (a, b) = (self.precision.unboxed(a), self.precision.unboxed(b));
+
+        match (value_a, value_b)
+        {
+            (Value::NaN(_), Value::NaN(_)) => self.precision.boxed(self.precision.canonical_nan()),
+            (Value::NaN(_), _) => self.precision.boxed(b),
+            (_, Value::NaN(_)) => self.precision.boxed(a),
+            _ =>
+            {
+                let (key_a, key_b) = (self.key(a), self.key(b));
+                let pick_a = if key_a == key_b { (a & self.precision.sign_bit() != 0) != maximum } else { (key_a < key_b) != maximum };
+
+                self.precision.boxed(if pick_a { a } else { b })
+            }
+        }
+    }
+
+    // Quiet equality ("feq") or signaling ordered comparisons ("flt", "fle"), false when unordered.
+    pub fn compare(&mut self, operation: &str, a: u64, b: u64) -> bool
+    {
+        let (value_a, value_b) = (self.unpack(a), self.unpack(b));
+
+        if matches!(value_a, Value::NaN(_)) || matches!(value_b, Value::NaN(_))
+        {
+            if operation != "feq" || matches!(value_a, Value::NaN(true)) || matches!(value_b, Value::NaN(true))
+            {
+                self.flags |= FloatFlags::NV;
+            }
+            return false
+        }
+
+        let (key_a, key_b) = (self.key(self.precision.unboxed(a)), self.key(self.precision.unboxed(b)));
+
+        match operation
+        {
+            "feq" => key_a == key_b,
+            "flt" => key_a < key_b,
+            _     => key_a <= key_b
+        }
+    }
+
+    // One-hot class mask as written by "fclass".
+    pub fn classify(&self, a: u64) -> u64
+    {
+        let subnormal = |significand: u128| significand >> self.precision.fraction_bits() == 0;
+
+        1 << match self.unpack(a)
+        {
+            Value::Infinity(true) => 0,
+            Value::Finite(true, _, significand) if !subnormal(significand) => 1,
+            Value::Finite(true, ..) => 2,
+            Value::Zero(true) => 3,
+            Value::Zero(false) => 4,
+            Value::Finite(false, _, significand) if subnormal(significand) => 5,
+            Value::Finite(false, ..) => 6,
+            Value::Infinity(false) => 7,
+            Value::NaN(true) => 8,
+            Value::NaN(false) => 9
+        }
+    }
+
+    // Converts to a `width` bit integer, saturating out of range values and NaNs. 32-bit results are sign-extended.
+    pub fn to_integer(&mut self, a: u64, signed: bool, width: u32) -> u64
+    {
+        let (min, max): (i128, i128) = match signed
+        {
+            true  => (-(1 << (width - 1)), (1 << (width - 1)) - 1),
+            false => (0, (1 << width) - 1)
+        };
+
+        let integer = match self.unpack(a)
+        {
+            Value::Zero(_) => Ok(0),
+            Value::NaN(_) | Value::Infinity(false) => Err(max),
+            Value::Infinity(true) => Err(min),
+            Value::Finite(sign, exponent, significand) =>
+            {
+                let (magnitude, inexact) = match exponent
+                {
+                    _ if exponent > 64 => (u128::MAX >> 1, false),
+                    0.. => (significand << exponent, false),
+                    _ => self.round(sign, significand, -exponent)
+                };
+
+                let integer = if sign { -(magnitude as i128) } else { magnitude as i128 };
+
+                if integer < min || integer > max
+                {
+                    Err(if sign { min } else { max })
+                }
+                else
+                {
+                    if inexact
+                    {
+                        self.flags |= FloatFlags::NX;
+                    }
+                    Ok(integer)
+                }
+            }
+        };
+
+        // Out of range conversions are invalid, but not inexact.
+        let integer = integer.unwrap_or_else(|saturated|
+        {
+            self.flags |= FloatFlags::NV;
+            saturated
+        });
+
+        match width
+        {
+            32 => integer as i32 as i64 as u64,
+            _  => integer as u64
+        }
+    }
+
+    // Converts the low `width` bits of `value` to floating point.
+    pub fn from_integer(&mut self, value: u64, signed: bool, width: u32) -> u64
+    {
+        let integer = match (signed, width)
+        {
+            (true, 32)  => value as i32 as i128,
+            (false, 32) => value as u32 as i128,
+            (true, _)   => value as i64 as i128,
+            (false, _)  => value as i128
+        };
+
+        match integer
+        {
+            0 => self.zero(false),
+            _ => self.pack(integer < 0, 0, integer.unsigned_abs())
+        }
+    }
+
+    // Converts `a` from the `source` precision.
+    pub fn convert(&mut self, a: u64, source: Precision) -> u64
+    {
+        match Self::unpack_as(source, a)
+        {
+            Value::NaN(signaling) => self.nan(signaling),
+            Value::Infinity(sign) => self.infinity(sign),
+            Value::Zero(sign) => self.zero(sign),
+            Value::Finite(sign, exponent, significand) => self.pack(sign, exponent, significand)
+        }
+    }
+
+    fn add(&mut self, a: Value, b: Value) -> u64
+    {
+        if let Some(nan) = self.propagate_nan(&[a, b], false)
+        {
+            return nan
+        }
+
+        match (a, b)
+        {
+            (Value::Infinity(s1), Value::Infinity(s2)) if s1 != s2 => self.nan(true),
+            (Value::Infinity(sign), _) | (_, Value::Infinity(sign)) => self.infinity(sign),
+            (Value::Zero(s1), Value::Zero(s2)) => self.zero(if s1 == s2 { s1 } else { self.rounding == Rounding::Down }),
+            (Value::Zero(_), Value::Finite(sign, exponent, significand)) | (Value::Finite(sign, exponent, significand), Value::Zero(_)) =>
+                self.pack(sign, exponent, significand),
+            (Value::Finite(s1, e1, m1), Value::Finite(s2, e2, m2)) =>
+            { // Align the smaller operand, bits shifted out stick to it's lowest bit.
+                let ((s1, e1, m1), (s2, e2, m2)) = (Self::normalize(s1, e1, m1), Self::normalize(s2, e2, m2));
+                let ((big_sign, exponent, big), (small_sign, small)) = match e1 >= e2
+                {
+                    true  => ((s1, e1, m1), (s2, Self::jam(m2, e1 - e2))),
+                    false => ((s2, e2, m2), (s1, Self::jam(m1, e2 - e1)))
+                };
+
+                match big_sign == small_sign
+                {
+                    true => self.pack(big_sign, exponent, big + small),
+                    false if big == small => self.zero(self.rounding == Rounding::Down),
+                    false if big > small => self.pack(big_sign, exponent, big - small),
+                    false => self.pack(small_sign, exponent, small - big)
+                }
+            },
+            _ => unreachable!()
+        }
+    }
+
+    fn multiply(&mut self, a: Value, b: Value) -> u64
+    {
+        match (a, b)
+        {
+            (Value::NaN(_), _) | (_, Value::NaN(_)) => self.propagate_nan(&[a, b], false).unwrap(),
+            (Value::Infinity(_), Value::Zero(_)) | (Value::Zero(_), Value::Infinity(_)) => self.nan(true),
+            (Value::Infinity(s1) | Value::Finite(s1, ..), Value::Infinity(s2)) | (Value::Infinity(s1), Value::Finite(s2, ..)) => self.infinity(s1 != s2),
+            (Value::Zero(s1) | Value::Finite(s1, ..), Value::Zero(s2)) | (Value::Zero(s1), Value::Finite(s2, ..)) => self.zero(s1 != s2),
+            (Value::Finite(s1, e1, m1), Value::Finite(s2, e2, m2)) => self.pack(s1 != s2, e1 + e2, m1 * m2)
+        }
+    }
+
+    fn divide(&mut self, a: Value, b: Value) -> u64
+    {
+        match (a, b)
+        {
+            (Value::NaN(_), _) | (_, Value::NaN(_)) => self.propagate_nan(&[a, b], false).unwrap(),
+            (Value::Infinity(_), Value::Infinity(_)) | (Value::Zero(_), Value::Zero(_)) => self.nan(true),
+            (Value::Infinity(s1), Value::Zero(s2) | Value::Finite(s2, ..)) => self.infinity(s1 != s2),
+            (Value::Finite(s1, ..), Value::Zero(s2)) =>
+            {
+                self.flags |= FloatFlags::DZ;
+                self.infinity(s1 != s2)
+            },
+            (Value::Zero(s1) | Value::Finite(s1, ..), Value::Infinity(s2)) | (Value::Zero(s1), Value::Finite(s2, ..)) => self.zero(s1 != s2),
+            (Value::Finite(s1, e1, m1), Value::Finite(s2, e2, m2)) =>
+            { // Widen the dividend so the quotient carries enough bits for rounding.
+                let shift = 126 - Self::width(m1);
+                let dividend = m1 << shift;
+
+                self.pack(s1 != s2, e1 - shift - e2, (dividend / m2) | (dividend % m2 != 0) as u128)
+            }
+        }
+    }
+
+    // Canonical NaN when any operand is a NaN (or `invalid`), raising NV for signaling NaNs.
+    fn propagate_nan(&mut self, values: &[Value], invalid: bool) -> Option<u64>
+    {
+        let signaling = values.iter().any(|value| matches!(value, Value::NaN(true)));
+
+        if invalid || values.iter().any(|value| matches!(value, Value::NaN(_)))
+        {
+            return Some(self.nan(invalid || signaling))
+        }
+        None
+    }
+
+    fn negate(value: Value) -> Value
+    {
+        match value
+        {
+            Value::Zero(sign) => Value::Zero(!sign),
+            Value::Finite(sign, exponent, significand) => Value::Finite(!sign, exponent, significand),
+            Value::Infinity(sign) => Value::Infinity(!sign),
+            nan => nan
+        }
+    }
+
+    fn unpack(&self, bits: u64) -> Value
+    {
+        Self::unpack_as(self.precision, bits)
+    }
+
+    fn unpack_as(precision: Precision, bits: u64) -> Value
+    {
+        let bits = precision.unboxed(bits);
+        let fraction_bits = precision.fraction_bits();
+
+        let sign = bits & precision.sign_bit() != 0;
+        let biased = ((bits >> fraction_bits) & ((1 << precision.exponent_bits()) - 1)) as i32;
+        let fraction = (bits & ((1 << fraction_bits) - 1)) as u128;
+
+        match biased
+        {
+            0 if fraction == 0 => Value::Zero(sign),
+            0 => Value::Finite(sign, 1 - precision.bias() - fraction_bits, fraction),
+            _ if biased == (1 << precision.exponent_bits()) - 1 => match fraction
+            {
+                0 => Value::Infinity(sign),
+                _ => Value::NaN(fraction >> (fraction_bits - 1) == 0)
+            },
+            _ => Value::Finite(sign, biased - precision.bias() - fraction_bits, fraction | 1 << fraction_bits)
+        }
+    }
+
+    // Rounds `significand` * 2^`exponent` to the destination precision, raising NX, UF and OF as needed.
+    fn pack(&mut self, sign: bool, exponent: i32, significand: u128) -> u64
+    {
+        let (fraction_bits, bias) = (self.precision.fraction_bits(), self.precision.bias());
+        let (minimum, maximum) = (1 - bias, (1 << self.precision.exponent_bits()) - 2 - bias);
+
+        let top = exponent + Self::width(significand) - 1;
+        let mut quantum = (top - fraction_bits).max(minimum - fraction_bits);
+
+        let (mut kept, inexact) = self.round(sign, significand, quantum - exponent);
+
+        // Tininess is detected after rounding, as if the exponent range were unbounded.
+        if top < minimum && inexact
+        {
+            let (unbounded, _) = self.round(sign, significand, top - fraction_bits - exponent);
+            if top < minimum - 1 || unbounded >> (fraction_bits + 1) == 0
+            {
+                self.flags |= FloatFlags::UF;
+            }
+        }
+
+        if inexact
+        {
+            self.flags |= FloatFlags::NX;
+        }
+
+        if kept >> (fraction_bits + 1) != 0
+        {
+            kept >>= 1;
+            quantum += 1;
+        }
+
+        let biased = if kept >> fraction_bits == 0 { 0 } else { quantum + fraction_bits + bias };
+
+        if biased > maximum + bias
+        {
+            self.flags |= FloatFlags::OF | FloatFlags::NX;
+
+            return match self.rounding
+            {
+                Rounding::NearestEven | Rounding::NearestMaxMagnitude => self.infinity(sign),
+                Rounding::Down if sign => self.infinity(sign),
+                Rounding::Up if !sign => self.infinity(sign),
+                _ => self.encode(sign, maximum + bias, u64::MAX)
+            }
+        }
+        self.encode(sign, biased, kept as u64)
+    }
+
+    // Drops `shift` low bits of `significand` under the current rounding mode, returning the result and whether it's inexact.
+    fn round(&self, sign: bool, significand: u128, shift: i32) -> (u128, bool)
+    {
+        if shift <= 0
+        {
+            return (significand << -shift, false)
+        }
+
+        let (kept, half, rest) = match shift
+        {
+            129.. => (0, false, significand != 0),
+            128 => (0, significand >> 127 != 0, significand << 1 != 0),
+            _ => (significand >> shift, (significand >> (shift - 1)) & 1 != 0, significand & ((1 << (shift - 1)) - 1) != 0)
+        };
+
+        let inexact = half || rest;
+        let increment = match self.rounding
+        {
+            Rounding::NearestEven => half && (rest || kept & 1 != 0),
+            Rounding::TowardZero => false,
+            Rounding::Down => inexact && sign,
+            Rounding::Up => inexact && !sign,
+            Rounding::NearestMaxMagnitude => half
+        };
+        (kept + increment as u128, inexact)
+    }
+
+    // Moves the leading bit of `significand` to bit 125, leaving room for the carry of a sum.
+    fn normalize(sign: bool, exponent: i32, significand: u128) -> (bool, i32, u128)
+    {
+        let shift = significand.leading_zeros() as i32 - 2;
+        (sign, exponent - shift, significand << shift)
+    }
+
+    // Shifts right, keeping any bits shifted out as a sticky lowest bit.
+    fn jam(significand: u128, shift: i32) -> u128
+    {
+        match shift
+        {
+            0 => significand,
+            128.. => (significand != 0) as u128,
+            _ => significand >> shift | (significand & ((1 << shift) - 1) != 0) as u128
+        }
+    }
+
+    // Integer square root and whether a remainder was left.
+    fn isqrt(value: u128) -> (u128, bool)
+    {
+        let (mut remainder, mut root, mut bit) = (value, 0u128, 1u128 << 126);
+
+        while bit > remainder
+        {
+            bit >>= 2;
+        }
+
+        while bit != 0
+        {
+            if remainder >= root + bit
+            {
+                remainder -= root + bit;
+                root = (root >> 1) + bit;
+            }
+            else
+            {
+                root >>= 1;
+            }
+            bit >>= 2;
+        }
+        (root, remainder != 0)
+    }
+
+    fn width(significand: u128) -> i32
+    {
+        128 - significand.leading_zeros() as i32
+    }
+
+    // Sign-magnitude key that orders non-NaN values, with both zeros equal.
+    fn key(&self, bits: u64) -> i128
+    {
+        let magnitude = (bits & !self.precision.sign_bit()) as i128;
+        if bits & self.precision.sign_bit() != 0 { -magnitude } else { magnitude }
+    }
+
+    fn nan(&mut self, invalid: bool) -> u64
+    {
+        if invalid
+        {
+            self.flags |= FloatFlags::NV;
+        }
+        self.precision.boxed(self.precision.canonical_nan())
+    }
+
+    fn infinity(&self, sign: bool) -> u64
+    {
+        self.encode(sign, (1 << self.precision.exponent_bits()) - 1, 0)
+    }
+
+    fn zero(&self, sign: bool) -> u64
+    {
+        self.encode(sign, 0, 0)
+    }
+
+    fn encode(&self, sign: bool, biased: i32, fraction: u64) -> u64
+    {
+        let fraction_bits = self.precision.fraction_bits();
+        let bits = if sign { self.precision.sign_bit() } else { 0 } | (biased as u64) << fraction_bits | (fraction & ((1 << fraction_bits) - 1));
+
+        self.precision.boxed(bits)
+    }
+}
@@ -0,0 +1,888 @@
+use crate::{
+    lexer::*, arch::*,
+    codec::dec::*,
+    mmu::*, mem::*
+};
+use super::{
+    muldiv::*, amo::*, fpu::*, csr::*, tlb::*
+};
+
+pub use crate::arch::Xlen;
+
+// Privilege levels, encoded as in the mstatus.MPP field.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Privilege
+{
+    User       = 0b00,
+    Supervisor = 0b01,
+    Machine    = 0b11
+}
+
+impl Privilege
+{
+    fn from_bits(bits: u64) -> Self
+    {
+        match bits & 0b11
+        {
+            0b00 => Privilege::User,
+            0b01 => Privilege::Supervisor,
+            _    => Privilege::Machine
+        }
+    }
+}
+
+// Synchronous exceptions raised while executing an instruction, carrying the faulting address or instruction,
+// and the asynchronous interrupts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trap
+{
+    InstructionAddressMisaligned(u64),
+    InstructionAccessFault(u64),
+    IllegalInstruction(u32),
+    Breakpoint(u64),
+    LoadAddressMisaligned(u64),
+    LoadAccessFault(u64),
+    StoreAddressMisaligned(u64),
+    StoreAccessFault(u64),
+    EnvironmentCall,
+    InstructionPageFault(u64),
+    LoadPageFault(u64),
+    StorePageFault(u64),
+    SupervisorSoftwareInterrupt,
+    MachineSoftwareInterrupt,
+    SupervisorTimerInterrupt,
+    MachineTimerInterrupt,
+    SupervisorExternalInterrupt,
+    MachineExternalInterrupt
+}
+
+impl Trap
+{
+    // Exception or interrupt code reported in mcause/scause, environment calls are distinguished by the calling privilege.
+    pub fn cause(&self, privilege: Privilege) -> (bool /* Interrupt */, u64 /* Code */)
+    {
+        match self
+        {
+            Trap::InstructionAddressMisaligned(_) => (false, 0),
+            Trap::InstructionAccessFault(_)       => (false, 1),
+            Trap::IllegalInstruction(_)           => (false, 2),
+            Trap::Breakpoint(_)                   => (false, 3),
+            Trap::LoadAddressMisaligned(_)        => (false, 4),
+            Trap::LoadAccessFault(_)              => (false, 5),
+            Trap::StoreAddressMisaligned(_)       => (false, 6),
+            Trap::StoreAccessFault(_)             => (false, 7),
+            Trap::EnvironmentCall                 => (false, 8 + privilege as u64),
+            Trap::InstructionPageFault(_)         => (false, 12),
+            Trap::LoadPageFault(_)                => (false, 13),
+            Trap::StorePageFault(_)               => (false, 15),
+            Trap::SupervisorSoftwareInterrupt     => (true, 1),
+            Trap::MachineSoftwareInterrupt        => (true, 3),
+            Trap::SupervisorTimerInterrupt        => (true, 5),
+            Trap::MachineTimerInterrupt           => (true, 7),
+            Trap::SupervisorExternalInterrupt     => (true, 9),
+            Trap::MachineExternalInterrupt        => (true, 11)
+        }
+    }
+
+    // Trap value reported in mtval/stval, the faulting address or instruction and zero otherwise.
+    pub fn value(&self) -> u64
+    {
+        match self
+        {
+            Trap::IllegalInstruction(binary) => *binary as u64,
+            Trap::InstructionAddressMisaligned(address) | Trap::InstructionAccessFault(address) | Trap::Breakpoint(address) |
+            Trap::LoadAddressMisaligned(address) | Trap::LoadAccessFault(address) |
+            Trap::StoreAddressMisaligned(address) | Trap::StoreAccessFault(address) |
+            Trap::InstructionPageFault(address) | Trap::LoadPageFault(address) | Trap::StorePageFault(address) => *address,
+            _ => 0
+        }
+    }
+}
+
+pub struct Hart
+{
+    pub id: usize,
+    pub xlen: Xlen,
+    pub x: [u64; 32],
+    pub f: [u64; 32],
+    pub csrs: CsrFile,
+    pub privilege: Privilege,
+    pub tlb: Tlb,
+    pub pc: u64,
+    pub waiting: bool // Stalled by "wfi" until an interrupt is pending.
+}
+
+impl Hart
+{
+    pub fn new(id: usize, xlen: Xlen) -> Self
+    {
+        Hart{ id, xlen, x: [0; 32], f: [0; 32], csrs: CsrFile::new(id as u64, xlen), privilege: Privilege::Machine, tlb: Tlb::new(TLB_ENTRIES), pc: 0, waiting: false }
+    }
+
+    // Fetches, decodes and executes a single instruction; `pc` is left at the faulting instruction on a trap.
+    pub fn step(&mut self, mmu: &mut MMU) -> Result<(), Trap>
+    {
+        // Cycles elapse whether or not the instruction retires.
+        self.csrs.cycle = self.csrs.cycle.wrapping_add(1);
+        self.csrs.time = self.time(mmu);
+
+        let binary = self.fetch(mmu)?;
+
+        let decoder = Decoder::new(binary)
+            .map_err(|_| Trap::IllegalInstruction(binary))?;
+
+        let instruction = &RV_ISA[decoder.mnemonic.as_str()];
+        if !self.implements(&instruction.isa)
+        {
+            return Err(Trap::IllegalInstruction(binary))
+        }
+
+        self.pc = match instruction.isa
+        {
+            ISA::RV32F | ISA::RV64F | ISA::RV32D | ISA::RV64D => self.execute_float(mmu, binary, instruction, &decoder.mnemonic, &decoder.operands)?,
+            _ => self.execute(mmu, binary, &decoder.mnemonic, &decoder.operands)?
+        };
+
+        self.csrs.instret = self.csrs.instret.wrapping_add(1);
+        Ok(())
+    }
+
+    // Platform timer when a device provides one, otherwise time advances with every cycle.
+    fn time(&self, mmu: &MMU) -> u64
+    {
+        mmu.time().unwrap_or(self.csrs.time.wrapping_add(1))
+    }
+
+    // Steps until an instruction traps.
+    pub fn run(&mut self, mmu: &mut MMU) -> Trap
+    {
+        loop
+        {
+            if let Err(trap) = self.step(mmu)
+            {
+                return trap
+            }
+        }
+    }
+
+    // Steps with trap handling, exceptions and enabled interrupts enter the handler at mtvec or stvec when delegated.
+    pub fn tick(&mut self, mmu: &mut MMU)
+    {
+        // Interrupt lines are sampled from the devices on every tick.
+        self.csrs.lines = mmu.interrupts(self.id);
+
+        // Pending interrupts wake a waiting hart even when they are globally disabled.
+        let pending = self.csrs.pending() & self.csrs.mie;
+        if self.waiting && pending == 0
+        {
+            self.csrs.cycle = self.csrs.cycle.wrapping_add(1);
+            self.csrs.time = self.time(mmu);
+            return
+        }
+        self.waiting = false;
+
+        if let Some(interrupt) = self.interrupt()
+        {
+            return self.trap(&interrupt)
+        }
+
+        if let Err(trap) = self.step(mmu)
+        {
+            self.trap(&trap);
+        }
+    }
+
+    // Highest priority interrupt that is pending, enabled and not masked at the current privilege.
+    // Interrupts for a more privileged mode are always taken, delegated interrupts never preempt machine mode.
+    pub fn interrupt(&self) -> Option<Trap>
+    {
+        let pending = self.csrs.pending() & self.csrs.mie;
+        let status = self.csrs.mstatus;
+
+        let machine = match self.privilege
+        {
+            Privilege::Machine => status & MSTATUS_MIE != 0,
+            _ => true
+        };
+        let supervisor = match self.privilege
+        {
+            Privilege::Machine => false,
+            Privilege::Supervisor => status & MSTATUS_SIE != 0,
+            Privilege::User => true
+        };
+
+        let enabled = (if machine { pending & !self.csrs.mideleg } else { 0 })
+            | (if supervisor { pending & self.csrs.mideleg } else { 0 });
+
+        [
+            (MEI, Trap::MachineExternalInterrupt), (MSI, Trap::MachineSoftwareInterrupt), (MTI, Trap::MachineTimerInterrupt),
+            (SEI, Trap::SupervisorExternalInterrupt), (SSI, Trap::SupervisorSoftwareInterrupt), (STI, Trap::SupervisorTimerInterrupt)
+        ]
+            .into_iter()
+            .find(|(bit, _)| enabled & bit != 0)
+            .map(|(_, interrupt)| interrupt)
+    }
+
+    // Enters the trap handler, supervisor mode handles traps delegated by medeleg/mideleg that don't occur in machine mode.
+    // Interrupts are vectored to the base plus four times the cause in vectored mode.
+    pub fn trap(&mut self, trap: &Trap)
+    {
+        let (interrupt, code) = trap.cause(self.privilege);
+        let interrupt_bit = if interrupt { 1 << (self.width() - 1) } else { 0 };
+
+        let delegation = if interrupt { self.csrs.mideleg } else { self.csrs.medeleg };
+        let delegated = self.privilege != Privilege::Machine && delegation & (1 << code) != 0;
+
+        let value = self.truncate(trap.value());
+        let status = self.csrs.mstatus;
+
+        // Interrupts are disabled in the handler, the previous enable and privilege are stacked.
+        let vector = if delegated
+        {
+            self.csrs.sepc = self.pc;
+            self.csrs.scause = interrupt_bit | code;
+            self.csrs.stval = value;
+
+            let stacked = if status & MSTATUS_SIE != 0 { MSTATUS_SPIE } else { 0 };
+            let previous = if self.privilege == Privilege::Supervisor { MSTATUS_SPP } else { 0 };
+            self.csrs.mstatus = (status & !(MSTATUS_SIE | MSTATUS_SPIE | MSTATUS_SPP)) | stacked | previous;
+
+            self.privilege = Privilege::Supervisor;
+            self.csrs.stvec
+        }
+        else
+        {
+            self.csrs.mepc = self.pc;
+            self.csrs.mcause = interrupt_bit | code;
+            self.csrs.mtval = value;
+
+            let stacked = if status & MSTATUS_MIE != 0 { MSTATUS_MPIE } else { 0 };
+            let previous = (self.privilege as u64) << 11;
+            self.csrs.mstatus = (status & !(MSTATUS_MIE | MSTATUS_MPIE | MSTATUS_MPP)) | stacked | previous;
+
+            self.privilege = Privilege::Machine;
+            self.csrs.mtvec
+        };
+
+        let base = vector & !0b11;
+        self.pc = match vector & 0b11
+        {
+            1 if interrupt => base.wrapping_add(4 * code),
+            _ => base
+        };
+    }
+
+    fn implements(&self, isa: &ISA) -> bool
+    {
+        match isa
+        {
+            ISA::RV32I | ISA::RV32M | ISA::RV32A | ISA::ZiFencei | ISA::Zicsr | ISA::Priv => true,
+            ISA::RV64I | ISA::RV64M | ISA::RV64A => self.xlen == Xlen::X64,
+            // Floating point instructions are illegal while mstatus.FS is Off.
+            ISA::RV32F | ISA::RV32D => self.csrs.float_enabled(),
+            ISA::RV64F | ISA::RV64D => self.csrs.float_enabled() && self.xlen == Xlen::X64,
+            _ => false
+        }
+    }
+
+    fn fetch(&mut self, mmu: &mut MMU) -> Result<u32, Trap>
+    {
+        self.fetch_at(mmu, self.pc)
+    }
+
+    // Instruction word at the virtual `address`, translated and checked the way instruction fetches are.
+    pub(super) fn fetch_at(&mut self, mmu: &mut MMU, address: u64) -> Result<u32, Trap>
+    {
+        if !address.is_multiple_of(4)
+        {
+            return Err(Trap::InstructionAddressMisaligned(address))
+        }
+
+        let at = self.translate(mmu, address, 4, Access::Fetch)?;
+
+        // Reads also succeed on readable pages, fetches require execute permission.
+        match mmu.query(at)
+        {
+            Some(protection) if protection.contains(Protection::EXECUTE) =>
+                mmu.read::<u32>(at).map_err(|_| Trap::InstructionAccessFault(address)),
+            _ => Err(Trap::InstructionAccessFault(address))
+        }
+    }
+
+    // Physical address of `size` bytes at a virtual `address`, loads and stores use the privilege in MPP when mstatus.MPRV
+    // is set. Both the page table walk and the physical access are checked against the PMP entries.
+    fn translate(&mut self, mmu: &mut MMU, address: u64, size: usize, access: Access) -> Result<Address, Trap>
+    {
+        let privilege = self.access_privilege(access);
+        let translation = self.csrs.translation(privilege);
+
+        let at = self.tlb.translate(mmu, address, access, &translation).map_err(|mmu_err| match (mmu_err, access)
+        {
+            (MMUErr::PageFault(_), Access::Fetch) => Trap::InstructionPageFault(address),
+            (MMUErr::PageFault(_), Access::Load)  => Trap::LoadPageFault(address),
+            (MMUErr::PageFault(_), Access::Store) => Trap::StorePageFault(address),
+            (_, Access::Fetch) => Trap::InstructionAccessFault(address),
+            (_, Access::Load)  => Trap::LoadAccessFault(address),
+            (_, Access::Store) => Trap::StoreAccessFault(address)
+        })?;
+
+        match (translation.pmp.permits(at, size, access, privilege == Privilege::Machine), access)
+        {
+            (true, _) => Ok(at),
+            (false, Access::Fetch) => Err(Trap::InstructionAccessFault(address)),
+            (false, Access::Load)  => Err(Trap::LoadAccessFault(address)),
+            (false, Access::Store) => Err(Trap::StoreAccessFault(address))
+        }
+    }
+
+    // Physical address `translate` would give, found without filling the TLB or setting accessed and dirty bits so that
+    // memory can be observed without disturbing the hart. None wherever the access would trap.
+    pub(super) fn probe(&self, mmu: &MMU, address: u64, size: usize, access: Access) -> Option<Address>
+    {
+        let privilege = self.access_privilege(access);
+        let translation = self.csrs.translation(privilege);
+
+        let at = match translation.paging
+        {
+            Paging::Bare => address as Address,
+            _ => mmu.probe(address, &translation).ok()
+                .filter(|mapping| translation.permits(mapping.flags, access))
+                .map(|mapping| mapping.physical(address))?
+        };
+        translation.pmp.permits(at, size, access, privilege == Privilege::Machine).then_some(at)
+    }
+
+    // Privilege loads and stores are performed at, the one in MPP while mstatus.MPRV is set in machine mode.
+    fn access_privilege(&self, access: Access) -> Privilege
+    {
+        match access
+        {
+            Access::Load | Access::Store if self.privilege == Privilege::Machine && self.csrs.mstatus & MSTATUS_MPRV != 0 =>
+                Privilege::from_bits(self.csrs.mstatus >> 11),
+            _ => self.privilege
+        }
+    }
+
+    // Bytes accessed by a load, store or atomic memory instruction.
+    fn access_size(mnemonic: &str) -> usize
+    {
+        match mnemonic.trim_end_matches('u').chars().last()
+        {
+            Some('b') => 1,
+            Some('h') => 2,
+            Some('w') => 4,
+            _ => 8
+        }
+    }
+
+    fn execute(&mut self, mmu: &mut MMU, binary: u32, mnemonic: &str, operands: &[Operand]) -> Result<u64, Trap>
+    {
+        let args = Operands{ binary, operands };
+        let next_pc = self.truncate(self.pc.wrapping_add(4));
+
+        match mnemonic
+        {
+            "lui" => self.set(args.x(0)?, (args.immediate(1)? << 12) as i32 as i64 as u64),
+            "auipc" => self.set(args.x(0)?, self.pc.wrapping_add((args.immediate(1)? << 12) as i32 as i64 as u64)),
+            "jal" | "jalr" =>
+            {
+                let target = match mnemonic
+                {
+                    "jal" => self.truncate(self.pc.wrapping_add(args.immediate(1)? as u64)),
+                    _ => self.truncate(args.address(1, &self.x)?) & !1
+                };
+
+                if !target.is_multiple_of(4)
+                {
+                    return Err(Trap::InstructionAddressMisaligned(target))
+                }
+
+                self.set(args.x(0)?, next_pc);
+                return Ok(target)
+            },
+            "beq" | "bne" | "blt" | "bge" | "bltu" | "bgeu" =>
+            {
+                let (rs1, rs2) = (self.x[args.x(0)?], self.x[args.x(1)?]);
+
+                let taken = match mnemonic
+                {
+                    "beq"  => rs1 == rs2,
+                    "bne"  => rs1 != rs2,
+                    "blt"  => (rs1 as i64) < (rs2 as i64),
+                    "bge"  => (rs1 as i64) >= (rs2 as i64),
+                    "bltu" => self.truncate(rs1) < self.truncate(rs2),
+                    _      => self.truncate(rs1) >= self.truncate(rs2)
+                };
+
+                if taken
+                {
+                    let target = self.truncate(self.pc.wrapping_add(args.immediate(2)? as u64));
+                    if !target.is_multiple_of(4)
+                    {
+                        return Err(Trap::InstructionAddressMisaligned(target))
+                    }
+                    return Ok(target)
+                }
+            },
+            "lb" | "lh" | "lw" | "ld" | "lbu" | "lhu" | "lwu" =>
+            {
+                let address = self.truncate(args.address(1, &self.x)?);
+                let at = self.translate(mmu, address, Self::access_size(mnemonic), Access::Load)?;
+
+                let value = match mnemonic
+                {
+                    "lb"  => mmu.read::<i8>(at).map(|value| value as i64 as u64),
+                    "lh"  => mmu.read::<i16>(at).map(|value| value as i64 as u64),
+                    "lw"  => mmu.read::<i32>(at).map(|value| value as i64 as u64),
+                    "ld"  => mmu.read::<u64>(at),
+                    "lbu" => mmu.read::<u8>(at).map(|value| value as u64),
+                    "lhu" => mmu.read::<u16>(at).map(|value| value as u64),
+                    _     => mmu.read::<u32>(at).map(|value| value as u64)
+                }.map_err(|mmu_err| Self::load_trap(address, mmu_err))?;
+
+                self.set(args.x(0)?, value);
+            },
+            "sb" | "sh" | "sw" | "sd" =>
+            {
+                let address = self.truncate(args.address(1, &self.x)?);
+                let (at, value) = (self.translate(mmu, address, Self::access_size(mnemonic), Access::Store)?, self.x[args.x(0)?]);
+
+                match mnemonic
+                {
+                    "sb" => mmu.write(at, value as u8),
+                    "sh" => mmu.write(at, value as u16),
+                    "sw" => mmu.write(at, value as u32),
+                    _    => mmu.write(at, value)
+                }.map_err(|mmu_err| Self::store_trap(address, mmu_err))?;
+            },
+            "addi" | "slti" | "sltiu" | "xori" | "ori" | "andi" | "addiw" =>
+            {
+                let (rs1, imm) = (self.x[args.x(1)?], args.immediate(2)? as u64);
+
+                let value = match mnemonic
+                {
+                    "addi"  => rs1.wrapping_add(imm),
+                    "slti"  => ((rs1 as i64) < (imm as i64)) as u64,
+                    "sltiu" => (self.truncate(rs1) < self.truncate(imm)) as u64,
+                    "xori"  => rs1 ^ imm,
+                    "ori"   => rs1 | imm,
+                    "andi"  => rs1 & imm,
+                    _       => rs1.wrapping_add(imm) as i32 as i64 as u64
+                };
+                self.set(args.x(0)?, value);
+            },
+            "slli" | "srli" | "srai" | "slliw" | "srliw" | "sraiw" =>
+            {
+                let shamt = args.immediate(2)? as u32;
+
+                // RV32I reserves shift amounts with bit 5 set.
+                if self.xlen == Xlen::X32 && shamt >= 32
+                {
+                    return Err(args.illegal())
+                }
+
+                let value = self.shift(&mnemonic.replacen('i', "", 1), self.x[args.x(1)?], shamt);
+                self.set(args.x(0)?, value);
+            },
+            "add" | "sub" | "sll" | "slt" | "sltu" | "xor" | "srl" | "sra" | "or" | "and" |
+            "addw" | "subw" | "sllw" | "srlw" | "sraw" =>
+            {
+                let (rs1, rs2) = (self.x[args.x(1)?], self.x[args.x(2)?]);
+
+                let value = match mnemonic
+                {
+                    "add"  => rs1.wrapping_add(rs2),
+                    "sub"  => rs1.wrapping_sub(rs2),
+                    "slt"  => ((rs1 as i64) < (rs2 as i64)) as u64,
+                    "sltu" => (self.truncate(rs1) < self.truncate(rs2)) as u64,
+                    "xor"  => rs1 ^ rs2,
+                    "or"   => rs1 | rs2,
+                    "and"  => rs1 & rs2,
+                    "addw" => rs1.wrapping_add(rs2) as i32 as i64 as u64,
+                    "subw" => rs1.wrapping_sub(rs2) as i32 as i64 as u64,
+                    // Only the low log2(XLEN) bits of rs2 select the shift amount.
+                    _ => self.shift(mnemonic, rs1, rs2 as u32)
+                };
+                self.set(args.x(0)?, value);
+            },
+            "mul" | "mulh" | "mulhsu" | "mulhu" | "div" | "divu" | "rem" | "remu" |
+            "mulw" | "divw" | "divuw" | "remw" | "remuw" =>
+            {
+                let value = muldiv(mnemonic, self.xlen, self.x[args.x(1)?], self.x[args.x(2)?]).ok_or_else(|| args.illegal())?;
+                self.set(args.x(0)?, value);
+            },
+            "lr.w" | "lr.d" =>
+            {
+                let address = self.truncate(self.x[args.x(1)?]);
+                let at = self.translate(mmu, address, Self::access_size(mnemonic), Access::Load)?;
+
+                let value = match mnemonic
+                {
+                    "lr.w" => mmu.read::<i32>(at).map(|value| value as i64 as u64),
+                    _      => mmu.read::<u64>(at)
+                }.map_err(|mmu_err| Self::load_trap(address, mmu_err))?;
+
+                mmu.reserve(self.id, at);
+                self.set(args.x(0)?, value);
+            },
+            "sc.w" | "sc.d" =>
+            {
+                let address = self.truncate(self.x[args.x(1)?]);
+                let (at, value) = (self.translate(mmu, address, Self::access_size(mnemonic), Access::Store)?, self.x[args.x(2)?]);
+
+                // Misaligned addresses trap whether or not the reservation is still held.
+                if !address.is_multiple_of(if mnemonic == "sc.w" { 4 } else { 8 })
+                {
+                    return Err(Trap::StoreAddressMisaligned(address))
+                }
+
+                // Zero signals success, the reservation is released either way.
+                let held = mmu.release(self.id, at);
+                if held
+                {
+                    match mnemonic
+                    {
+                        "sc.w" => mmu.write(at, value as u32),
+                        _      => mmu.write(at, value)
+                    }.map_err(|mmu_err| Self::store_trap(address, mmu_err))?;
+                }
+                self.set(args.x(0)?, !held as u64);
+            },
+            _ if mnemonic.starts_with("amo") =>
+            { // The read-modify-write completes within a single step, no other hart can interleave.
+                let address = self.truncate(self.x[args.x(1)?]);
+                let (at, operand) = (self.translate(mmu, address, Self::access_size(mnemonic), Access::Store)?, self.x[args.x(2)?]);
+
+                // Faults are reported as store/AMO exceptions, including those of the load.
+                let trap = |mmu_err| Self::store_trap(address, mmu_err);
+
+                let word = mnemonic.ends_with(".w");
+
+                let loaded = if word { mmu.read::<i32>(at).map(|value| value as i64 as u64) } else { mmu.read::<u64>(at) }
+                    .map_err(trap)?;
+
+                let value = amo(mnemonic, loaded, operand).ok_or_else(|| args.illegal())?;
+                if word { mmu.write(at, value as u32) } else { mmu.write(at, value) }
+                    .map_err(trap)?;
+                self.set(args.x(0)?, loaded);
+            },
+            "csrrw" | "csrrs" | "csrrc" | "csrrwi" | "csrrsi" | "csrrci" =>
+            {
+                let csr = args.immediate(1)? as u16;
+                let (source, value) = match mnemonic.ends_with('i')
+                {
+                    true  => (args.immediate(2)? as usize, args.immediate(2)? as u64),
+                    false => (args.x(2)?, self.x[args.x(2)?])
+                };
+
+                let old = self.csrs.read(csr, self.privilege).ok_or_else(|| args.illegal())?;
+
+                // Set and clear don't write when the source is x0 or a zero immediate, so read-only CSRs can be read.
+                match &mnemonic[..5]
+                {
+                    "csrrw" => self.csrs.write(csr, value, self.privilege),
+                    "csrrs" if source != 0 => self.csrs.write(csr, old | value, self.privilege),
+                    "csrrc" if source != 0 => self.csrs.write(csr, old & !value, self.privilege),
+                    _ => Some(())
+                }.ok_or_else(|| args.illegal())?;
+
+                self.set(args.x(0)?, old);
+            },
+            "mret" | "sret" =>
+            { // Restores the stacked interrupt enable and privilege, MPRV only applies when returning to machine mode.
+                let status = self.csrs.mstatus;
+
+                let (target, epc) = match mnemonic
+                {
+                    "mret" if self.privilege == Privilege::Machine =>
+                    {
+                        let stacked = if status & MSTATUS_MPIE != 0 { MSTATUS_MIE } else { 0 };
+                        self.csrs.mstatus = (status & !(MSTATUS_MIE | MSTATUS_MPP)) | MSTATUS_MPIE | stacked;
+                        (Privilege::from_bits(status >> 11), self.csrs.mepc)
+                    },
+                    "sret" if self.privilege == Privilege::Machine
+                        || (self.privilege == Privilege::Supervisor && status & MSTATUS_TSR == 0) =>
+                    {
+                        let stacked = if status & MSTATUS_SPIE != 0 { MSTATUS_SIE } else { 0 };
+                        self.csrs.mstatus = (status & !(MSTATUS_SIE | MSTATUS_SPP)) | MSTATUS_SPIE | stacked;
+                        (if status & MSTATUS_SPP != 0 { Privilege::Supervisor } else { Privilege::User }, self.csrs.sepc)
+                    },
+                    _ => return Err(args.illegal())
+                };
+
+                if target != Privilege::Machine
+                {
+                    self.csrs.mstatus &= !MSTATUS_MPRV;
+                }
+                self.privilege = target;
+                return Ok(self.truncate(epc))
+            },
+            "wfi" =>
+            { // Supervisor mode may only wait when not trapped by TW, user mode never.
+                match self.privilege
+                {
+                    Privilege::Machine => (),
+                    Privilege::Supervisor if self.csrs.mstatus & MSTATUS_TW == 0 => (),
+                    _ => return Err(args.illegal())
+                }
+                self.waiting = true;
+            },
+            "sfence.vma" =>
+            { // Flushes the translations of the address in rs1 and the address space in rs2, x0 selects all of them.
+                match self.privilege
+                {
+                    Privilege::Machine => (),
+                    Privilege::Supervisor if self.csrs.mstatus & MSTATUS_TVM == 0 => (),
+                    _ => return Err(args.illegal())
+                }
+
+                let (rs1, rs2) = (args.x(0)?, args.x(1)?);
+                let address = (rs1 != 0).then(|| self.truncate(self.x[rs1]));
+                let asid = (rs2 != 0).then(|| self.x[rs2] as u16);
+
+                self.tlb.flush(address, asid);
+            },
+            "fence" | "fence.i" => (), // Accesses are performed in program order by a single hart.
+            "ecall" => return Err(Trap::EnvironmentCall),
+            "ebreak" => return Err(Trap::Breakpoint(self.pc)),
+            _ => return Err(args.illegal())
+        }
+        Ok(next_pc)
+    }
+
+    fn execute_float(&mut self, mmu: &mut MMU, binary: u32, instruction: &Instruction, mnemonic: &str, operands: &[Operand]) -> Result<u64, Trap>
+    {
+        let args = Operands{ binary, operands };
+        let next_pc = self.truncate(self.pc.wrapping_add(4));
+
+        let precision = match instruction.float_format
+        {
+            Some(FloatFormat::Double) => Precision::Double,
+            _ => Precision::Single
+        };
+
+        // A static rounding mode overrides frm, invalid frm values are only illegal for instructions that round.
+        let rm = args.rounding().unwrap_or((self.csrs.fcsr >> 5) as u8 & 0b111);
+        let rounding = match Rounding::try_from(rm)
+        {
+            Ok(rounding) => rounding,
+            Err(_) if instruction.operands.contains(&OperandType::RoundingMode) => return Err(args.illegal()),
+            Err(_) => Rounding::NearestEven
+        };
+
+        let mut fpu = Fpu::new(precision, rounding);
+        let (operation, conversion) = mnemonic.split_once('.').unwrap_or((mnemonic, ""));
+
+        match operation
+        {
+            "flw" | "fld" =>
+            {
+                let address = self.truncate(args.address(1, &self.x)?);
+                let at = self.translate(mmu, address, Self::access_size(mnemonic), Access::Load)?;
+
+                self.f[args.f(0)?] = match mnemonic
+                {
+                    "flw" => mmu.read::<u32>(at).map(|value| Precision::Single.boxed(value as u64)),
+                    _     => mmu.read::<u64>(at)
+                }.map_err(|mmu_err| Self::load_trap(address, mmu_err))?;
+            },
+            "fsw" | "fsd" =>
+            { // Stores copy the raw register bits, NaN-boxing is not checked.
+                let address = self.truncate(args.address(1, &self.x)?);
+                let (at, value) = (self.translate(mmu, address, Self::access_size(mnemonic), Access::Store)?, self.f[args.f(0)?]);
+
+                match mnemonic
+                {
+                    "fsw" => mmu.write(at, value as u32),
+                    _     => mmu.write(at, value)
+                }.map_err(|mmu_err| Self::store_trap(address, mmu_err))?;
+            },
+            "fadd" | "fsub" | "fmul" | "fdiv" =>
+                self.f[args.f(0)?] = fpu.arithmetic(operation, self.f[args.f(1)?], self.f[args.f(2)?]),
+            "fsqrt" => self.f[args.f(0)?] = fpu.square_root(self.f[args.f(1)?]),
+            "fmadd" | "fmsub" | "fnmadd" | "fnmsub" =>
+            {
+                let (a, b, c) = (self.f[args.f(1)?], self.f[args.f(2)?], self.f[args.f(3)?]);
+                let negate_addend = operation == "fmsub" || operation == "fnmadd";
+
+                self.f[args.f(0)?] = fpu.fused_multiply_add(a, b, c, operation.starts_with("fnm"), negate_addend);
+            },
+            "fsgnj" | "fsgnjn" | "fsgnjx" =>
+                self.f[args.f(0)?] = fpu.sign_inject(operation, self.f[args.f(1)?], self.f[args.f(2)?]),
+            "fmin" | "fmax" =>
+                self.f[args.f(0)?] = fpu.min_max(self.f[args.f(1)?], self.f[args.f(2)?], operation == "fmax"),
+            "feq" | "flt" | "fle" =>
+            {
+                let value = fpu.compare(operation, self.f[args.f(1)?], self.f[args.f(2)?]);
+                self.set(args.x(0)?, value as u64);
+            },
+            "fclass" =>
+            {
+                let value = fpu.classify(self.f[args.f(1)?]);
+                self.set(args.x(0)?, value);
+            },
+            "fmv" => match conversion
+            { // Moves copy bits without inspecting them, "fmv.x.w" sign-extends the low 32 bits.
+                "x.w" => self.set(args.x(0)?, self.f[args.f(1)?] as u32 as i32 as i64 as u64),
+                "w.x" => self.f[args.f(0)?] = Precision::Single.boxed(self.x[args.x(1)?] & 0xFFFF_FFFF),
+                "x.d" => self.set(args.x(0)?, self.f[args.f(1)?]),
+                _     => self.f[args.f(0)?] = self.x[args.x(1)?]
+            },
+            "fcvt" =>
+            {
+                let (destination, source) = conversion.split_once('.').unwrap_or((conversion, ""));
+                let width = |format: &str| if format.starts_with('w') { 32 } else { 64 };
+
+                match (destination, source)
+                {
+                    ("w" | "wu" | "l" | "lu", _) =>
+                    {
+                        let value = fpu.to_integer(self.f[args.f(1)?], !destination.ends_with('u'), width(destination));
+                        self.set(args.x(0)?, value);
+                    },
+                    (_, "w" | "wu" | "l" | "lu") =>
+                        self.f[args.f(0)?] = fpu.from_integer(self.x[args.x(1)?], !source.ends_with('u'), width(source)),
+                    (_, "s") => self.f[args.f(0)?] = fpu.convert(self.f[args.f(1)?], Precision::Single),
+                    (_, "d") => self.f[args.f(0)?] = fpu.convert(self.f[args.f(1)?], Precision::Double),
+                    _ => return Err(args.illegal())
+                }
+            },
+            _ => return Err(args.illegal())
+        }
+
+        self.csrs.fcsr |= fpu.flags.bits();
+        self.csrs.float_dirty();
+        Ok(next_pc)
+    }
+
+    fn load_trap(address: u64, mmu_err: MMUErr) -> Trap
+    {
+        match mmu_err
+        {
+            MMUErr::MisalignedAccess(_) => Trap::LoadAddressMisaligned(address),
+            _ => Trap::LoadAccessFault(address)
+        }
+    }
+
+    fn store_trap(address: u64, mmu_err: MMUErr) -> Trap
+    {
+        match mmu_err
+        {
+            MMUErr::MisalignedAccess(_) => Trap::StoreAddressMisaligned(address),
+            _ => Trap::StoreAccessFault(address)
+        }
+    }
+
+    // Logical and arithmetic shifts, "w" variants operate on the low 32 bits and sign-extend the result.
+    fn shift(&self, mnemonic: &str, value: u64, shamt: u32) -> u64
+    {
+        match (mnemonic, self.xlen)
+        {
+            ("sllw", _) => ((value as u32) << (shamt & 0x1F)) as i32 as i64 as u64,
+            ("srlw", _) => ((value as u32) >> (shamt & 0x1F)) as i32 as i64 as u64,
+            ("sraw", _) => ((value as i32) >> (shamt & 0x1F)) as i64 as u64,
+            ("sll", Xlen::X32) => ((value as u32) << (shamt & 0x1F)) as u64,
+            ("srl", Xlen::X32) => ((value as u32) >> (shamt & 0x1F)) as u64,
+            ("sra", Xlen::X32) => ((value as i32) >> (shamt & 0x1F)) as u64,
+            ("sll", _) => value << (shamt & 0x3F),
+            ("srl", _) => value >> (shamt & 0x3F),
+            _ => ((value as i64) >> (shamt & 0x3F)) as u64
+        }
+    }
+
+    fn width(&self) -> u32
+    {
+        match self.xlen
+        {
+            Xlen::X32 => 32,
+            Xlen::X64 => 64
+        }
+    }
+
+    // Zero-extends the low XLEN bits of `value`, used for addresses and unsigned comparisons.
+    pub fn truncate(&self, value: u64) -> u64
+    {
+        match self.xlen
+        {
+            Xlen::X32 => value & 0xFFFF_FFFF,
+            Xlen::X64 => value
+        }
+    }
+
+    // Writes `rd`, RV32 registers are kept sign-extended to 64 bits and x0 is hardwired to zero.
+    pub fn set(&mut self, rd: usize, value: u64)
+    {
+        if rd != 0
+        {
+            self.x[rd] = match self.xlen
+            {
+                Xlen::X32 => value as i32 as i64 as u64,
+                Xlen::X64 => value
+            };
+        }
+    }
+}
+
+// Operands of the executing instruction, shapes that don't match the expected signature are illegal.
+struct Operands<'a>
+{
+    binary: u32,
+    operands: &'a [Operand]
+}
+
+impl Operands<'_>
+{
+    fn illegal(&self) -> Trap
+    {
+        Trap::IllegalInstruction(self.binary)
+    }
+
+    fn register(&self, index: usize, file: char) -> Result<usize, Trap>
+    {
+        match self.operands.get(index)
+        {
+            Some(Operand::RValue(RValue::Register(prefix, register))) if *prefix == file => Ok(*register as usize),
+            _ => Err(self.illegal())
+        }
+    }
+
+    fn x(&self, index: usize) -> Result<usize, Trap>
+    {
+        self.register(index, 'x')
+    }
+
+    fn f(&self, index: usize) -> Result<usize, Trap>
+    {
+        self.register(index, 'f')
+    }
+
+    fn immediate(&self, index: usize) -> Result<i64, Trap>
+    {
+        match self.operands.get(index)
+        {
+            Some(Operand::RValue(RValue::Immediate(value))) => Ok(*value as i64),
+            _ => Err(self.illegal())
+        }
+    }
+
+    // Effective address of an "offset(rs1)" operand.
+    fn address(&self, index: usize, x: &[u64; 32]) -> Result<u64, Trap>
+    {
+        match self.operands.get(index)
+        {
+            Some(Operand::Address(RValue::Register('x', base), RValue::Immediate(offset))) =>
+                Ok(x[*base as usize].wrapping_add(*offset as i64 as u64)),
+            _ => Err(self.illegal())
+        }
+    }
+
+    // Static rounding mode, absent when the instruction uses the dynamic mode in frm.
+    fn rounding(&self) -> Option<u8>
+    {
+        self.operands.iter().find_map(|operand| match operand
+        {
+            Operand::RValue(RValue::Identifier(mode)) => ROUNDING_MODES.iter().position(|name| name == mode).map(|rm| rm as u8),
+            _ => None
+        })
+    }
+}
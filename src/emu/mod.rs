@@ -0,0 +1,26 @@
+// Hardware thread (hart) execution.
+pub mod hart;
+
+// M-extension multiplication and division.
+pub mod muldiv;
+
+// A-extension atomic memory operations.
+pub mod amo;
+
+// F/D-extension floating point arithmetic.
+pub mod fpu;
+
+// Control and status registers.
+pub mod csr;
+
+// Translation lookaside buffer.
+pub mod tlb;
+
+// Linux user-mode system call emulation.
+pub mod syscall;
+
+// ARM-style semihosting through ebreak.
+pub mod semihost;
+
+// Spike compatible commit traces.
+pub mod trace;
\ No newline at end of file
@@ -0,0 +1,49 @@
+use super::hart::Xlen;
+
+// Result of an M-extension instruction on XLEN bit operands, "w" variants operate on the low 32 bits.
+// Division by zero and signed overflow (e.g. INT_MIN / -1) don't trap, they produce the results defined by the spec.
+pub fn muldiv(mnemonic: &str, xlen: Xlen, rs1: u64, rs2: u64) -> Option<u64>
+{
+    match (mnemonic, xlen)
+    {
+        ("mulw" | "divw" | "divuw" | "remw" | "remuw", _) | (_, Xlen::X32) =>
+            muldiv32(mnemonic.trim_end_matches('w'), rs1 as u32, rs2 as u32).map(|value| value as i32 as i64 as u64),
+        _ => muldiv64(mnemonic, rs1, rs2)
+    }
+}
+
+fn muldiv32(mnemonic: &str, rs1: u32, rs2: u32) -> Option<u32>
+{
+    let (signed1, signed2) = (rs1 as i32, rs2 as i32);
+
+    Some(match mnemonic
+    {
+        "mul"    => rs1.wrapping_mul(rs2),
+        "mulh"   => ((signed1 as i64 * signed2 as i64) >> 32) as u32,
+        "mulhsu" => ((signed1 as i64 * rs2 as i64) >> 32) as u32,
+        "mulhu"  => ((rs1 as u64 * rs2 as u64) >> 32) as u32,
+        "div"    => if rs2 == 0 { u32::MAX } else { signed1.wrapping_div(signed2) as u32 },
+        "divu"   => rs1.checked_div(rs2).unwrap_or(u32::MAX),
+        "rem"    => if rs2 == 0 { rs1 } else { signed1.wrapping_rem(signed2) as u32 },
+        "remu"   => rs1.checked_rem(rs2).unwrap_or(rs1),
+        _ => return None
+    })
+}
+
+fn muldiv64(mnemonic: &str, rs1: u64, rs2: u64) -> Option<u64>
+{
+    let (signed1, signed2) = (rs1 as i64, rs2 as i64);
+
+    Some(match mnemonic
+    {
+        "mul"    => rs1.wrapping_mul(rs2),
+        "mulh"   => ((signed1 as i128 * signed2 as i128) >> 64) as u64,
+        "mulhsu" => ((signed1 as i128 * rs2 as i128) >> 64) as u64,
+        "mulhu"  => ((rs1 as u128 * rs2 as u128) >> 64) as u64,
+        "div"    => if rs2 == 0 { u64::MAX } else { signed1.wrapping_div(signed2) as u64 },
+        "divu"   => rs1.checked_div(rs2).unwrap_or(u64::MAX),
+        "rem"    => if rs2 == 0 { rs1 } else { signed1.wrapping_rem(signed2) as u64 },
+        "remu"   => rs1.checked_rem(rs2).unwrap_or(rs1),
+        _ => return None
+    })
+}
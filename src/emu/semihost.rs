@@ -0,0 +1,320 @@
+use std::{
+    collections::HashMap, fs::{self, File, OpenOptions}, io::{self, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf}, time::{Instant, SystemTime, UNIX_EPOCH}
+};
+
+use crate::{
+    mem::*, mmu::*
+};
+use super::{
+    hart::*, syscall::{errno, load, load_string, store, CHUNK_SIZE, EACCES, EBADF, EFAULT, EINVAL, ENOSYS}
+};
+
+// Instruction sequence of a semihosting call, read from physical memory around the "ebreak".
+pub const SEMIHOST_ENTRY: u32  = 0x01F0_1013; // slli x0, x0, 0x1f
+pub const SEMIHOST_EBREAK: u32 = 0x0010_0073;
+pub const SEMIHOST_EXIT: u32   = 0x4070_5013; // srai x0, x0, 7
+
+// Semihosting operation numbers, passed in a0.
+pub const SYS_OPEN: u64          = 0x01;
+pub const SYS_CLOSE: u64         = 0x02;
+pub const SYS_WRITEC: u64        = 0x03;
+pub const SYS_WRITE0: u64        = 0x04;
+pub const SYS_WRITE: u64         = 0x05;
+pub const SYS_READ: u64          = 0x06;
+pub const SYS_READC: u64         = 0x07;
+pub const SYS_ISERROR: u64       = 0x08;
+pub const SYS_ISTTY: u64         = 0x09;
+pub const SYS_SEEK: u64          = 0x0A;
+pub const SYS_FLEN: u64          = 0x0C;
+pub const SYS_REMOVE: u64        = 0x0E;
+pub const SYS_RENAME: u64        = 0x0F;
+pub const SYS_CLOCK: u64         = 0x10;
+pub const SYS_TIME: u64          = 0x11;
+pub const SYS_ERRNO: u64         = 0x13;
+pub const SYS_GET_CMDLINE: u64   = 0x15;
+pub const SYS_HEAPINFO: u64      = 0x16;
+pub const SYS_EXIT: u64          = 0x18;
+pub const SYS_EXIT_EXTENDED: u64 = 0x20;
+pub const SYS_ELAPSED: u64       = 0x30;
+pub const SYS_TICKFREQ: u64      = 0x31;
+
+// Reason of a SYS_EXIT reporting a normal exit.
+pub const ADP_STOPPED_APPLICATION_EXIT: u64 = 0x20026;
+
+// Frequency of the SYS_ELAPSED counter.
+const TICK_FREQUENCY: u64 = 1_000_000;
+
+// Open handle of the target, ":tt" opens the console.
+enum Handle
+{
+    Input,
+    Output,
+    File(File)
+}
+
+// Host side of the ARM semihosting interface as adopted by RISC-V. The operation is in a0 and a1 holds its parameter
+// or points to a block of XLEN sized parameters, the result is returned in a0. Files are opened inside `root` only.
+pub struct Semihosting
+{
+    pub root: PathBuf,
+    pub input: Box<dyn Read>,
+    pub output: Box<dyn Write>,
+    pub command_line: String,
+    files: HashMap<u64 /* Handle */, Handle>,
+    errno: i64,
+    started: Instant
+}
+
+impl Semihosting
+{
+    pub fn new(root: impl Into<PathBuf>) -> Self
+    {
+        Semihosting{
+            root: root.into(), input: Box::new(io::stdin()), output: Box::new(io::stdout()), command_line: String::new(),
+            files: HashMap::new(), errno: 0, started: Instant::now()
+        }
+    }
+
+    // Whether the virtual `pc` of `hart` is the "ebreak" of a semihosting call sequence. The three instructions are
+    // fetched through the instruction translation of `hart`.
+    pub fn at_call(hart: &mut Hart, mmu: &mut MMU, pc: u64) -> bool
+    {
+        pc >= 4 && hart.fetch_at(mmu, pc) == Ok(SEMIHOST_EBREAK)
+            && hart.fetch_at(mmu, pc - 4) == Ok(SEMIHOST_ENTRY) && hart.fetch_at(mmu, pc.wrapping_add(4)) == Ok(SEMIHOST_EXIT)
+    }
+
+    // Runs `hart` serving its semihosting calls until the program exits with the returned status.
+    // Other traps, including plain breakpoints, are returned to the caller.
+    pub fn run(&mut self, hart: &mut Hart, mmu: &mut MMU) -> Result<i64, Trap>
+    {
+        loop
+        {
+            match hart.run(mmu)
+            {
+                Trap::Breakpoint(pc) if Self::at_call(hart, mmu, pc) =>
+                {
+                    hart.pc = hart.truncate(pc.wrapping_add(4));
+                    if let Some(status) = self.call(hart, mmu)
+                    {
+                        return Ok(status)
+                    }
+                },
+                trap => return Err(trap)
+            }
+        }
+    }
+
+    // Ticks `hart` with trap handling, a semihosting call at pc is served in place of the breakpoint exception.
+    // Returns the exit status once the program exits.
+    pub fn tick(&mut self, hart: &mut Hart, mmu: &mut MMU) -> Option<i64>
+    {
+        if !hart.waiting && hart.interrupt().is_none() && Self::at_call(hart, mmu, hart.pc)
+        {
+            hart.pc = hart.truncate(hart.pc.wrapping_add(4));
+            return self.call(hart, mmu)
+        }
+
+        hart.tick(mmu);
+        None
+    }
+
+    // Serves the operation in a0, failures return -1 and set the error number reported by SYS_ERRNO.
+    pub fn call(&mut self, hart: &mut Hart, mmu: &mut MMU) -> Option<i64>
+    {
+        let (operation, parameter) = (hart.x[10], hart.x[11]);
+        let word = match hart.xlen
+        {
+            Xlen::X32 => 4,
+            Xlen::X64 => 8
+        };
+        let field = |index: usize| -> Result<u64, i64>
+        {
+            let bytes = load(mmu, (parameter as Address).checked_add(index * word).ok_or(EFAULT)?, word)?;
+            Ok(bytes.iter().rev().fold(0, |value, &byte| value << 8 | byte as u64))
+        };
+
+        let result = match operation
+        {
+            SYS_EXIT if hart.xlen == Xlen::X32 => return Some((parameter != ADP_STOPPED_APPLICATION_EXIT) as i64),
+            SYS_EXIT | SYS_EXIT_EXTENDED => match (field(0), field(1))
+            {
+                (Ok(ADP_STOPPED_APPLICATION_EXIT), Ok(code)) => return Some(code as i32 as i64),
+                (Ok(_), _) => return Some(1),
+                (Err(errno), _) => Err(errno)
+            },
+            SYS_OPEN => field(0).and_then(|name| Ok((name, field(1)?, field(2)?)))
+                .and_then(|(name, mode, length)| self.open(mmu, name as Address, mode, length as usize)),
+            SYS_CLOSE => field(0).and_then(|handle| self.files.remove(&handle).map_or(Err(EBADF), |_| Ok(0))),
+            SYS_WRITEC => load(mmu, parameter as Address, 1).and_then(|byte| self.console(&byte)),
+            SYS_WRITE0 => load_string(mmu, parameter as Address).and_then(|string| self.console(string.as_bytes())),
+            SYS_WRITE => field(0).and_then(|handle| Ok((handle, field(1)?, field(2)?)))
+                .and_then(|(handle, buffer, length)| self.write(mmu, handle, buffer as Address, length as usize)),
+            SYS_READ => field(0).and_then(|handle| Ok((handle, field(1)?, field(2)?)))
+                .and_then(|(handle, buffer, length)| self.read(mmu, handle, buffer as Address, length as usize)),
+            SYS_READC =>
+            {
+                let mut byte = [0];
+                self.input.read_exact(&mut byte).map(|_| byte[0] as i64).map_err(errno)
+            },
+            SYS_ISERROR => field(0).map(|status| (status >> (word * 8 - 1) & 1) as i64),
+            SYS_ISTTY => field(0).and_then(|handle| match self.files.get(&handle)
+            {
+                Some(Handle::File(_)) => Ok(0),
+                Some(_) => Ok(1),
+                None => Err(EBADF)
+            }),
+            SYS_SEEK => field(0).and_then(|handle| Ok((handle, field(1)?)))
+                .and_then(|(handle, position)| match self.files.get_mut(&handle)
+                {
+                    Some(Handle::File(file)) => file.seek(SeekFrom::Start(position)).map(|_| 0).map_err(errno),
+                    Some(_) => Err(EINVAL),
+                    None => Err(EBADF)
+                }),
+            SYS_FLEN => field(0).and_then(|handle| match self.files.get(&handle)
+            {
+                Some(Handle::File(file)) => file.metadata().map(|metadata| metadata.len() as i64).map_err(errno),
+                Some(_) => Ok(0),
+                None => Err(EBADF)
+            }),
+            SYS_REMOVE => field(0).and_then(|name| Ok((name, field(1)?)))
+                .and_then(|(name, length)| self.path(mmu, name as Address, length as usize))
+                .and_then(|path| fs::remove_file(path).map(|_| 0).map_err(errno)),
+            SYS_RENAME => field(0).and_then(|from| Ok((from, field(1)?, field(2)?, field(3)?)))
+                .and_then(|(from, from_length, to, to_length)| Ok((
+                    self.path(mmu, from as Address, from_length as usize)?, self.path(mmu, to as Address, to_length as usize)?
+                )))
+                .and_then(|(from, to)| fs::rename(from, to).map(|_| 0).map_err(errno)),
+            SYS_CLOCK => Ok((self.started.elapsed().as_millis() / 10) as i64),
+            SYS_TIME => Ok(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64),
+            SYS_ERRNO => Ok(self.errno),
+            SYS_GET_CMDLINE => field(0).and_then(|buffer| Ok((buffer, field(1)?)))
+                .and_then(|(buffer, length)| self.command_line(mmu, parameter as Address, buffer as Address, length as usize, word)),
+            SYS_HEAPINFO => field(0).and_then(|block| store(mmu, block as Address, &vec![0; 4 * word]).map(|_| 0)),
+            SYS_ELAPSED =>
+            {
+                let ticks = self.started.elapsed().as_micros() as u64;
+                store(mmu, parameter as Address, &ticks.to_le_bytes()).map(|_| 0)
+            },
+            SYS_TICKFREQ => Ok(TICK_FREQUENCY as i64),
+            _ => Err(ENOSYS)
+        };
+
+        let result = result.unwrap_or_else(|errno|
+        {
+            self.errno = errno;
+            -1
+        });
+        hart.set(10, result as u64);
+        None
+    }
+
+    // Resolves a target path inside `root`, absolute paths and parent components are refused.
+    fn path(&self, mmu: &MMU, name: Address, length: usize) -> Result<PathBuf, i64>
+    {
+        let name = String::from_utf8(load(mmu, name, length)?).map_err(|_| EINVAL)?;
+        let path = Path::new(&name);
+
+        match path.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            true => Ok(self.root.join(path)),
+            false => Err(EACCES)
+        }
+    }
+
+    // Opens with the fopen() style `mode` 0-11 (r, rb, r+, r+b, w, wb, w+, w+b, a, ab, a+, a+b).
+    fn open(&mut self, mmu: &MMU, name: Address, mode: u64, length: usize) -> Result<i64, i64>
+    {
+        let handle = (3..).find(|handle| !self.files.contains_key(handle)).unwrap_or(3);
+
+        if load(mmu, name, length)? == b":tt"
+        {
+            self.files.insert(handle, if mode < 4 { Handle::Input } else { Handle::Output });
+            return Ok(handle as i64)
+        }
+
+        let update = mode & 0b10 != 0;
+        let mut options = OpenOptions::new();
+        match mode >> 2
+        {
+            0 => options.read(true).write(update),
+            1 => options.write(true).create(true).truncate(true).read(update),
+            2 => options.append(true).create(true).read(update),
+            _ => return Err(EINVAL)
+        };
+
+        let file = options.open(self.path(mmu, name, length)?).map_err(errno)?;
+        self.files.insert(handle, Handle::File(file));
+        Ok(handle as i64)
+    }
+
+    fn console(&mut self, bytes: &[u8]) -> Result<i64, i64>
+    {
+        self.output.write_all(bytes).and_then(|_| self.output.flush()).map(|_| 0).map_err(errno)
+    }
+
+    // Returns the number of bytes not written.
+    // Writes in pieces of at most CHUNK_SIZE bytes until all `length` bytes are written.
+    fn write(&mut self, mmu: &MMU, handle: u64, buffer: Address, length: usize) -> Result<i64, i64>
+    {
+        let mut done = 0;
+        loop
+        {
+            let chunk = (length - done).min(CHUNK_SIZE);
+            let bytes = load(mmu, buffer.checked_add(done).ok_or(EFAULT)?, chunk)?;
+            match self.files.get_mut(&handle)
+            {
+                Some(Handle::Output) => self.console(&bytes)?,
+                Some(Handle::File(file)) => file.write_all(&bytes).map_err(errno).map(|_| 0)?,
+                _ => return Err(EBADF)
+            };
+            done += chunk;
+
+            if done == length
+            {
+                return Ok(0)
+            }
+        }
+    }
+
+    // Returns the number of bytes not read, all of them at the end of the file.
+    // Reads in pieces of at most CHUNK_SIZE bytes until `length` bytes are read or a read comes up short.
+    fn read(&mut self, mmu: &mut MMU, handle: u64, buffer: Address, length: usize) -> Result<i64, i64>
+    {
+        let mut bytes = vec![0; length.min(CHUNK_SIZE)];
+        let mut done = 0;
+        loop
+        {
+            let chunk = (length - done).min(CHUNK_SIZE);
+            let read = match self.files.get_mut(&handle)
+            {
+                Some(Handle::Input) => self.input.read(&mut bytes[..chunk]),
+                Some(Handle::File(file)) => file.read(&mut bytes[..chunk]),
+                _ => return Err(EBADF)
+            }.map_err(errno)?;
+
+            store(mmu, buffer.checked_add(done).ok_or(EFAULT)?, &bytes[..read])?;
+            done += read;
+
+            if done == length || read < chunk
+            {
+                return Ok((length - done) as i64)
+            }
+        }
+    }
+
+    // Copies the command line to `buffer` and its length to the second parameter.
+    fn command_line(&self, mmu: &mut MMU, parameter: Address, buffer: Address, length: usize, word: usize) -> Result<i64, i64>
+    {
+        let command_line = self.command_line.as_bytes();
+        if command_line.len() >= length
+        {
+            return Err(EINVAL)
+        }
+
+        store(mmu, buffer, command_line)?;
+        store(mmu, buffer.checked_add(command_line.len()).ok_or(EFAULT)?, &[0])?;
+        store(mmu, parameter.checked_add(word).ok_or(EFAULT)?, &command_line.len().to_le_bytes()[..word])?;
+        Ok(0)
+    }
+}
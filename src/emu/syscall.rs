@@ -0,0 +1,488 @@
+use std::{
+    collections::{HashMap, hash_map::RandomState}, fs::{File, OpenOptions}, hash::{BuildHasher, Hasher},
+    io::{self, Read, Seek, SeekFrom, Write}, path::{Component, Path, PathBuf}, time::{Instant, SystemTime, UNIX_EPOCH}
+};
+
+use crate::{
+    mem::*, mmu::*, pmp::Pmp
+};
+use super::hart::*;
+
+// Linux system call numbers shared by RV32 and RV64, clock_gettime64 is the RV32 variant of clock_gettime.
+pub const SYS_OPENAT: u64         = 56;
+pub const SYS_CLOSE: u64          = 57;
+pub const SYS_LSEEK: u64          = 62;
+pub const SYS_READ: u64           = 63;
+pub const SYS_WRITE: u64          = 64;
+pub const SYS_FSTAT: u64          = 80;
+pub const SYS_EXIT: u64           = 93;
+pub const SYS_EXIT_GROUP: u64     = 94;
+pub const SYS_CLOCK_GETTIME: u64  = 113;
+pub const SYS_BRK: u64            = 214;
+pub const SYS_MUNMAP: u64         = 215;
+pub const SYS_MMAP: u64           = 222;
+pub const SYS_GETRANDOM: u64      = 278;
+pub const SYS_CLOCK_GETTIME64: u64 = 403;
+
+// Error numbers, returned negated in a0.
+pub const EBADF: i64  = 9;
+pub const ENOMEM: i64 = 12;
+pub const EACCES: i64 = 13;
+pub const EFAULT: i64 = 14;
+pub const EINVAL: i64 = 22;
+pub const ESPIPE: i64 = 29;
+pub const ENOSYS: i64 = 38;
+
+// openat, mmap and fstat constants.
+const AT_FDCWD: i64       = -100;
+const O_ACCMODE: u64      = 0b11;
+const O_CREAT: u64        = 0x40;
+const O_EXCL: u64         = 0x80;
+const O_TRUNC: u64        = 0x200;
+const O_APPEND: u64       = 0x400;
+const PROT_READ: u64      = 1;
+const PROT_WRITE: u64     = 2;
+const PROT_EXEC: u64      = 4;
+const MAP_FIXED: u64      = 0x10;
+const MAP_ANONYMOUS: u64  = 0x20;
+const S_IFCHR: u32        = 0o020000;
+const S_IFDIR: u32        = 0o040000;
+const S_IFREG: u32        = 0o100000;
+
+// Largest host buffer a single transfer is staged through, larger transfers are served in pieces.
+pub(super) const CHUNK_SIZE: usize = 0x10000;
+
+// Open file description of the emulated process.
+pub enum Descriptor
+{
+    Input(Box<dyn Read>),
+    Output(Box<dyn Write>),
+    File(File)
+}
+
+// Host side of the Linux system call interface for statically linked user programs. Memory is managed in whole pages,
+// the heap grows up from the initial break and anonymous or file mappings are placed down from `mmap_top`.
+pub struct Syscalls
+{
+    pub files: HashMap<i64 /* File descriptor */, Descriptor>,
+    pub root: PathBuf, // Directory the program sees as "/", paths never leave it.
+    pub initial_brk: Address,
+    pub brk: Address,
+    pub mmap_top: Address,
+    started: Instant,
+    random: u64
+}
+
+impl Syscalls
+{
+    pub fn new(brk: Address, mmap_top: Address) -> Self
+    {
+        let mut files = HashMap::new();
+        files.insert(0, Descriptor::Input(Box::new(io::stdin())));
+        files.insert(1, Descriptor::Output(Box::new(io::stdout())));
+        files.insert(2, Descriptor::Output(Box::new(io::stderr())));
+
+        let brk = align_address(brk, PAGE_SIZE);
+        let random = RandomState::new().build_hasher().finish() | 1;
+
+        Syscalls{ files, root: PathBuf::from("."), initial_brk: brk, brk, mmap_top, started: Instant::now(), random }
+    }
+
+    // Replaces the file description behind `fd`, such as capturing stdout.
+    pub fn with_file(mut self, fd: i64, descriptor: Descriptor) -> Self
+    {
+        self.files.insert(fd, descriptor);
+        self
+    }
+
+    // Opens files inside `root` instead of the working directory.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self
+    {
+        self.root = root.into();
+        self
+    }
+
+    // Runs `hart` in user mode, serving its system calls until the program exits with the returned status.
+    // Traps other than environment calls are returned to the caller, physical memory protection doesn't apply.
+    pub fn run(&mut self, hart: &mut Hart, mmu: &mut MMU) -> Result<i64, Trap>
+    {
+        hart.privilege = Privilege::User;
+        hart.csrs.pmp = Pmp::new(0);
+
+        loop
+        {
+            match hart.run(mmu)
+            {
+                Trap::EnvironmentCall =>
+                {
+                    hart.pc = hart.truncate(hart.pc.wrapping_add(4));
+                    if let Some(status) = self.call(hart, mmu)
+                    {
+                        return Ok(status)
+                    }
+                },
+                trap => return Err(trap)
+            }
+        }
+    }
+
+    // Serves the system call in a7 with arguments in a0-a5, the result or negated error number is returned in a0.
+    // Returns the exit status once the program exits.
+    pub fn call(&mut self, hart: &mut Hart, mmu: &mut MMU) -> Option<i64>
+    {
+        let args: [u64; 6] = std::array::from_fn(|i| hart.x[10 + i]);
+        let signed = |i: usize| match hart.xlen
+        {
+            Xlen::X32 => args[i] as i32 as i64,
+            Xlen::X64 => args[i] as i64
+        };
+
+        let result = match hart.x[17]
+        {
+            SYS_EXIT | SYS_EXIT_GROUP => return Some(signed(0)),
+            SYS_READ => self.read(mmu, signed(0), args[1] as Address, args[2] as usize),
+            SYS_WRITE => self.write(mmu, signed(0), args[1] as Address, args[2] as usize),
+            SYS_OPENAT => self.openat(mmu, signed(0), args[1] as Address, args[2]),
+            SYS_CLOSE => self.files.remove(&signed(0)).map_or(Err(EBADF), |_| Ok(0)),
+            SYS_LSEEK => self.lseek(signed(0), signed(1), args[2]),
+            SYS_FSTAT => self.fstat(mmu, signed(0), args[1] as Address),
+            SYS_BRK => Ok(self.set_brk(mmu, args[0] as Address) as i64),
+            SYS_MMAP => self.mmap(mmu, args[0] as Address, args[1] as usize, args[2], args[3], signed(4), args[5]),
+            SYS_MUNMAP => self.munmap(mmu, args[0] as Address, args[1] as usize),
+            SYS_CLOCK_GETTIME | SYS_CLOCK_GETTIME64 => self.clock_gettime(mmu, args[0], args[1] as Address),
+            SYS_GETRANDOM => self.getrandom(mmu, args[0] as Address, args[1] as usize),
+            _ => Err(ENOSYS)
+        };
+
+        hart.set(10, result.unwrap_or_else(|errno| -errno) as u64);
+        None
+    }
+
+    // Reads up to `count` bytes, stopping early at a short read or once an error follows some data.
+    fn read(&mut self, mmu: &mut MMU, fd: i64, buffer: Address, count: usize) -> Result<i64, i64>
+    {
+        let mut bytes = vec![0; count.min(CHUNK_SIZE)];
+        let mut done = 0;
+        loop
+        {
+            let length = (count - done).min(CHUNK_SIZE);
+            let read = match self.files.get_mut(&fd)
+            {
+                Some(Descriptor::Input(input)) => input.read(&mut bytes[..length]),
+                Some(Descriptor::File(file)) => file.read(&mut bytes[..length]),
+                _ => return Err(EBADF)
+            };
+
+            let read = match read
+            {
+                Ok(read) => read,
+                Err(_) if done > 0 => break,
+                Err(io_err) => return Err(errno(io_err))
+            };
+            store(mmu, buffer.checked_add(done).ok_or(EFAULT)?, &bytes[..read])?;
+            done += read;
+
+            if done == count || read < length
+            {
+                return Ok(done as i64)
+            }
+        }
+        Ok(done as i64)
+    }
+
+    // Writes `count` bytes in pieces of at most CHUNK_SIZE bytes, stopping early once an error follows some data.
+    fn write(&mut self, mmu: &mut MMU, fd: i64, buffer: Address, count: usize) -> Result<i64, i64>
+    {
+        let mut done = 0;
+        loop
+        {
+            let length = (count - done).min(CHUNK_SIZE);
+            let bytes = match buffer.checked_add(done).ok_or(EFAULT).and_then(|address| load(mmu, address, length))
+            {
+                Ok(bytes) => bytes,
+                Err(_) if done > 0 => break,
+                Err(errno) => return Err(errno)
+            };
+
+            let written = match self.files.get_mut(&fd)
+            {
+                Some(Descriptor::Output(output)) => output.write_all(&bytes).and_then(|_| output.flush()),
+                Some(Descriptor::File(file)) => file.write_all(&bytes),
+                _ => return Err(EBADF)
+            };
+
+            match written
+            {
+                Ok(()) => done += length,
+                Err(_) if done > 0 => break,
+                Err(io_err) => return Err(errno(io_err))
+            }
+
+            if done == count
+            {
+                break
+            }
+        }
+        Ok(done as i64)
+    }
+
+    // Opens a path inside `root`, which is both the root and working directory of the program. Parent components are
+    // refused, directory descriptors other than the working directory aren't supported.
+    fn openat(&mut self, mmu: &mut MMU, dirfd: i64, path: Address, flags: u64) -> Result<i64, i64>
+    {
+        let path = load_string(mmu, path)?;
+        if dirfd != AT_FDCWD && !path.starts_with('/')
+        {
+            return Err(EBADF)
+        }
+
+        let path = Path::new(path.trim_start_matches('/'));
+        if !path.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(EACCES)
+        }
+
+        let mut options = OpenOptions::new();
+        match flags & O_ACCMODE
+        {
+            0 => options.read(true),
+            1 => options.write(true),
+            _ => options.read(true).write(true)
+        };
+        options.append(flags & O_APPEND != 0).truncate(flags & O_TRUNC != 0);
+        match flags & (O_CREAT | O_EXCL)
+        {
+            O_CREAT => options.create(true),
+            flags if flags & O_EXCL != 0 => options.create_new(true),
+            _ => &mut options
+        };
+
+        let file = options.open(self.root.join(path)).map_err(errno)?;
+        let fd = (3..).find(|fd| !self.files.contains_key(fd)).unwrap_or(3);
+        self.files.insert(fd, Descriptor::File(file));
+        Ok(fd)
+    }
+
+    fn lseek(&mut self, fd: i64, offset: i64, whence: u64) -> Result<i64, i64>
+    {
+        let position = match whence
+        {
+            0 => SeekFrom::Start(offset as u64),
+            1 => SeekFrom::Current(offset),
+            2 => SeekFrom::End(offset),
+            _ => return Err(EINVAL)
+        };
+
+        match self.files.get_mut(&fd)
+        {
+            Some(Descriptor::File(file)) => file.seek(position).map(|position| position as i64).map_err(errno),
+            Some(_) => Err(ESPIPE),
+            None => Err(EBADF)
+        }
+    }
+
+    // Fills the generic 128-byte struct stat, standard streams are reported as character devices.
+    fn fstat(&mut self, mmu: &mut MMU, fd: i64, buffer: Address) -> Result<i64, i64>
+    {
+        let (mode, size, modified) = match self.files.get(&fd)
+        {
+            Some(Descriptor::File(file)) =>
+            {
+                let metadata = file.metadata().map_err(errno)?;
+                let kind = if metadata.is_dir() { S_IFDIR } else { S_IFREG };
+                let modified = metadata.modified().ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |duration| duration.as_secs());
+                (kind | 0o644, metadata.len(), modified)
+            },
+            Some(_) => (S_IFCHR | 0o620, 0, 0),
+            None => return Err(EBADF)
+        };
+
+        let mut stat = [0u8; 128];
+        stat[16..20].copy_from_slice(&mode.to_le_bytes());
+        stat[20..24].copy_from_slice(&1u32.to_le_bytes());
+        stat[48..56].copy_from_slice(&size.to_le_bytes());
+        stat[56..60].copy_from_slice(&(PAGE_SIZE as u32).to_le_bytes());
+        stat[64..72].copy_from_slice(&size.div_ceil(512).to_le_bytes());
+        for time in [72, 88, 104]
+        {
+            stat[time..time + 8].copy_from_slice(&modified.to_le_bytes());
+        }
+
+        store(mmu, buffer, &stat)?;
+        Ok(0)
+    }
+
+    // Moves the program break, returning the new break or the current one when it can't move there.
+    // Pages are added or removed as the heap grows and shrinks, the heap never overlaps the mappings.
+    fn set_brk(&mut self, mmu: &mut MMU, brk: Address) -> Address
+    {
+        let current = align_address(self.brk, PAGE_SIZE);
+        let Some(requested) = brk.checked_next_multiple_of(PAGE_SIZE) else { return self.brk };
+        if brk < self.initial_brk || requested > self.mmap_top.min(mmu.memory.len())
+        {
+            return self.brk
+        }
+
+        if requested > current
+        {
+            if mmu.protect(current, requested - 1, Protection::READ | Protection::WRITE).is_err()
+            {
+                return self.brk
+            }
+            if let Some(pages) = mmu.physical_mut(current..requested)
+            {
+                pages.fill(0);
+            }
+        }
+        else if requested < current
+        {
+            mmu.unprotect(requested, current - 1);
+        }
+
+        self.brk = brk;
+        brk
+    }
+
+    // Private mappings only, file mappings are copied in. Placed mappings must be page aligned and may replace pages
+    // with MAP_FIXED, other mappings are placed below the previous ones.
+    #[allow(clippy::too_many_arguments)]
+    fn mmap(&mut self, mmu: &mut MMU, address: Address, length: usize, prot: u64, flags: u64, fd: i64, offset: u64) -> Result<i64, i64>
+    {
+        let length = length.checked_next_multiple_of(PAGE_SIZE).ok_or(ENOMEM)?;
+        if length == 0 || !address.is_multiple_of(PAGE_SIZE) || !(offset as usize).is_multiple_of(PAGE_SIZE)
+        {
+            return Err(EINVAL)
+        }
+
+        // File contents are read before any pages are replaced.
+        let contents = match flags & MAP_ANONYMOUS
+        {
+            0 => match self.files.get_mut(&fd)
+            {
+                Some(Descriptor::File(file)) =>
+                {
+                    let mut contents = Vec::new();
+                    file.seek(SeekFrom::Start(offset)).and_then(|_| file.take(length as u64).read_to_end(&mut contents))
+                        .map_err(errno)?;
+                    contents
+                },
+                _ => return Err(EBADF)
+            },
+            _ => Vec::new()
+        };
+
+        let start = match flags & MAP_FIXED
+        {
+            0 => self.mmap_top.checked_sub(length).filter(|&start| start >= align_address(self.brk, PAGE_SIZE)).ok_or(ENOMEM)?,
+            _ => address
+        };
+        let end = start.checked_add(length).filter(|&end| end <= mmu.memory.len()).ok_or(ENOMEM)?;
+        if flags & MAP_FIXED == 0
+        {
+            self.mmap_top = start;
+        }
+
+        let mut protection = Protection::empty();
+        for (bit, flag) in [(PROT_READ, Protection::READ), (PROT_WRITE, Protection::WRITE), (PROT_EXEC, Protection::EXECUTE)]
+        {
+            if prot & bit != 0
+            {
+                protection |= flag;
+            }
+        }
+
+        let pages = mmu.physical_mut(start..end).ok_or(ENOMEM)?;
+        pages.fill(0);
+        pages[..contents.len()].copy_from_slice(&contents);
+
+        mmu.unprotect(start, end - 1);
+        mmu.protect(start, end - 1, protection).map_err(|_| ENOMEM)?;
+        Ok(start as i64)
+    }
+
+    // Ranges reaching past the end of memory are refused.
+    fn munmap(&mut self, mmu: &mut MMU, address: Address, length: usize) -> Result<i64, i64>
+    {
+        let end = length.checked_next_multiple_of(PAGE_SIZE).and_then(|length| address.checked_add(length));
+        match end
+        {
+            Some(end) if address.is_multiple_of(PAGE_SIZE) && length != 0 && end <= mmu.memory.len() =>
+            {
+                mmu.unprotect(address, end - 1);
+                Ok(0)
+            },
+            _ => Err(EINVAL)
+        }
+    }
+
+    // Realtime clocks read the host time, every other clock counts from the start of the program.
+    // Both XLENs use the 64-bit struct timespec.
+    fn clock_gettime(&mut self, mmu: &mut MMU, clock: u64, buffer: Address) -> Result<i64, i64>
+    {
+        let time = match clock
+        {
+            0 => SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default(),
+            _ => self.started.elapsed()
+        };
+
+        let mut timespec = [0u8; 16];
+        timespec[..8].copy_from_slice(&time.as_secs().to_le_bytes());
+        timespec[8..].copy_from_slice(&(time.subsec_nanos() as u64).to_le_bytes());
+        store(mmu, buffer, &timespec)?;
+        Ok(0)
+    }
+
+    // Fills `buffer` from a xorshift generator seeded by the host.
+    fn getrandom(&mut self, mmu: &mut MMU, buffer: Address, length: usize) -> Result<i64, i64>
+    {
+        let mut bytes = vec![0; length.min(CHUNK_SIZE)];
+        for done in (0..length).step_by(CHUNK_SIZE)
+        {
+            let chunk = &mut bytes[..(length - done).min(CHUNK_SIZE)];
+            for byte in chunk.iter_mut()
+            {
+                self.random ^= self.random << 13;
+                self.random ^= self.random >> 7;
+                self.random ^= self.random << 17;
+                *byte = self.random as u8;
+            }
+            store(mmu, buffer.checked_add(done).ok_or(EFAULT)?, chunk)?;
+        }
+        Ok(length as i64)
+    }
+}
+
+pub(super) fn errno(io_err: io::Error) -> i64
+{
+    io_err.raw_os_error().map_or(EINVAL, |errno| errno as i64)
+}
+
+// Copies `bytes` to the program, faulting on pages it can't write.
+pub(super) fn store(mmu: &mut MMU, address: Address, bytes: &[u8]) -> Result<(), i64>
+{
+    for (i, &byte) in bytes.iter().enumerate()
+    {
+        mmu.write_byte(address.checked_add(i).ok_or(EFAULT)?, byte).map_err(|_| EFAULT)?;
+    }
+    Ok(())
+}
+
+pub(super) fn load(mmu: &MMU, address: Address, length: usize) -> Result<Vec<u8>, i64>
+{
+    (0..length).map(|i| mmu.read_byte(address.checked_add(i).ok_or(EFAULT)?).map_err(|_| EFAULT)).collect()
+}
+
+pub(super) fn load_string(mmu: &MMU, address: Address) -> Result<String, i64>
+{
+    let mut bytes = Vec::new();
+    loop
+    {
+        match mmu.read_byte(address.checked_add(bytes.len()).ok_or(EFAULT)?).map_err(|_| EFAULT)?
+        {
+            0 => return String::from_utf8(bytes).map_err(|_| EINVAL),
+            byte => bytes.push(byte)
+        }
+    }
+}
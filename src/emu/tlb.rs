@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+
+use crate::{
+    mmu::*, mem::*
+};
+
+// Default number of entries on each side of the TLB.
+pub const TLB_ENTRIES: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TlbEntry
+{
+    pub asid: u16,
+    pub mapping: PageMapping
+}
+
+impl TlbEntry
+{
+    // Global mappings match every address space.
+    fn matches(&self, address: u64, asid: u16) -> bool
+    {
+        (self.asid == asid || self.mapping.flags.contains(Protection::GLOBAL)) && self.mapping.contains(address)
+    }
+}
+
+// One side of the TLB, fully associative with round-robin replacement.
+pub struct TlbSide
+{
+    pub entries: Vec<TlbEntry>,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+    next: usize
+}
+
+impl TlbSide
+{
+    pub fn new(capacity: usize) -> Self
+    {
+        TlbSide{ entries: Vec::with_capacity(capacity), capacity, hits: 0, misses: 0, next: 0 }
+    }
+
+    fn lookup(&self, address: u64, asid: u16) -> Option<PageMapping>
+    {
+        self.entries.iter()
+            .find(|entry| entry.matches(address, asid))
+            .map(|entry| entry.mapping)
+    }
+
+    fn insert(&mut self, entry: TlbEntry)
+    {
+        if self.capacity == 0
+        {
+            return
+        }
+
+        if self.entries.len() < self.capacity
+        {
+            self.entries.push(entry);
+        }
+        else
+        {
+            self.entries[self.next] = entry;
+            self.next = (self.next + 1) % self.capacity;
+        }
+    }
+
+    // Removes every entry `address` would hit in `asid`.
+    fn evict(&mut self, address: u64, asid: u16)
+    {
+        self.entries.retain(|entry| !entry.matches(address, asid));
+    }
+
+    // Removes the entries mapping `address` and belonging to `asid`, global entries are kept when flushing an address space.
+    fn flush(&mut self, address: Option<u64>, asid: Option<u16>)
+    {
+        self.entries.retain(|entry|
+        {
+            let in_address = address.is_none_or(|address| entry.mapping.contains(address));
+            let in_space = asid.is_none_or(|asid| entry.asid == asid && !entry.mapping.flags.contains(Protection::GLOBAL));
+            !(in_address && in_space)
+        });
+    }
+}
+
+// Per-hart translation cache, split into instruction and data sides and tagged with the address space identifier.
+// Verification re-walks the page tables on every hit and records virtual pages whose cached translation is stale,
+// which happens when page tables are modified without a following "sfence.vma".
+pub struct Tlb
+{
+    pub instruction: TlbSide,
+    pub data: TlbSide,
+    pub verify: bool,
+    pub stale: HashSet<u64 /* Virtual page address */>
+}
+
+impl Tlb
+{
+    pub fn new(entries: usize) -> Self
+    {
+        Tlb{ instruction: TlbSide::new(entries), data: TlbSide::new(entries), verify: false, stale: HashSet::new() }
+    }
+
+    // Translates through the cache, permissions are checked on every hit and misses walk the page tables.
+    // Stores to pages that aren't marked dirty walk again to set the dirty bit.
+    pub fn translate(&mut self, mmu: &mut MMU, address: u64, access: Access, translation: &Translation) -> Result<Address, MMUErr>
+    {
+        if translation.paging == Paging::Bare
+        {
+            return Ok(address as Address)
+        }
+
+        let side = match access
+        {
+            Access::Fetch => &mut self.instruction,
+            _ => &mut self.data
+        };
+
+        let cached = side.lookup(address, translation.asid).filter(|mapping|
+            translation.permits(mapping.flags, access) && (access != Access::Store || mapping.flags.contains(Protection::DIRTY))
+        );
+
+        if let Some(mapping) = cached
+        {
+            side.hits += 1;
+
+            if self.verify && Self::is_stale(mmu, address, &mapping, translation)
+            {
+                self.stale.insert(address & !(PAGE_SIZE as u64 - 1));
+            }
+            return Ok(mapping.physical(address))
+        }
+
+        side.misses += 1;
+
+        // Refills replace any entry for the same page, such as one cached before the dirty bit was set.
+        let mapping = mmu.walk(address, access, translation)?;
+        side.evict(address, translation.asid);
+        side.insert(TlbEntry{ asid: translation.asid, mapping });
+
+        Ok(mapping.physical(address))
+    }
+
+    // Flushes as "sfence.vma" does, an absent address or address space identifier selects all of them.
+    pub fn flush(&mut self, address: Option<u64>, asid: Option<u16>)
+    {
+        self.instruction.flush(address, asid);
+        self.data.flush(address, asid);
+    }
+
+    fn is_stale(mmu: &MMU, address: u64, mapping: &PageMapping, translation: &Translation) -> bool
+    {
+        let permissions = Protection::READ | Protection::WRITE | Protection::EXECUTE | Protection::USER;
+
+        match mmu.probe(address, translation)
+        {
+            Ok(walked) => walked.physical(address) != mapping.physical(address)
+                || walked.flags & permissions != mapping.flags & permissions,
+            Err(_) => true
+        }
+    }
+}
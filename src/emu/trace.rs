@@ -0,0 +1,414 @@
+use std::{
+    collections::{HashMap, VecDeque}, fmt, io::{self, BufRead, Write}
+};
+
+use crate::{
+    arch::*, codec::dec::*, disasm::*,
+    mmu::*, mem::*
+};
+use super::{
+    hart::*, csr::*
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceErr
+{
+    Symbol(String)
+}
+
+// Architectural state before an instruction, effects are read from the hart once it retires.
+struct Snapshot
+{
+    pc: u64,
+    privilege: Privilege,
+    x: [u64; 32],
+    f: [u64; 32],
+    instret: u64,
+    binary: Option<u32>
+}
+
+// Commit trace in the layout of Spike's "-l --log-commits". Every retired instruction is logged as a disassembly line
+// followed by a commit line with the privilege, pc, instruction word, the registers written with their new values and
+// the addresses of loads and stores, stores followed by the data written. Like Spike, the disassembly uses ABI register
+// names and writes branch and jump targets relative to pc:
+//
+//     core   0: 0x0000000000000010 (0x00b2b023) sd      a1, 0(t0)
+//     core   0: 3 0x0000000000000010 (0x00b2b023) mem 0x0000000000001000 0x0000000000000020
+//     core   0: 0x0000000000000014 (0x008000ef) jal     ra, pc + 0x8
+//     core   0: 3 0x0000000000000014 (0x008000ef) x1  0x0000000000000018
+pub struct Tracer
+{
+    pub output: Box<dyn Write>,
+    pub ranges: Vec<(Address, Address)>, // Inclusive pc ranges to trace, everything is traced when empty.
+    pub disassemble: bool,               // Whether disassembly lines are logged.
+    pub symbolize: bool,                 // Whether branch targets are named after the symbols of `with_symbols` instead.
+    symbols: Vec<(usize, String)>
+}
+
+impl Tracer
+{
+    pub fn new(output: Box<dyn Write>) -> Self
+    {
+        Tracer{ output, ranges: Vec::new(), disassemble: true, symbolize: false, symbols: Vec::new() }
+    }
+
+    // Traces instructions from `start` to `end` inclusive, in addition to the ranges already traced.
+    pub fn with_range(mut self, start: Address, end: Address) -> Self
+    {
+        self.ranges.push((start, end));
+        self
+    }
+
+    // Symbols naming branch targets in the disassembly once `symbolize` is set, such as those of an object or a loaded
+    // executable.
+    pub fn with_symbols(mut self, symbols: &HashMap<String, Address>) -> Self
+    {
+        self.symbols = symbols.iter().map(|(name, &address)| (address, name.clone())).collect();
+        self.symbols.sort();
+        self
+    }
+
+    // Traces the code from the symbol `name` up to the next higher symbol of `symbols`.
+    pub fn with_symbol(self, symbols: &HashMap<String, Address>, name: &str) -> Result<Self, TraceErr>
+    {
+        let start = *symbols.get(name)
+            .ok_or_else(|| TraceErr::Symbol(format!(r#"Symbol "{}" is not defined"#, name)))?;
+        let end = symbols.values().filter(|&&address| address > start).min()
+            .map_or(Address::MAX, |address| address - 1);
+
+        Ok(self.with_range(start, end))
+    }
+
+    // Steps `hart`, logging the instruction if it retires.
+    pub fn step(&mut self, hart: &mut Hart, mmu: &mut MMU) -> Result<(), Trap>
+    {
+        let snapshot = Self::snapshot(hart, mmu);
+        let result = hart.step(mmu);
+        self.retire(&snapshot, hart, mmu);
+        result
+    }
+
+    // Ticks `hart` with trap handling, logging the instruction if it retires.
+    pub fn tick(&mut self, hart: &mut Hart, mmu: &mut MMU)
+    {
+        let snapshot = Self::snapshot(hart, mmu);
+        hart.tick(mmu);
+        self.retire(&snapshot, hart, mmu);
+    }
+
+    pub fn run(&mut self, hart: &mut Hart, mmu: &mut MMU) -> Trap
+    {
+        loop
+        {
+            if let Err(trap) = self.step(hart, mmu)
+            {
+                return trap
+            }
+        }
+    }
+
+    // State before a step, the instruction is read through a probe so that tracing leaves the TLB and page tables as
+    // they are.
+    fn snapshot(hart: &Hart, mmu: &MMU) -> Snapshot
+    {
+        let binary = hart.probe(mmu, hart.pc, 4, Access::Fetch)
+            .filter(|&at| hart.pc.is_multiple_of(4) && mmu.query(at).is_some_and(|protection| protection.contains(Protection::EXECUTE)))
+            .and_then(|at| mmu.read::<u32>(at).ok());
+
+        Snapshot{ pc: hart.pc, privilege: hart.privilege, x: hart.x, f: hart.f, instret: hart.csrs.instret, binary }
+    }
+
+    fn traced(&self, pc: u64) -> bool
+    {
+        self.ranges.is_empty() || self.ranges.iter().any(|&(start, end)| (start as u64..=end as u64).contains(&pc))
+    }
+
+    fn retire(&mut self, before: &Snapshot, hart: &Hart, mmu: &MMU)
+    {
+        let Some(binary) = before.binary else { return };
+        if hart.csrs.instret == before.instret || !self.traced(before.pc)
+        {
+            return
+        }
+
+        let Ok(decoder) = Decoder::new(binary) else { return };
+        let instruction = &RV_ISA[decoder.mnemonic.as_str()];
+        let digits = match hart.xlen
+        {
+            Xlen::X32 => 8,
+            Xlen::X64 => 16
+        };
+        let value = |value: u64, digits: usize| format!("0x{:0digits$x}", value & (u64::MAX >> (64 - digits * 4)));
+
+        if self.disassemble
+        {
+            let symbols: Vec<(usize, &String)> = self.symbols.iter().map(|(address, name)| (*address, name)).collect();
+            let text = Disassembler::disassemble(before.pc as usize, binary, self.symbolize.then_some(&symbols[..]), true, true);
+            let text = match text.split_once(' ')
+            {
+                Some((mnemonic, operands)) => format!("{:<7} {}", mnemonic, operands),
+                None => text
+            };
+            let _ = writeln!(self.output, "core {:3}: {} (0x{:08x}) {}", hart.id, value(before.pc, digits), binary, text);
+        }
+
+        let mut line = format!("core {:3}: {} {} (0x{:08x})", hart.id, before.privilege as u8, value(before.pc, digits), binary);
+
+        let (rd, rs1, rs2) = ((binary >> 7) as usize & 0x1F, (binary >> 15) as usize & 0x1F, (binary >> 20) as usize & 0x1F);
+        match instruction.operands.iter().find(|operand| matches!(operand, OperandType::Rd(_)))
+        {
+            Some(OperandType::Rd(RegisterFile::Float)) => line += &format!(" f{:<2} {}", rd, value(hart.f[rd], 16)),
+            Some(OperandType::Rd(RegisterFile::Int)) if rd != 0 => line += &format!(" x{:<2} {}", rd, value(hart.x[rd], digits)),
+            _ => ()
+        }
+
+        // Explicit CSR writes, csrrs and csrrc only write with a non-zero source.
+        if instruction.operands.contains(&OperandType::Csr) && (decoder.mnemonic.starts_with("csrrw") || rs1 != 0)
+        {
+            let csr = (binary >> 20) as u16;
+            if let Some(written) = hart.csrs.read(csr, Privilege::Machine)
+            {
+                line += &format!(" c{}_{} {}", csr, csr_name(csr), value(written, digits));
+            }
+        }
+
+        // Accesses of loads, stores and atomics, sized by the low bits of funct3.
+        let size = 1usize << ((binary >> 12) & 0b11);
+        let signed = |immediate: u32| ((immediate << 20) as i32 >> 20) as i64 as u64;
+        let address = |offset: u64| hart.truncate(before.x[rs1].wrapping_add(offset));
+        match instruction.opcode
+        {
+            Opcode::Load | Opcode::LoadFp => line += &format!(" mem {}", value(address(signed(binary >> 20)), digits)),
+            Opcode::Store | Opcode::StoreFp =>
+            {
+                let data = if instruction.opcode == Opcode::Store { before.x[rs2] } else { before.f[rs2] };
+                let offset = signed(((binary >> 25) << 5) | ((binary >> 7) & 0x1F));
+                line += &format!(" mem {} {}", value(address(offset), digits), value(data, size * 2));
+            },
+            Opcode::Amo =>
+            {
+                let address = address(0);
+                let stored = match decoder.mnemonic.split('.').next()
+                {
+                    Some("lr") => None,
+                    Some("sc") => (hart.x[rd] == 0 || rd == 0).then_some(before.x[rs2]),
+                    _ => hart.probe(mmu, address, size, Access::Load)
+                        .and_then(|at| if size == 4 { mmu.read::<u32>(at).map(|data| data as u64).ok() } else { mmu.read::<u64>(at).ok() })
+                };
+
+                if !decoder.mnemonic.starts_with("sc")
+                {
+                    line += &format!(" mem {}", value(address, digits));
+                }
+                if let Some(data) = stored
+                {
+                    line += &format!(" mem {} {}", value(address, digits), value(data, size * 2));
+                }
+            },
+            _ => ()
+        }
+
+        let _ = writeln!(self.output, "{}", line);
+    }
+}
+
+// Name of a CSR as printed by Spike.
+fn csr_name(csr: u16) -> String
+{
+    let name = match csr
+    {
+        FFLAGS => "fflags", FRM => "frm", FCSR => "fcsr",
+        CYCLE => "cycle", TIME => "time", INSTRET => "instret", CYCLEH => "cycleh", TIMEH => "timeh", INSTRETH => "instreth",
+        SSTATUS => "sstatus", SIE => "sie", STVEC => "stvec", SCOUNTEREN => "scounteren", SSCRATCH => "sscratch",
+        SEPC => "sepc", SCAUSE => "scause", STVAL => "stval", SIP => "sip", SATP => "satp",
+        MVENDORID => "mvendorid", MARCHID => "marchid", MIMPID => "mimpid", MHARTID => "mhartid", MCONFIGPTR => "mconfigptr",
+        MSTATUS => "mstatus", MISA => "misa", MEDELEG => "medeleg", MIDELEG => "mideleg", MIE => "mie", MTVEC => "mtvec",
+        MCOUNTEREN => "mcounteren", MSTATUSH => "mstatush", MSCRATCH => "mscratch", MEPC => "mepc", MCAUSE => "mcause",
+        MTVAL => "mtval", MIP => "mip", MCYCLE => "mcycle", MINSTRET => "minstret", MCYCLEH => "mcycleh", MINSTRETH => "minstreth",
+        PMPCFG0..=PMPCFG15 => return format!("pmpcfg{}", csr - PMPCFG0),
+        PMPADDR0..=PMPADDR63 => return format!("pmpaddr{}", csr - PMPADDR0),
+        _ => "unknown"
+    };
+    name.to_string()
+}
+
+// Retired instruction parsed from a commit line, with the disassembly line logged before it if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Commit
+{
+    pub line: usize, // Line number in the trace, starting at 1.
+    pub text: String,
+    pub disassembly: Option<String>,
+    pub privilege: u8,
+    pub pc: u64,
+    pub binary: u64,
+    pub writes: Vec<(String /* Register */, u64 /* Value */)>,
+    pub accesses: Vec<(u64 /* Address */, Option<u64> /* Stored data */)>
+}
+
+impl Commit
+{
+    // Parses a line in the layout of Spike's "--log-commits", None for any other line.
+    pub fn parse(line: usize, text: &str) -> Option<Self>
+    {
+        let hex = |token: &str| token.strip_prefix("0x").and_then(|digits| u64::from_str_radix(digits, 16).ok());
+
+        let (_, fields) = text.strip_prefix("core")?.split_once(':')?;
+        let mut tokens = fields.split_whitespace().peekable();
+
+        let privilege = tokens.next()?.parse::<u8>().ok()?;
+        let pc = hex(tokens.next()?)?;
+        let binary = hex(tokens.next()?.strip_prefix('(')?.strip_suffix(')')?)?;
+
+        let (mut writes, mut accesses) = (Vec::new(), Vec::new());
+        while let Some(token) = tokens.next()
+        {
+            match token
+            {
+                "mem" =>
+                {
+                    let address = hex(tokens.next()?)?;
+                    let data = tokens.next_if(|token| token.starts_with("0x")).and_then(hex);
+                    accesses.push((address, data));
+                },
+                register => writes.push((register.to_string(), hex(tokens.next()?)?))
+            }
+        }
+
+        Some(Commit{ line, text: text.to_string(), disassembly: None, privilege, pc, binary, writes, accesses })
+    }
+}
+
+// Point where two traces stop agreeing, `left` or `right` is None when that trace ended first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence
+{
+    pub index: usize, // Number of instructions both traces agree on.
+    pub context: Vec<Commit>,
+    pub left: Option<Commit>,
+    pub right: Option<Commit>
+}
+
+impl fmt::Display for Divergence
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let line = |commit: &Option<Commit>| commit.as_ref().map_or("end of trace".to_string(), |commit| format!("line {}", commit.line));
+        writeln!(f, "Traces diverge after {} instructions ({} of the first trace, {} of the second):", self.index, line(&self.left), line(&self.right))?;
+
+        for commit in &self.context
+        {
+            if let Some(disassembly) = &commit.disassembly
+            {
+                writeln!(f, "  {}", disassembly)?;
+            }
+            writeln!(f, "  {}", commit.text)?;
+        }
+
+        for (sign, commit) in [('-', &self.left), ('+', &self.right)]
+        {
+            match commit
+            {
+                Some(commit) =>
+                {
+                    if let Some(disassembly) = &commit.disassembly
+                    {
+                        writeln!(f, "{} {}", sign, disassembly)?;
+                    }
+                    writeln!(f, "{} {}", sign, commit.text)?;
+                },
+                None => writeln!(f, "{} (end of trace)", sign)?
+            }
+        }
+        Ok(())
+    }
+}
+
+// Lock-step comparison of two commit traces, such as one of aem and one of Spike. Instructions must agree in pc,
+// register writes and memory accesses. CSR writes are only compared with `csrs`, simulators log different side effects.
+pub struct TraceDiff
+{
+    pub context: usize, // Number of agreeing instructions reported before the divergence.
+    pub csrs: bool
+}
+
+impl TraceDiff
+{
+    pub fn new(context: usize) -> Self
+    {
+        TraceDiff{ context, csrs: false }
+    }
+
+    // Returns the first divergence of the traces, None when they are the same.
+    pub fn compare(&self, left: impl BufRead, right: impl BufRead) -> io::Result<Option<Divergence>>
+    {
+        let (mut left, mut right) = (Commits::new(left), Commits::new(right));
+        let mut context = VecDeque::with_capacity(self.context + 1);
+
+        let mut index = 0;
+        loop
+        {
+            match (left.next()?, right.next()?)
+            {
+                (None, None) => return Ok(None),
+                (Some(a), Some(b)) if self.agree(&a, &b) =>
+                {
+                    context.push_back(a);
+                    if context.len() > self.context
+                    {
+                        context.pop_front();
+                    }
+                    index += 1;
+                },
+                (left, right) => return Ok(Some(Divergence{ index, context: context.into(), left, right }))
+            }
+        }
+    }
+
+    fn agree(&self, left: &Commit, right: &Commit) -> bool
+    {
+        let writes = |commit: &Commit| commit.writes.iter()
+            .filter(|(register, _)| self.csrs || !register.starts_with('c'))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        left.pc == right.pc && left.accesses == right.accesses && writes(left) == writes(right)
+    }
+}
+
+// Commits of a trace in order, other lines are skipped. A disassembly line is kept for the commit following it.
+struct Commits<R: BufRead>
+{
+    reader: R,
+    line: usize,
+    disassembly: Option<String>
+}
+
+impl<R: BufRead> Commits<R>
+{
+    fn new(reader: R) -> Self
+    {
+        Commits{ reader, line: 0, disassembly: None }
+    }
+
+    fn next(&mut self) -> io::Result<Option<Commit>>
+    {
+        let mut text = String::new();
+        loop
+        {
+            text.clear();
+            if self.reader.read_line(&mut text)? == 0
+            {
+                return Ok(None)
+            }
+            self.line += 1;
+
+            let text = text.trim_end();
+            match Commit::parse(self.line, text)
+            {
+                Some(commit) => return Ok(Some(Commit{ disassembly: self.disassembly.take(), ..commit })),
+                None => self.disassembly = text.starts_with("core").then(|| text.to_string())
+            }
+        }
+    }
+}
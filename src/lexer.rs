@@ -6,6 +6,7 @@ use crate::{
 
 use lazy_static::lazy_static;
 use std::convert::TryFrom;
+use std::fmt;
 use num_traits::Num;
 use regex::Regex;
 
@@ -73,6 +74,32 @@ impl TryFrom<Operand> for RValue<i32>
     }
 }
 
+impl<T: Num + fmt::Display> fmt::Display for RValue<T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            RValue::Register(prefix, index) => write!(f, "{}{}", prefix, index),
+            RValue::Identifier(name) => write!(f, "{}", name),
+            RValue::Immediate(value) => write!(f, "{}", value)
+        }
+    }
+}
+
+impl fmt::Display for Operand
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        { // Operands are printed in the same syntax the lexer accepts.
+            Operand::RValue(rvalue) => write!(f, "{}", rvalue),
+            Operand::RelocationFn(func_str, symbol) => write!(f, "%{}({})", func_str.trim_start_matches('%'), symbol),
+            Operand::Address(base, offset) => write!(f, "{}({})", offset, base)
+        }
+    }
+}
+
 // Types that directly emit to binary.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Emittable
@@ -267,7 +294,7 @@ impl Lexer
                     if let Some((name_str, value_str)) = args_str.split_once(',')
                     {
                         let const_val = i32::parse(value_str.trim())
-                                    .map(|val|RValue::Immediate(val))
+                                    .map(RValue::Immediate)
                                     .map_err(|_|LexerErr::Parsing(
                                         format!("Unable to parse immediate value: {}", value_str)
                                     ))?;
@@ -300,7 +327,7 @@ impl Lexer
                         .collect();
 
                     // Too few or too many arguments provided.
-                    if args_split.len() <= 0 || args_split.len() > 3
+                    if args_split.is_empty() || args_split.len() > 3
                     {
                         return Err(LexerErr::Syntax(
                             format!(r#"Expected 1-3 arguments. {} arguments were provided."#, args_split.len())
@@ -345,7 +372,9 @@ impl Lexer
                             };
                         } 
                     }
-                    Ok(Directive::Section(directive_str.into(), flags, 4))
+                    // Section names are stored without their leading '.' (e.g. ".section .vectors, "ax"").
+                    let name_str = args_str.split(',').next().unwrap_or_default().trim().trim_start_matches('.');
+                    Ok(Directive::Section(name_str.into(), flags, 4))
                 },
                 _ => Err(LexerErr::Parsing(
                     format!(r#"Unable to parse directive: "{}""#, directive_str)
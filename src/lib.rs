@@ -4,6 +4,15 @@ pub mod util;
 // Memory utility.
 pub mod mem;
 
+// Memory management unit.
+pub mod mmu;
+
+// Physical memory protection.
+pub mod pmp;
+
+// Memory-mapped devices.
+pub mod dev;
+
 // RISC-V ISA.
 pub mod arch;
 
@@ -16,5 +25,17 @@ pub mod codec;
 // Language assembler.
 pub mod asm;
 
+// Object disassembler.
+pub mod disasm;
+
+// ELF executable parsing.
+pub mod elf;
+
+// Object loader.
+pub mod loader;
+
+// RISC-V emulator.
+pub mod emu;
+
 // Object linker.
 // pub mod linker;
\ No newline at end of file
@@ -0,0 +1,227 @@
+use std::{
+    cell::RefCell, collections::{HashMap, hash_map::RandomState}, hash::{BuildHasher, Hasher}, io::{self, Write},
+    rc::Rc
+};
+
+use crate::{
+    asm::*, dev::htif::*, elf::*, mem::*, mmu::*
+};
+
+// Auxiliary vector entry types passed on the initial process stack.
+pub const AT_NULL: u64   = 0;
+pub const AT_PHDR: u64   = 3;
+pub const AT_PHENT: u64  = 4;
+pub const AT_PHNUM: u64  = 5;
+pub const AT_PAGESZ: u64 = 6;
+pub const AT_ENTRY: u64  = 9;
+pub const AT_RANDOM: u64 = 25;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoaderErr
+{
+    Layout(String),
+    Memory(MMUErr),
+    Elf(ElfErr)
+}
+
+pub struct Loader
+{
+    pub mmu: MMU,
+    pub entry: Address,
+    pub symbols: HashMap<String /* Identifier */, Address /* Loaded address */>,
+    pub brk: Address,     // Page aligned end of the loaded image, where a heap may start.
+    pub elf: Option<Elf>  // Executable the image was loaded from.
+}
+
+impl Loader
+{
+    // Loads `object` at `base` into a new address space of `size` bytes.
+    pub fn new(object: &Object, base: Address, size: usize) -> Result<Self, LoaderErr>
+    {
+        Self::with_mmu(MMU::new(size), object, base)
+    }
+
+    // Places each section of `object` at `base` plus it's address, protected according to it's attributes.
+    pub fn with_mmu(mut mmu: MMU, object: &Object, base: Address) -> Result<Self, LoaderErr>
+    {
+        for section in object.sections.iter().filter(|section| section.length > 0)
+        {
+            let start = base + section.address;
+            let end = start + section.length;
+
+            mmu.physical_mut(start..end)
+                .ok_or_else(|| LoaderErr::Layout(
+                    format!(r#"Section "{}" does not fit in memory at address: 0x{:x}"#, section.name, start)
+                ))?
+                .copy_from_slice(&object.binary[section.address..section.address + section.length]);
+            mmu.protect(start, end - 1, attributes_to_protection(section.attributes.clone()))
+                .map_err(LoaderErr::Memory)?;
+        }
+
+        let symbols: HashMap<String, Address> = object.symbols.iter()
+            .map(|(name, address)| (name.clone(), base + address))
+            .collect();
+
+        // Execution starts at "_start" when defined, otherwise at the start of "text".
+        let entry = symbols.get("_start").copied()
+            .or_else(|| object.sections.iter().find(|section| section.name == "text").map(|section| base + section.address))
+            .unwrap_or(base);
+
+        let brk = object.sections.iter().map(|section| align_address(base + section.address + section.length, PAGE_SIZE))
+            .max()
+            .unwrap_or(base);
+
+        Ok(Loader{ mmu, entry, symbols, brk, elf: None })
+    }
+
+    // Loads the RISC-V executable `bytes` into a new address space of `size` bytes.
+    pub fn from_elf(bytes: &[u8], size: usize) -> Result<Self, LoaderErr>
+    {
+        Self::with_elf(MMU::new(size), bytes)
+    }
+
+    // Places each loadable segment of the executable at its address, protected according to its flags. The part of a
+    // segment not backed by the file (.bss) is zero filled.
+    pub fn with_elf(mut mmu: MMU, bytes: &[u8]) -> Result<Self, LoaderErr>
+    {
+        let elf = Elf::parse(bytes).map_err(LoaderErr::Elf)?;
+        let mut brk = 0;
+
+        for segment in elf.program_headers.iter().filter(|header| header.kind == PT_LOAD && header.memory_size > 0)
+        {
+            let (start, offset) = (segment.address as usize, segment.offset as usize);
+            let file_size = (segment.file_size as usize).min(segment.memory_size as usize);
+            let data = offset.checked_add(file_size).and_then(|data_end| bytes.get(offset..data_end)).ok_or_else(|| LoaderErr::Elf(ElfErr::Truncated(
+                format!("Segment data truncated at offset: 0x{:x}", offset)
+            )))?;
+
+            let target = start.checked_add(segment.memory_size as usize).and_then(|end| mmu.physical_mut(start..end))
+                .ok_or_else(|| LoaderErr::Layout(
+                    format!("Segment does not fit in memory at address: 0x{:x}", start)
+                ))?;
+            target[..file_size].copy_from_slice(data);
+            target[file_size..].fill(0);
+
+            let end = start + target.len();
+            mmu.protect(start, end - 1, flags_to_protection(segment.flags)).map_err(LoaderErr::Memory)?;
+
+            brk = brk.max(align_address(end, PAGE_SIZE));
+        }
+
+        let symbols = elf.symbols.iter()
+            .map(|(name, &value)| (name.clone(), value as Address))
+            .collect();
+
+        Ok(Loader{ mmu, entry: elf.entry as Address, symbols, brk, elf: Some(elf) })
+    }
+
+    // Builds the initial stack of a process in a readable and writable region of `size` bytes below `top`. From the
+    // returned stack pointer up it holds argc, the argv and envp pointer arrays, the auxiliary vector and the strings.
+    pub fn stack(&mut self, top: Address, size: usize, args: &[&str], env: &[&str]) -> Result<Address, LoaderErr>
+    {
+        let Some(elf) = &self.elf else
+        {
+            return Err(LoaderErr::Layout("The initial stack requires an executable".to_string()))
+        };
+
+        let bottom = top.checked_sub(size).filter(|_| top <= self.mmu.memory.len())
+            .ok_or_else(|| LoaderErr::Layout(format!("Stack does not fit in memory at address: 0x{:x}", top)))?;
+        self.mmu.protect(bottom, top - 1, Protection::READ | Protection::WRITE).map_err(LoaderErr::Memory)?;
+
+        // Strings and the random bytes for AT_RANDOM are placed at the top.
+        let mut data = Vec::new();
+        let mut random = Vec::with_capacity(16);
+        for _ in 0..2
+        {
+            random.extend_from_slice(&RandomState::new().build_hasher().finish().to_le_bytes());
+        }
+        data.extend_from_slice(&random);
+
+        let mut strings = Vec::with_capacity(args.len() + env.len());
+        for string in args.iter().chain(env)
+        {
+            strings.push(data.len());
+            data.extend_from_slice(string.as_bytes());
+            data.push(0);
+        }
+
+        let data_start = top.checked_sub(data.len()).filter(|&start| start >= bottom)
+            .ok_or_else(|| LoaderErr::Layout("Arguments do not fit on the stack".to_string()))?;
+        let pointer = |offset: usize| (data_start + offset) as u64;
+
+        let mut auxv = vec![(AT_PAGESZ, PAGE_SIZE as u64), (AT_ENTRY, elf.entry), (AT_RANDOM, pointer(0))];
+        if let Some(address) = elf.header_address()
+        {
+            auxv.extend([(AT_PHDR, address), (AT_PHENT, elf.header_size as u64), (AT_PHNUM, elf.program_headers.len() as u64)]);
+        }
+        auxv.push((AT_NULL, 0));
+
+        let mut words = vec![args.len() as u64];
+        words.extend(strings[..args.len()].iter().map(|&offset| pointer(offset)));
+        words.push(0);
+        words.extend(strings[args.len()..].iter().map(|&offset| pointer(offset)));
+        words.push(0);
+        words.extend(auxv.iter().flat_map(|&(kind, value)| [kind, value]));
+
+        let word = elf.class.word();
+        let sp = data_start.checked_sub(words.len() * word).map(|sp| sp & !0xF).filter(|&sp| sp >= bottom)
+            .ok_or_else(|| LoaderErr::Layout("Arguments do not fit on the stack".to_string()))?;
+
+        let stack = self.mmu.physical_mut(sp..top)
+            .ok_or_else(|| LoaderErr::Layout(format!("Stack does not fit in memory at address: 0x{:x}", top)))?;
+        for (index, value) in words.iter().enumerate()
+        {
+            stack[index * word..(index + 1) * word].copy_from_slice(&value.to_le_bytes()[..word]);
+        }
+        stack[data_start - sp..].copy_from_slice(&data);
+
+        Ok(sp)
+    }
+
+    // Maps a host-target interface over the "tohost" symbol, replying at "fromhost" when defined.
+    pub fn htif(&mut self, output: Box<dyn Write>) -> Result<Rc<RefCell<Htif>>, LoaderErr>
+    {
+        let tohost = *self.symbols.get("tohost")
+            .ok_or_else(|| LoaderErr::Layout(r#"Symbol "tohost" is not defined"#.to_string()))?;
+
+        let end = tohost.checked_add(7)
+            .ok_or_else(|| LoaderErr::Layout(format!(r#"Symbol "tohost" is out of range: 0x{:x}"#, tohost)))?;
+
+        let htif = Rc::new(RefCell::new(Htif::new(output, self.symbols.get("fromhost").copied())));
+        self.mmu.unprotect(tohost, end);
+        self.mmu.map(tohost, end, htif.clone()).map_err(LoaderErr::Memory)?;
+        Ok(htif)
+    }
+
+    // Writes the signature between the "begin_signature" and "end_signature" symbols in the format of the
+    // architecture tests.
+    pub fn signature(&self, granularity: usize, output: &mut dyn Write) -> io::Result<()>
+    {
+        match (self.symbols.get("begin_signature"), self.symbols.get("end_signature"))
+        {
+            (Some(&start), Some(&end)) => write_signature(&self.mmu.memory, start, end, granularity, output),
+            _ => Err(io::Error::new(io::ErrorKind::NotFound, "Signature symbols are not defined"))
+        }
+    }
+}
+
+// Protection of a loadable segment with the program header `flags`.
+pub fn flags_to_protection(flags: u32) -> Protection
+{
+    let mut protection = Protection::empty();
+
+    if flags & PF_R != 0
+    {
+        protection |= Protection::READ;
+    }
+    if flags & PF_W != 0
+    {
+        protection |= Protection::WRITE;
+    }
+    if flags & PF_X != 0
+    {
+        protection |= Protection::EXECUTE;
+    }
+
+    protection
+}
@@ -1,5 +1,6 @@
 use aem::{ 
-    asm::*, assemble
+    asm::*, assemble,
+    disasm::*
 };
 
 fn main() {
@@ -24,21 +25,8 @@ fn main() {
         nop"#)
     {
         Ok(object) => 
-        {   // print hex view of generated assembly.
-            for (i, chunk) in object.binary.chunks(4).enumerate() 
-            {
-                // Initialize bytes array with zeros.
-                let mut bytes = [0u8; 4];
-
-                // Copy up to 4 bytes from the chunk into the bytes array.
-                bytes[..chunk.len()].copy_from_slice(chunk);
-
-                // Convert bytes to u32; using little-endian format here.
-                let value = u32::from_le_bytes(bytes);
-
-                // Print the u32 value in hexadecimal format.
-                println!("{:04x}: 0x{:08x}", i * 4, value);
-            }
+        {   // print disassembly of generated assembly.
+            print!("{}", Disassembler::new(&object));
         },
         Err(e) => 
         {
@@ -1,5 +1,8 @@
 use bitflags::bitflags;
 
+// Physical or virtual memory address.
+pub type Address = usize;
+
 // Returns `address` aligned to `alignment`.
 pub fn align_address(address: usize, alignment: usize) -> usize
 {
@@ -21,6 +24,7 @@ bitflags!
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Section
 { // Section address, length and attributes.
     pub name: String,
@@ -1,8 +1,17 @@
+use std::{
+    cell::RefCell, collections::HashMap, ops::Range, rc::Rc
+};
 use bitflags::bitflags;
-use super::memory::*;
+use crate::{
+    mem::*, pmp::Pmp, dev::Device
+};
+
+// Size and alignment of the memory region a load-reserved instruction reserves.
+pub const RESERVATION_GRANULE: usize = 8;
 
 bitflags!
 { // Memory protection flags.
+    #[derive(Debug, Clone, Copy, PartialEq)]
     pub struct Protection: u32
     {
         const READ      = 0b0000_0001;
@@ -49,17 +58,190 @@ impl MemoryPage
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum MMUErr
 {
     AccessViolation(String),
     MisalignedAccess(String),
-    OutOfBounds(String)
+    OutOfBounds(String),
+    PageFault(String),
+    BusError(String)
+}
+
+// Kind of memory access being translated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access
+{
+    Fetch,
+    Load,
+    Store // Stores and AMOs.
+}
+
+// Virtual memory scheme selected by satp.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Paging
+{
+    Bare,
+    Sv32,
+    Sv39,
+    Sv48
+}
+
+impl Paging
+{
+    fn levels(self) -> u32
+    {
+        match self
+        {
+            Paging::Bare => 0,
+            Paging::Sv32 => 2,
+            Paging::Sv39 => 3,
+            Paging::Sv48 => 4
+        }
+    }
+
+    // Bits of the virtual page number translated by each level.
+    fn vpn_bits(self) -> u32
+    {
+        if self == Paging::Sv32 { 10 } else { 9 }
+    }
+
+    fn pte_size(self) -> usize
+    {
+        if self == Paging::Sv32 { 4 } else { 8 }
+    }
+}
+
+// Page size and page table entry fields shared by all paging schemes.
+pub const PAGE_SIZE: usize = 4096;
+const PTE_VALID: u64 = 1;
+const PTE_PPN_SHIFT: u32 = 10;
+
+// Address translation state of the accessing hart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Translation
+{
+    pub paging: Paging,
+    pub root: Address, // Physical address of the root page table.
+    pub asid: u16,
+    pub user: bool,    // Accessing with user privilege.
+    pub sum: bool,     // Supervisor accesses to user pages are permitted.
+    pub mxr: bool,     // Loads from executable pages are permitted.
+    pub pmp: Pmp       // Physical memory protection of the implicit page table accesses.
+}
+
+impl Translation
+{
+    // Whether the leaf permissions in `flags` allow `access` with this privilege.
+    pub fn permits(&self, flags: Protection, access: Access) -> bool
+    {
+        let permitted = match access
+        {
+            Access::Fetch => flags.contains(Protection::EXECUTE),
+            Access::Load  => flags.contains(Protection::READ) || (self.mxr && flags.contains(Protection::EXECUTE)),
+            Access::Store => flags.contains(Protection::WRITE)
+        };
+
+        // Supervisor accesses to user pages require SUM and are never fetches.
+        let privileged = match (flags.contains(Protection::USER), self.user)
+        {
+            (true, false) => self.sum && access != Access::Fetch,
+            (user_page, user) => user_page == user
+        };
+
+        permitted && privileged
+    }
+}
+
+// Leaf page table entry mapping a virtual page, or a superpage of `pages` pages, to physical memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageMapping
+{
+    pub virtual_page: u64,
+    pub physical_page: usize,
+    pub pages: u64,
+    pub flags: Protection,
+    pub pte_address: Address
+}
+
+impl PageMapping
+{
+    pub fn contains(&self, address: u64) -> bool
+    {
+        (address >> 12) & !(self.pages - 1) == self.virtual_page
+    }
+
+    pub fn physical(&self, address: u64) -> Address
+    {
+        let page = self.physical_page + ((address >> 12) & (self.pages - 1)) as usize;
+        page * PAGE_SIZE + (address as usize & (PAGE_SIZE - 1))
+    }
+}
+
+// Device mapped onto the inclusive physical range `start` to `end`, shared with the code that drives it.
+pub struct DeviceMapping
+{
+    pub start: Address,
+    pub end: Address,
+    pub device: Rc<RefCell<dyn Device>>
+}
+
+impl DeviceMapping
+{
+    fn contains(&self, addr: Address) -> bool
+    {
+        addr >= self.start && addr <= self.end
+    }
+}
+
+// Physical memory as devices see it for DMA, bypassing protection. Writable views invalidate the reservations on the
+// granules they cover like stores from a hart.
+pub struct PhysicalMemory<'a>
+{
+    memory: &'a mut [u8],
+    reservations: &'a mut HashMap<usize, Address>
+}
+
+impl PhysicalMemory<'_>
+{
+    pub fn len(&self) -> usize
+    {
+        self.memory.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.memory.is_empty()
+    }
+
+    pub fn get(&self, range: Range<Address>) -> Option<&[u8]>
+    {
+        self.memory.get(range)
+    }
+
+    pub fn get_mut(&mut self, range: Range<Address>) -> Option<&mut [u8]>
+    {
+        store(self.memory, self.reservations, range)
+    }
+}
+
+// Bytes of `range` about to be stored to, every store to physical memory goes through here to drop the reservations
+// it breaks. None when out of bounds.
+fn store<'a>(memory: &'a mut [u8], reservations: &mut HashMap<usize, Address>, range: Range<Address>) -> Option<&'a mut [u8]>
+{
+    let granules = (range.start & !(RESERVATION_GRANULE - 1))..range.end;
+    let bytes = memory.get_mut(range)?;
+
+    reservations.retain(|_, granule| !granules.contains(granule));
+    Some(bytes)
 }
 
 pub struct MMU
 {
     pub memory: Vec<u8>,
-    pub pages: Vec<MemoryPage>
+    pub pages: Vec<MemoryPage>,
+    pub devices: Vec<DeviceMapping>,
+    pub reservations: HashMap<usize /* Hart id */, Address /* Reserved granule */>
 }
 
 impl MMU
@@ -69,24 +251,263 @@ impl MMU
         Self
         {
             memory: vec![0; size],
-            pages: Vec::new()
+            pages: Vec::new(),
+            devices: Vec::new(),
+            reservations: HashMap::new()
         }
     }
 
-    pub fn protect(&mut self, start: Address, end: Address, protection: Protection) -> Result<(), MMUErr>
+    // Reserves the granule containing `address` for `hart`, replacing it's previous reservation.
+    pub fn reserve(&mut self, hart: usize, address: Address)
     {
-        for page in &self.pages {
-            // New page is within an already present page, or end is within the page.
-            if (start >= page.start && start < page.end) || (end > page.start && end <= page.end) || (start <= page.start && end >= page.end)
+        self.reservations.insert(hart, address & !(RESERVATION_GRANULE - 1));
+    }
+
+    // Physical memory in the form devices are ticked with.
+    pub fn physical(&mut self) -> PhysicalMemory<'_>
+    {
+        PhysicalMemory{ memory: &mut self.memory, reservations: &mut self.reservations }
+    }
+
+    // Writable bytes of physical memory, bypassing protection and devices, for loaders and emulated system calls.
+    // Reservations on the granules covered are invalidated.
+    pub fn physical_mut(&mut self, range: Range<Address>) -> Option<&mut [u8]>
+    {
+        store(&mut self.memory, &mut self.reservations, range)
+    }
+
+    // Releases the reservation of `hart`, returning whether it still covered `address`.
+    pub fn release(&mut self, hart: usize, address: Address) -> bool
+    {
+        self.reservations.remove(&hart) == Some(address & !(RESERVATION_GRANULE - 1))
+    }
+
+    // Translates the virtual `address` by walking the page tables, setting the accessed and dirty bits of the leaf entry.
+    pub fn translate(&mut self, address: u64, access: Access, translation: &Translation) -> Result<Address, MMUErr>
+    {
+        match translation.paging
+        {
+            Paging::Bare => Ok(address as Address),
+            _ => self.walk(address, access, translation).map(|mapping| mapping.physical(address))
+        }
+    }
+
+    // Finds the leaf entry mapping `address` for `access`, updating it's accessed and dirty bits in place
+    // as if by an atomic write to the entry.
+    pub fn walk(&mut self, address: u64, access: Access, translation: &Translation) -> Result<PageMapping, MMUErr>
+    {
+        let mut mapping = self.probe(address, translation)?;
+
+        if !translation.permits(mapping.flags, access)
+        {
+            return Err(Self::page_fault(address))
+        }
+
+        let mut flags = mapping.flags | Protection::ACCESSED;
+        if access == Access::Store
+        {
+            flags |= Protection::DIRTY;
+        }
+
+        if flags != mapping.flags
+        {
+            Self::check_pte(mapping.pte_address, Access::Store, translation)?;
+
+            let update = (flags.bits() as u64) << 1;
+            match translation.paging
             {
-                return Err(MMUErr::AccessViolation(format!(r#"Memory page overlap between addresses: {} - {}"#, start, end)));
+                Paging::Sv32 => self.write(mapping.pte_address, self.read::<u32>(mapping.pte_address)? | update as u32)?,
+                _ => self.write(mapping.pte_address, self.read::<u64>(mapping.pte_address)? | update)?
             }
+            mapping.flags = flags;
         }
+        Ok(mapping)
+    }
+
+    // Finds the leaf entry mapping `address` without checking or updating it's permissions.
+    // Faults on the page table entries themselves are reported as access errors.
+    pub fn probe(&self, address: u64, translation: &Translation) -> Result<PageMapping, MMUErr>
+    {
+        let paging = translation.paging;
+
+        // Virtual addresses must be sign-extended from their most significant bit.
+        let va_bits = 12 + paging.levels() * paging.vpn_bits();
+        if paging != Paging::Sv32 && ((address as i64) << (64 - va_bits) >> (64 - va_bits)) as u64 != address
+        {
+            return Err(Self::page_fault(address))
+        }
+
+        let vpn = |level: u32| (address >> (12 + level * paging.vpn_bits())) as usize & ((1 << paging.vpn_bits()) - 1);
+
+        let mut table = translation.root;
+        let mut level = paging.levels() - 1;
+
+        let (pte_address, pte) = loop
+        {
+            let pte_address = table + vpn(level) * paging.pte_size();
+            Self::check_pte(pte_address, Access::Load, translation)?;
+
+            let pte = match paging
+            {
+                Paging::Sv32 => self.read::<u32>(pte_address)? as u64,
+                _ => self.read::<u64>(pte_address)?
+            };
+
+            // Reserved bits, invalid entries and write-only permissions fault.
+            let flags = Protection::from_bits_truncate((pte >> 1) as u32);
+            if pte & PTE_VALID == 0 || (paging != Paging::Sv32 && pte >> 54 != 0)
+                || (flags.contains(Protection::WRITE) && !flags.contains(Protection::READ))
+            {
+                return Err(Self::page_fault(address))
+            }
+
+            if flags.intersects(Protection::READ | Protection::EXECUTE)
+            {
+                break (pte_address, pte)
+            }
+
+            // Non-leaf entries keep the accessed, dirty and user bits clear.
+            if level == 0 || flags.intersects(Protection::ACCESSED | Protection::DIRTY | Protection::USER)
+            {
+                return Err(Self::page_fault(address))
+            }
+
+            table = Self::pte_ppn(paging, pte) * PAGE_SIZE;
+            level -= 1;
+        };
+
+        // Superpages must be aligned to their size.
+        let pages = 1u64 << (level * paging.vpn_bits());
+        let physical_page = Self::pte_ppn(paging, pte);
+
+        if physical_page as u64 & (pages - 1) != 0
+        {
+            return Err(Self::page_fault(address))
+        }
+
+        Ok(PageMapping{
+            virtual_page: (address >> 12) & !(pages - 1),
+            physical_page,
+            pages,
+            flags: Protection::from_bits_truncate((pte >> 1) as u32),
+            pte_address
+        })
+    }
+
+    fn page_fault(address: u64) -> MMUErr
+    {
+        MMUErr::PageFault(format!("Page fault at virtual address: 0x{:x}", address))
+    }
+
+    // Page table accesses are checked by PMP as supervisor accesses.
+    fn check_pte(pte_address: Address, access: Access, translation: &Translation) -> Result<(), MMUErr>
+    {
+        match translation.pmp.permits(pte_address, translation.paging.pte_size(), access, false)
+        {
+            true => Ok(()),
+            false => Err(MMUErr::AccessViolation(format!("PMP violation at page table entry address: 0x{:x}", pte_address)))
+        }
+    }
+
+    fn pte_ppn(paging: Paging, pte: u64) -> usize
+    {
+        match paging
+        {
+            Paging::Sv32 => (pte >> PTE_PPN_SHIFT) as usize & ((1 << 22) - 1),
+            _ => (pte >> PTE_PPN_SHIFT) as usize & ((1 << 44) - 1)
+        }
+    }
+
+    pub fn protect(&mut self, start: Address, end: Address, protection: Protection) -> Result<(), MMUErr>
+    {
+        self.check_overlap(start, end)?;
 
         self.pages.push(MemoryPage{ start, end, protection });
         Ok(())
     }
 
+    // Removes the protection of the inclusive range `start` to `end`, partially covered pages keep their remainder.
+    pub fn unprotect(&mut self, start: Address, end: Address)
+    {
+        let mut pages = Vec::with_capacity(self.pages.len());
+        for page in self.pages.drain(..)
+        {
+            if start > page.end || end < page.start
+            {
+                pages.push(page);
+                continue
+            }
+
+            if page.start < start
+            {
+                pages.push(MemoryPage{ start: page.start, end: start - 1, protection: page.protection });
+            }
+            if page.end > end
+            {
+                pages.push(MemoryPage{ start: end + 1, end: page.end, protection: page.protection });
+            }
+        }
+        self.pages = pages;
+    }
+
+    // Maps `device` onto the inclusive range `start` to `end`, accesses within it no longer reach memory.
+    pub fn map(&mut self, start: Address, end: Address, device: Rc<RefCell<dyn Device>>) -> Result<(), MMUErr>
+    {
+        self.check_overlap(start, end)?;
+
+        self.devices.push(DeviceMapping{ start, end, device });
+        Ok(())
+    }
+
+    // Advances every mapped device by one machine step, once per round of hart ticks.
+    pub fn tick(&mut self)
+    {
+        let mut memory = PhysicalMemory{ memory: &mut self.memory, reservations: &mut self.reservations };
+        for mapping in &self.devices
+        {
+            mapping.device.borrow_mut().tick(&mut memory);
+        }
+    }
+
+    // Interrupt pending bits raised by the mapped devices for `hart`.
+    pub fn interrupts(&self, hart: usize) -> u64
+    {
+        self.devices.iter().fold(0, |pending, mapping| pending | mapping.device.borrow().interrupts(hart))
+    }
+
+    // Platform timer of the first device providing one.
+    pub fn time(&self) -> Option<u64>
+    {
+        self.devices.iter().find_map(|mapping| mapping.device.borrow().time())
+    }
+
+    fn check_overlap(&self, start: Address, end: Address) -> Result<(), MMUErr>
+    {
+        let ranges = self.pages.iter().map(|page| (page.start, page.end))
+            .chain(self.devices.iter().map(|mapping| (mapping.start, mapping.end)));
+
+        for (page_start, page_end) in ranges {
+            // Page bounds are inclusive, any shared address is an overlap.
+            if start <= page_end && end >= page_start
+            {
+                return Err(MMUErr::AccessViolation(format!(r#"Memory page overlap between addresses: {} - {}"#, start, end)));
+            }
+        }
+        Ok(())
+    }
+
+    // Device mapped at `address` and the offset into it, accesses must not cross the end of the mapping.
+    fn device(&self, address: Address, width: usize) -> Option<Result<(&DeviceMapping, Address), MMUErr>>
+    {
+        let mapping = self.devices.iter().find(|mapping| mapping.contains(address))?;
+
+        match width <= 8 && address + width - 1 <= mapping.end
+        {
+            true => Some(Ok((mapping, address - mapping.start))),
+            false => Some(Err(MMUErr::BusError(format!("Device access crossing its mapping at address: {}", address))))
+        }
+    }
+
     pub fn query(&self, addr: Address) -> Option<Protection>
     {
         for page in &self.pages
@@ -101,6 +522,12 @@ impl MMU
 
     pub fn read_byte(&self, address: Address) -> Result<u8, MMUErr>
     {
+        if let Some(mapping) = self.device(address, 1)
+        {
+            let (mapping, offset) = mapping?;
+            return mapping.device.borrow_mut().read(offset, 1).map(|value| value as u8)
+        }
+
         if let Some(flags) = self.query(address)
         {
             if flags.contains(Protection::EXECUTE) || flags.contains(Protection::READ)
@@ -111,7 +538,8 @@ impl MMU
             {
                 Err(MMUErr::AccessViolation(format!("Memory read violation at address: {}", address)))
             }
-        } else
+        }
+        else
         {
             Err(MMUErr::OutOfBounds(format!("Address out of bounds: {}", address)))
         }
@@ -119,18 +547,27 @@ impl MMU
 
     pub fn write_byte(&mut self, address: Address, value: u8) -> Result<(), MMUErr>
     {
+        if let Some(mapping) = self.device(address, 1)
+        {
+            let (mapping, offset) = mapping?;
+            return mapping.device.borrow_mut().write(offset, 1, value as u64)
+        }
+
         if let Some(flags) = self.query(address)
         {
             if flags.contains(Protection::WRITE)
             {
-                self.memory[address] = value;
+                let byte = self.physical_mut(address..address + 1)
+                    .ok_or_else(|| MMUErr::OutOfBounds(format!("Address out of bounds: {}", address)))?;
+                byte[0] = value;
                 Ok(())
             }
             else
             {
                 Err(MMUErr::AccessViolation(format!("Memory write violation at address: {}", address)))
             }
-        } else
+        }
+        else
         {
             Err(MMUErr::OutOfBounds(format!("Address out of bounds: {}", address)))
         }
@@ -139,16 +576,28 @@ impl MMU
     pub fn write<T>(&mut self, address: Address, value: T) -> Result<(), MMUErr> 
         where T: Sized + Copy 
     {
-        if address % std::mem::align_of::<T>() != 0 
+        if !address.is_multiple_of(std::mem::align_of::<T>()) 
         {
             return Err(MMUErr::MisalignedAccess(format!("Misaligned memory access: {}", address)))
         }
 
+        // Devices take the whole access at once, as the little-endian bytes of `value`.
+        let width = std::mem::size_of::<T>();
+        if let Some(mapping) = self.device(address, width)
+        {
+            let (mapping, offset) = mapping?;
+
+            let mut bytes = [0u8; 8];
+            bytes[..width].copy_from_slice(unsafe { std::slice::from_raw_parts(&value as *const _ as *const u8, width) });
+            return mapping.device.borrow_mut().write(offset, width, u64::from_le_bytes(bytes))
+        }
+
         if address + std::mem::size_of::<T>() > self.memory.len() 
         {
             return Err(MMUErr::OutOfBounds(format!("Address out of bounds: {}", address)))
         }
 
+        // Each byte is stored through write_byte, which invalidates reservations on the granules touched.
         let bytes = &value as *const _ as *const u8;
         for i in 0..std::mem::size_of::<T>() 
         {
@@ -161,24 +610,35 @@ impl MMU
         where T: Sized + Default 
     {
         // Check alignment.
-        if address % std::mem::align_of::<T>() != 0 
+        if !address.is_multiple_of(std::mem::align_of::<T>()) 
         {
             return Err(MMUErr::MisalignedAccess(format!("Misaligned memory access: {}", address)))
         }
 
+        let mut value = T::default();
+        let value_bytes = unsafe {
+            std::slice::from_raw_parts_mut(&mut value as *mut _ as *mut u8, std::mem::size_of::<T>())
+        };
+
+        // Check devices.
+        let width = std::mem::size_of::<T>();
+        if let Some(mapping) = self.device(address, width)
+        {
+            let (mapping, offset) = mapping?;
+
+            let read = mapping.device.borrow_mut().read(offset, width)?;
+            value_bytes.copy_from_slice(&read.to_le_bytes()[..width]);
+            return Ok(value)
+        }
+
         // Check bounds.
         if address + std::mem::size_of::<T>() > self.memory.len() 
         {
             return Err(MMUErr::OutOfBounds(format!("Address out of bounds: {}", address)))
         }
 
-        let mut value = T::default();
-        let value_bytes = unsafe {
-            std::slice::from_raw_parts_mut(&mut value as *mut _ as *mut u8, std::mem::size_of::<T>())
-        };
-
-        for i in 0..std::mem::size_of::<T>() {
-            value_bytes[i] = self.read_byte(address + i)?;
+        for (i, byte) in value_bytes.iter_mut().enumerate() {
+            *byte = self.read_byte(address + i)?;
         }
 
         Ok(value)
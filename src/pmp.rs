@@ -0,0 +1,121 @@
+use crate::{
+    mem::*, mmu::Access
+};
+
+// Number of implemented PMP entries, the CSRs of the remaining entries read as zero.
+pub const PMP_ENTRIES: usize = 16;
+
+// pmpcfg fields.
+pub const PMP_R: u8     = 1 << 0;
+pub const PMP_W: u8     = 1 << 1;
+pub const PMP_X: u8     = 1 << 2;
+pub const PMP_A: u8     = 0b11 << 3;
+pub const PMP_TOR: u8   = 0b01 << 3;
+pub const PMP_NA4: u8   = 0b10 << 3;
+pub const PMP_NAPOT: u8 = 0b11 << 3;
+pub const PMP_L: u8     = 1 << 7;
+
+// Physical memory protection entries of a hart, with a 4-byte granularity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pmp
+{
+    pub entries: usize,
+    pub config: [u8; PMP_ENTRIES],
+    pub address: [u64; PMP_ENTRIES] // Bits 55:2 (or 33:2) of the physical address.
+}
+
+impl Pmp
+{
+    pub fn new(entries: usize) -> Self
+    {
+        Pmp{ entries: entries.min(PMP_ENTRIES), config: [0; PMP_ENTRIES], address: [0; PMP_ENTRIES] }
+    }
+
+    // Whether `size` bytes at `address` may be accessed. The lowest numbered entry matching any of the bytes decides and
+    // must cover all of them. Machine mode is only restricted by locked entries, other modes fail when no entry matches.
+    pub fn permits(&self, address: Address, size: usize, access: Access, machine: bool) -> bool
+    {
+        let (start, end) = (address as u128, address as u128 + size as u128);
+
+        for index in 0..self.entries
+        {
+            let Some((low, high)) = self.range(index) else { continue };
+
+            if start >= high || end <= low
+            {
+                continue
+            }
+
+            let config = self.config[index];
+            if start < low || end > high
+            {
+                return false
+            }
+
+            if machine && config & PMP_L == 0
+            {
+                return true
+            }
+
+            return match access
+            {
+                Access::Fetch => config & PMP_X != 0,
+                Access::Load  => config & PMP_R != 0,
+                Access::Store => config & PMP_W != 0
+            }
+        }
+
+        machine || self.entries == 0
+    }
+
+    pub fn locked(&self, index: usize) -> bool
+    {
+        index < self.entries && self.config[index] & PMP_L != 0
+    }
+
+    // Writes a pmpcfg byte unless locked, write-only permissions are reserved and clear W.
+    pub fn write_config(&mut self, index: usize, value: u8)
+    {
+        if index >= self.entries || self.locked(index)
+        {
+            return
+        }
+
+        let value = value & (PMP_R | PMP_W | PMP_X | PMP_A | PMP_L);
+        self.config[index] = if value & (PMP_R | PMP_W) == PMP_W { value & !PMP_W } else { value };
+    }
+
+    // Writes a pmpaddr register unless its entry is locked, or it's the locked top of a TOR range.
+    pub fn write_address(&mut self, index: usize, value: u64)
+    {
+        let top_of_locked = index + 1 < self.entries && self.locked(index + 1) && self.config[index + 1] & PMP_A == PMP_TOR;
+
+        if index < self.entries && !self.locked(index) && !top_of_locked
+        {
+            self.address[index] = value;
+        }
+    }
+
+    // Byte range [low, high) matched by an entry, None when it's off.
+    fn range(&self, index: usize) -> Option<(u128, u128)>
+    {
+        let address = self.address[index] as u128;
+
+        match self.config[index] & PMP_A
+        {
+            PMP_TOR =>
+            {
+                let low = if index == 0 { 0 } else { self.address[index - 1] as u128 };
+                Some((low << 2, address << 2))
+            },
+            PMP_NA4 => Some((address << 2, (address << 2) + 4)),
+            PMP_NAPOT =>
+            { // Trailing ones encode the size, a region of 2^(ones + 3) bytes.
+                let size = ((address ^ (address + 1)) + 1) << 2;
+                let low = (address << 2) & !(size - 1);
+                Some((low, low + size))
+            },
+            _ => None
+        }
+    }
+}
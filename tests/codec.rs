@@ -0,0 +1,88 @@
+use aem::{
+    lexer::*, arch::*,
+    codec::enc::*,
+    codec::dec::*, decode
+};
+
+// Decodes instruction words produced by the GNU assembler back into lexer shaped operands.
+#[test]
+fn decode_instructions()
+{
+    let expected: Vec<(u32, &str, Vec<Operand>)> = vec![
+        (0x02208033, "mul",  vec![RValue::Register('x', 0).into(), RValue::Register('x', 1).into(), RValue::Register('x', 2).into()]),
+        (0x0ff30293, "addi", vec![RValue::Register('x', 5).into(), RValue::Register('x', 6).into(), RValue::Immediate(255).into()]),
+        (0x40355513, "srai", vec![RValue::Register('x', 10).into(), RValue::Register('x', 10).into(), RValue::Immediate(3).into()]),
+        (0xff812603, "lw",   vec![RValue::Register('x', 12).into(), Operand::Address(RValue::Register('x', 2), RValue::Immediate(-8))]),
+        (0x00a12623, "sw",   vec![RValue::Register('x', 10).into(), Operand::Address(RValue::Register('x', 2), RValue::Immediate(12))]),
+        (0xfe050ee3, "beq",  vec![RValue::Register('x', 10).into(), RValue::Register('x', 0).into(), RValue::Immediate(-4).into()]),
+        (0x001000ef, "jal",  vec![RValue::Register('x', 1).into(), RValue::Immediate(2048).into()]),
+        (0xff1ff06f, "jal",  vec![RValue::Register('x', 0).into(), RValue::Immediate(-16).into()]),
+        (0x30002573, "csrrs", vec![RValue::Register('x', 10).into(), RValue::Immediate(0x300).into(), RValue::Register('x', 0).into()]),
+        (0x003170d3, "fadd.s", vec![RValue::Register('f', 1).into(), RValue::Register('f', 2).into(), RValue::Register('f', 3).into()]),
+        (0x4015f553, "fcvt.s.d", vec![RValue::Register('f', 10).into(), RValue::Register('f', 11).into()]),
+        (0x00000073, "ecall", vec![])
+    ];
+
+    for (binary, mnemonic, operands) in expected
+    {
+        match decode!(binary)
+        {
+            Ok(decoded) => assert_eq!(decoded, (mnemonic.to_string(), operands), "Mismatch decoding 0x{:08x}", binary),
+            Err(decoder_err) => panic!("failed {:?}", decoder_err)
+        }
+    }
+}
+
+// Unknown opcodes and encodings produce typed errors.
+#[test]
+fn decode_unknown_encodings()
+{
+    assert!(matches!(decode!(0xffffffff), Err(DecoderErr::Opcode(_))));
+    assert!(matches!(decode!(0xfe000033), Err(DecoderErr::Encoding(_))));
+    assert!(matches!(decode!(0x0031d0d3), Err(DecoderErr::FloatRounding(_))));
+}
+
+// Every RV_ISA entry encodes operands built from it's signature and decodes back to them.
+#[test]
+fn signature_round_trip()
+{
+    for (mnemonic, instruction) in RV_ISA.iter()
+    {
+        let operands: Vec<Operand> = instruction.operands.iter().map(|operand_type| match operand_type
+        {
+            OperandType::Rd(file) | OperandType::Rs1(file) | OperandType::Rs2(file) | OperandType::Rs3(file) =>
+            {
+                let index = match operand_type { OperandType::Rd(_) => 1, OperandType::Rs1(_) => 2, OperandType::Rs2(_) => 3, _ => 4 };
+                RValue::Register(if *file == RegisterFile::Float { 'f' } else { 'x' }, index).into()
+            },
+            OperandType::Immediate(_, true) => RValue::Immediate(-5).into(),
+            OperandType::Offset(_)          => RValue::Immediate(-8).into(),
+            OperandType::Address            => Operand::Address(RValue::Register('x', 2), RValue::Immediate(-16)),
+            OperandType::Csr                => RValue::Immediate(0x300).into(),
+            OperandType::RoundingMode       => RValue::Identifier("rtz".into()).into(),
+            _                               => RValue::Immediate(3).into()
+        }).collect();
+
+        let binary = Encoder::new(mnemonic, &operands)
+            .unwrap_or_else(|encoder_err| panic!("failed encoding {}: {:?}", mnemonic, encoder_err)).binary;
+
+        assert_eq!(decode!(binary), Ok((mnemonic.to_string(), operands)), "Mismatch decoding {} (0x{:08x})", mnemonic, binary);
+    }
+}
+
+// Operands that don't fit the signature are rejected with the expected syntax.
+#[test]
+fn signature_errors()
+{
+    let operands: Vec<Operand> = vec![RValue::Register('x', 1).into(), RValue::Register('x', 2).into(), RValue::Immediate(4096).into()];
+    assert!(matches!(Encoder::new("addi", &operands), Err(EncoderErr::Operands(message)) if message.contains("imm12")));
+
+    let operands: Vec<Operand> = vec![RValue::Register('x', 1).into(), RValue::Register('f', 2).into(), RValue::Register('f', 3).into()];
+    assert_eq!(Encoder::new("fadd.s", &operands).err(), Some(EncoderErr::Operands(
+        r#"Invalid operands, expected: "fadd.s frd, frs1, frs2, [rm]""#.into()
+    )));
+    // RV32I shift amounts are limited to 5 bits.
+    let operands: Vec<Operand> = vec![RValue::Register('x', 1).into(), RValue::Register('x', 1).into(), RValue::Immediate(40).into()];
+    assert_eq!(Encoder::new("slli", &operands).map(|encoder| encoder.binary), Ok(0x02809093));
+    assert!(matches!(Encoder::with_xlen("slli", &operands, Xlen::X32), Err(EncoderErr::Operands(message)) if message.contains("shamt5")));
+}
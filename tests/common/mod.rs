@@ -0,0 +1,41 @@
+use std::{
+    cell::RefCell, io::Write, rc::Rc
+};
+use aem::{
+    asm::*, assemble,
+    mmu::*
+};
+
+// Places `code` at address 0 with an executable code page and a writable data page at 0x100.
+pub fn load(code: &str) -> MMU
+{
+    let object = assemble!(code).unwrap_or_else(|assembler_err| panic!("failed {:?}", assembler_err));
+    load_object(&object)
+}
+
+// Places an assembled `object` with the same layout as `load`.
+pub fn load_object(object: &Object) -> MMU
+{
+    let mut mmu = MMU::new(0x200);
+    mmu.memory[..object.binary.len()].copy_from_slice(&object.binary);
+    mmu.protect(0x000, 0x0FF, Protection::READ | Protection::EXECUTE).unwrap();
+    mmu.protect(0x100, 0x1FF, Protection::READ | Protection::WRITE).unwrap();
+    mmu
+}
+
+// Output shared with the test after a device, system call layer or tracer takes ownership of it.
+#[derive(Clone, Default)]
+pub struct Shared(pub Rc<RefCell<Vec<u8>>>);
+
+impl Write for Shared
+{
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize>
+    {
+        self.0.borrow_mut().write(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()>
+    {
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+// Fixtures shared with the other emulator and device tests.
+mod common;
+
+use std::{
+    cell::RefCell, rc::Rc, time::Duration
+};
+use common::*;
+use aem::{
+    asm::*, assemble,
+    mmu::*, loader::*, dev::*, dev::clint::*, dev::plic::*, dev::uart::*, dev::virtio::*,
+    emu::hart::*, emu::csr::*
+};
+
+// Timer and software interrupts are raised through mtimecmp and msip, programmed one half at a time by an RV32 hart.
+#[test]
+fn clint_interrupts()
+{
+    let mut mmu = load(r#"
+        jal    zero, start
+    handler:
+        csrrs  t2, 0x342, zero
+        andi   t2, t2, 0xFF
+        slli   a0, a0, 8
+        or     a0, a0, t2
+        addi   t0, zero, -1
+        sw     t0, 4(a6)
+        sw     zero, 0(a5)
+        mret
+    start:
+        lui    a5, 0x2000
+        lui    a6, 0x2004
+        addi   t0, zero, 4
+        csrrw  zero, 0x305, t0
+        addi   t0, zero, 0x88
+        csrrs  zero, 0x304, t0
+        sw     zero, 4(a6)
+        addi   t0, zero, 40
+        sw     t0, 0(a6)
+        csrrsi zero, 0x300, 8
+        wfi
+        addi   t1, zero, 1
+        sw     t1, 0(a5)
+        csrrs  a1, 0xC01, zero
+    done:
+        jal    zero, done"#);
+
+    let clint = Rc::new(RefCell::new(Clint::new(1, Timebase::Instructions(1))));
+    mmu.map(CLINT_BASE, CLINT_BASE + CLINT_SIZE - 1, clint.clone()).unwrap();
+
+    let mut hart = Hart::new(0, Xlen::X32);
+    for _ in 0..100
+    {
+        mmu.tick();
+        hart.tick(&mut mmu);
+    }
+
+    assert_eq!(hart.x[10], 0x0703);
+    assert!((40..100).contains(&hart.x[11]));
+    assert_eq!((hart.csrs.time, clint.borrow().mtimecmp[0]), (100, 0xFFFF_FFFF_0000_0028));
+    assert_eq!(mmu.read::<u32>(CLINT_BASE + CLINT_MTIME + 4), Ok(0));
+    assert!(matches!(mmu.read::<u32>(CLINT_BASE + 4), Err(MMUErr::BusError(_))));
+}
+
+// Host time advances mtime at the configured frequency, writes move its origin.
+#[test]
+fn clint_host_timebase()
+{
+    let mut clint = Clint::new(1, Timebase::Host(1_000_000));
+    let mut mmu = MMU::new(0);
+
+    std::thread::sleep(Duration::from_millis(2));
+    clint.tick(&mut mmu.physical());
+    assert!(clint.mtime >= 2000);
+
+    clint.write(CLINT_MTIME, 8, 1 << 40).unwrap();
+    clint.tick(&mut mmu.physical());
+    assert!((1 << 40..(1 << 40) + 1_000_000).contains(&clint.mtime));
+}
+
+// Claims return the highest priority source above the threshold, completed sources with a raised line are pending again.
+#[test]
+fn plic_claims()
+{
+    let mut mmu = load("csrrs a0, 0x344, zero");
+
+    let plic = Rc::new(RefCell::new(Plic::new(1)));
+    mmu.map(PLIC_BASE, PLIC_BASE + PLIC_SIZE - 1, plic.clone()).unwrap();
+
+    let (machine, supervisor) = (PLIC_BASE + PLIC_CONTEXT, PLIC_BASE + PLIC_CONTEXT + 0x1000);
+    mmu.write::<u32>(PLIC_BASE + 3 * 4, 2).unwrap();
+    mmu.write::<u32>(PLIC_BASE + 5 * 4, 0xFF).unwrap();
+    mmu.write::<u32>(PLIC_BASE + PLIC_ENABLE, 0b10_1001).unwrap();
+    mmu.write::<u32>(PLIC_BASE + PLIC_ENABLE + 0x80, 0b1000).unwrap();
+    mmu.write::<u32>(machine, 1).unwrap();
+    assert_eq!(mmu.read::<u32>(PLIC_BASE + PLIC_ENABLE), Ok(0b10_1000));
+    assert_eq!(mmu.read::<u32>(PLIC_BASE + 5 * 4), Ok(7));
+
+    let (uart, disk) = (Plic::line(&plic, 3), Plic::line(&plic, 5));
+    uart.set(true);
+    disk.set(true);
+    assert_eq!(mmu.interrupts(0), MEI | SEI);
+    assert_eq!(mmu.read::<u32>(PLIC_BASE + PLIC_PENDING), Ok(0b10_1000));
+
+    assert_eq!(mmu.read::<u32>(machine + 4), Ok(5));
+    assert_eq!(mmu.read::<u32>(machine + 4), Ok(3));
+    assert_eq!(mmu.read::<u32>(machine + 4), Ok(0));
+    assert_eq!(mmu.interrupts(0), 0);
+
+    // Only the source with its line still raised is pending after completion.
+    disk.set(false);
+    mmu.write::<u32>(machine + 4, 5).unwrap();
+    mmu.write::<u32>(machine + 4, 3).unwrap();
+    assert_eq!(mmu.read::<u32>(PLIC_BASE + PLIC_PENDING), Ok(0b1000));
+
+    // Sources at or below the threshold don't interrupt the context.
+    mmu.write::<u32>(machine, 2).unwrap();
+    assert_eq!(mmu.interrupts(0), SEI);
+    assert!(matches!(mmu.read::<u16>(machine), Err(MMUErr::BusError(_))));
+
+    let mut hart = Hart::new(0, Xlen::X64);
+    hart.tick(&mut mmu);
+    assert_eq!(hart.x[10], SEI);
+
+    assert_eq!(mmu.read::<u32>(supervisor + 4), Ok(3));
+    assert_eq!(mmu.interrupts(0), 0);
+}
+
+// Transmitted bytes reach the output, scripted input is received in order and raises the interrupt line while enabled.
+#[test]
+fn uart_console()
+{
+    let mut mmu = load(r#"
+        lui    a5, 0x10000
+        addi   t0, zero, 0x68
+        sb     t0, 0(a5)
+        addi   t0, zero, 0x69
+        sb     t0, 0(a5)
+        lbu    a0, 5(a5)
+        lbu    a1, 0(a5)
+        lbu    a2, 0(a5)
+        lbu    a3, 5(a5)
+        ecall"#);
+
+    let plic = Rc::new(RefCell::new(Plic::new(1)));
+    plic.borrow_mut().set_priority(10, 1);
+    plic.borrow_mut().set_enable(0, 10, true);
+
+    let output = Shared::default();
+    let uart = Rc::new(RefCell::new(Uart::new(Box::new(output.clone())).with_input(b"ok").with_interrupt(Plic::line(&plic, 10))));
+    mmu.map(UART_BASE, UART_BASE + UART_SIZE - 1, uart.clone()).unwrap();
+    mmu.map(PLIC_BASE, PLIC_BASE + PLIC_SIZE - 1, plic.clone()).unwrap();
+
+    let mut hart = Hart::new(0, Xlen::X64);
+    assert_eq!(hart.run(&mut mmu), Trap::EnvironmentCall);
+    assert_eq!(&output.0.borrow()[..], b"hi");
+    assert_eq!(&hart.x[10..14], &[0x61, b'o' as u64, b'k' as u64, 0x60]);
+
+    mmu.write::<u8>(UART_BASE + UART_IIR, 0x01).unwrap();
+    mmu.write::<u8>(UART_BASE + UART_IER, 0x01).unwrap();
+    assert_eq!(mmu.interrupts(0), 0);
+
+    uart.borrow_mut().receive(b"!");
+    assert_eq!(mmu.interrupts(0), MEI);
+    assert_eq!(mmu.read::<u8>(UART_BASE + UART_IIR), Ok(0xC4));
+    assert_eq!(mmu.read::<u8>(UART_BASE + UART_RBR), Ok(b'!'));
+    assert_eq!(mmu.read::<u8>(UART_BASE + UART_IIR), Ok(0xC1));
+    assert!(!plic.borrow().levels[10]);
+
+    // Loopback returns transmitted bytes to the receiver.
+    mmu.write::<u8>(UART_BASE + UART_MCR, 0x10).unwrap();
+    mmu.write::<u8>(UART_BASE + UART_RBR, b'?').unwrap();
+    assert_eq!(mmu.read::<u8>(UART_BASE + UART_RBR), Ok(b'?'));
+    assert!(matches!(mmu.read::<u16>(UART_BASE), Err(MMUErr::BusError(_))));
+}
+
+// Queues a block request as a header, data and status descriptor chain in `slot` and notifies the device.
+fn block_request(mmu: &mut MMU, (desc, driver): (usize, usize), slot: u16, kind: u32, sector: u64, data: usize) -> usize
+{
+    let (header, status, head) = (0x4000 + 0x20 * slot as usize, 0x4010 + 0x20 * slot as usize, 3 * slot);
+    let writable = if kind == VIRTIO_BLK_T_OUT { 0 } else { 2 };
+
+    mmu.write::<u32>(header, kind).unwrap();
+    mmu.write::<u64>(header + 8, sector).unwrap();
+    mmu.write::<u8>(status, 0xFF).unwrap();
+    for (index, (address, length, flags)) in [(header, 16, 1), (data, 512, 1 | writable), (status, 1, 2)].into_iter().enumerate()
+    {
+        let entry = desc + 16 * (head as usize + index);
+        mmu.write::<u64>(entry, address as u64).unwrap();
+        mmu.write::<u32>(entry + 8, length).unwrap();
+        mmu.write::<u16>(entry + 12, flags).unwrap();
+        mmu.write::<u16>(entry + 14, head + index as u16 + 1).unwrap();
+    }
+
+    mmu.write::<u16>(driver + 4 + 2 * slot as usize, head).unwrap();
+    mmu.write::<u16>(driver + 2, slot + 1).unwrap();
+    mmu.write::<u32>(VIRTIO_BASE + VIRTIO_QUEUE_NOTIFY, 0).unwrap();
+    mmu.tick();
+    status
+}
+
+// Requests are served from the image on both transports, overlays take the writes and read-only images reject them.
+#[test]
+fn virtio_block()
+{
+    let path = std::env::temp_dir().join(format!("aem-virtio-{}.img", std::process::id()));
+    std::fs::write(&path, (0..4).flat_map(|sector| [sector as u8; 512]).collect::<Vec<_>>()).unwrap();
+
+    let mut mmu = MMU::new(0x8000);
+    mmu.protect(0x0000, 0x7FFF, Protection::READ | Protection::WRITE).unwrap();
+    mmu.memory[0x6000..0x6200].fill(0xAB);
+
+    let block = Rc::new(RefCell::new(VirtioBlock::new(Image::overlay(&path).unwrap(), Interface::Modern)));
+    mmu.map(VIRTIO_BASE, VIRTIO_BASE + VIRTIO_SIZE - 1, block.clone()).unwrap();
+
+    let registers = [(VIRTIO_QUEUE_NUM, 16), (VIRTIO_QUEUE_DESC, 0x1000), (VIRTIO_QUEUE_DRIVER, 0x2000), (VIRTIO_QUEUE_DEVICE, 0x3000), (VIRTIO_QUEUE_READY, 1)];
+    for (register, value) in registers
+    {
+        mmu.write::<u32>(VIRTIO_BASE + register, value).unwrap();
+    }
+    assert_eq!((mmu.read::<u32>(VIRTIO_BASE + VIRTIO_VERSION), mmu.read::<u64>(VIRTIO_BASE + VIRTIO_CONFIG)), (Ok(2), Ok(4)));
+
+    let status = block_request(&mut mmu, (0x1000, 0x2000), 0, VIRTIO_BLK_T_IN, 1, 0x5000);
+    assert_eq!((mmu.memory[status], &mmu.memory[0x5000..0x5200]), (VIRTIO_BLK_S_OK, &[1u8; 512][..]));
+    assert_eq!((mmu.read::<u16>(0x3002), mmu.read::<u32>(0x3008)), (Ok(1), Ok(513)));
+    assert_eq!(mmu.read::<u32>(VIRTIO_BASE + VIRTIO_INTERRUPT_STATUS), Ok(1));
+
+    mmu.write::<u32>(VIRTIO_BASE + VIRTIO_INTERRUPT_ACK, 1).unwrap();
+    assert_eq!(block.borrow().interrupt_status, 0);
+
+    // Overlay writes are read back without reaching the file.
+    let status = block_request(&mut mmu, (0x1000, 0x2000), 1, VIRTIO_BLK_T_OUT, 2, 0x6000);
+    assert_eq!(mmu.memory[status], VIRTIO_BLK_S_OK);
+    let status = block_request(&mut mmu, (0x1000, 0x2000), 2, VIRTIO_BLK_T_IN, 2, 0x5000);
+    assert_eq!((mmu.memory[status], mmu.memory[0x5000]), (VIRTIO_BLK_S_OK, 0xAB));
+    assert_eq!(std::fs::read(&path).unwrap()[1024], 2);
+
+    let status = block_request(&mut mmu, (0x1000, 0x2000), 3, 3, 0, 0x5000);
+    assert_eq!((mmu.memory[status], mmu.read::<u16>(0x3002)), (VIRTIO_BLK_S_UNSUPP, Ok(4)));
+
+    // Sectors whose disk offset overflows complete with an I/O error.
+    let status = block_request(&mut mmu, (0x1000, 0x2000), 4, VIRTIO_BLK_T_IN, u64::MAX, 0x5000);
+    assert_eq!((mmu.memory[status], mmu.read::<u16>(0x3002)), (VIRTIO_BLK_S_IOERR, Ok(5)));
+
+    // The legacy transport places the used ring on the next queue aligned boundary after the available ring.
+    let mut mmu = MMU::new(0x8000);
+    mmu.protect(0x0000, 0x7FFF, Protection::READ | Protection::WRITE).unwrap();
+
+    let block = Rc::new(RefCell::new(VirtioBlock::new(Image::open(&path, true).unwrap(), Interface::Legacy)));
+    mmu.map(VIRTIO_BASE, VIRTIO_BASE + VIRTIO_SIZE - 1, block.clone()).unwrap();
+
+    let registers = [(VIRTIO_GUEST_PAGE_SIZE, 0x1000), (VIRTIO_QUEUE_NUM, 16), (VIRTIO_QUEUE_ALIGN, 0x1000), (VIRTIO_QUEUE_PFN, 1)];
+    for (register, value) in registers
+    {
+        mmu.write::<u32>(VIRTIO_BASE + register, value).unwrap();
+    }
+    assert_eq!(mmu.read::<u32>(VIRTIO_BASE + VIRTIO_DEVICE_FEATURES), Ok((VIRTIO_BLK_F_RO | VIRTIO_BLK_F_FLUSH) as u32));
+
+    let status = block_request(&mut mmu, (0x1000, 0x1100), 0, VIRTIO_BLK_T_IN, 3, 0x5000);
+    assert_eq!((mmu.memory[status], mmu.memory[0x5000], mmu.read::<u16>(0x2002)), (VIRTIO_BLK_S_OK, 3, Ok(1)));
+    let status = block_request(&mut mmu, (0x1000, 0x1100), 1, VIRTIO_BLK_T_OUT, 3, 0x6000);
+    assert_eq!(mmu.memory[status], VIRTIO_BLK_S_IOERR);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+// Console requests are answered at fromhost, the exit code is taken from an RV32 style write and the signature dumped.
+#[test]
+fn htif_tohost()
+{
+    let object = assemble!(r#"
+    .text
+    _start:
+        addi   t0, zero, 0x41
+        lui    t1, 0x1010
+        slli   t1, t1, 32
+        or     t1, t1, t0
+        sd     t1, 0x40(zero)
+    wait:
+        ld     t2, 0x48(zero)
+        beq    t2, zero, wait
+        sd     zero, 0x48(zero)
+        addi   t3, zero, 0x123
+        sw     t3, 0x38(zero)
+        addi   t4, zero, 7
+        sw     t4, 0x40(zero)
+        sw     zero, 0x44(zero)
+    loop:
+        jal    zero, loop
+    .data
+    begin_signature:
+        .word 0x5eadbeef, 0xc0ffee
+    end_signature:
+    .section .tohost, "aw"
+    tohost:
+        .dword 0
+    fromhost:
+        .dword 0"#).unwrap_or_else(|assembler_err| panic!("failed {:?}", assembler_err));
+
+    let mut loader = Loader::new(&object, 0, 0x100).unwrap();
+    assert_eq!((loader.symbols["begin_signature"], loader.symbols["tohost"], loader.symbols["fromhost"]), (0x38, 0x40, 0x48));
+
+    let output = Shared::default();
+    let htif = loader.htif(Box::new(output.clone())).unwrap();
+    let mut hart = Hart::new(0, Xlen::X64);
+
+    for _ in 0..100
+    {
+        loader.mmu.tick();
+        if htif.borrow().exit.is_some()
+        {
+            break
+        }
+        hart.tick(&mut loader.mmu);
+    }
+
+    assert_eq!((htif.borrow().exit, &output.0.borrow()[..]), (Some(3), &b"A"[..]));
+    assert_eq!((loader.mmu.read::<u64>(0x40), loader.mmu.read::<u64>(0x48)), (Ok(0), Ok(0)));
+
+    let mut signature = Vec::new();
+    loader.signature(4, &mut signature).unwrap();
+    assert_eq!(String::from_utf8(signature).unwrap(), "00000123\n00c0ffee\n");
+}
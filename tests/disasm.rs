@@ -0,0 +1,98 @@
+use aem::{
+    asm::*, assemble,
+    disasm::*
+};
+
+// Labels are listed at their addresses and branch targets are printed symbolically.
+#[test]
+fn symbolic_disassembly()
+{
+    match assemble!(
+        r#"
+    start:
+        addi a0, zero, 10
+    loop:
+        addi a0, a0, -1
+        bnez a0, loop
+        beq  x0, x0, -4
+        j    start
+    end:
+        ecall"#)
+    {
+        Ok(object) =>
+        {
+            let disassembler = Disassembler::with_aliases(&object, false);
+
+            let expected = [
+                (vec!["start"], "addi x10, x0, 10"),
+                (vec!["loop"],  "addi x10, x10, -1"),
+                (vec![],        "bne x10, x0, loop"),
+                (vec![],        "beq x0, x0, loop+0x4"),
+                (vec![],        "jal x0, start"),
+                (vec!["end"],   "ecall")
+            ];
+
+            assert_eq!(disassembler.lines.len(), expected.len());
+
+            for (line, (labels, text)) in disassembler.lines.iter().zip(expected)
+            {
+                assert_eq!(line.labels, labels, "Mismatch at address 0x{:04x}", line.address);
+                assert_eq!(line.text, text, "Mismatch at address 0x{:04x}", line.address);
+            }
+        },
+        Err(asm_err) =>
+        {
+            panic!("failed {:?}", asm_err)
+        }
+    }
+}
+
+// Canonical instructions produced by pseudo-instructions are printed as the pseudo-instruction.
+#[test]
+fn alias_disassembly()
+{
+    let code = r#"
+    start:
+        nop
+        addi a0, zero, 10
+        mv   a0, a1
+        not  a0, a1
+        neg  a0, a1
+        seqz a0, a1
+        beqz a0, start
+        j    start
+        jalr x0, x1, 0
+        addi a0, a1, 4"#;
+
+    match assemble!(code)
+    {
+        Ok(mut object) =>
+        { // fsgnj.s f1, f2, f2
+            object.binary.extend_from_slice(&0x202100d3u32.to_le_bytes());
+
+            let aliased: Vec<String> = Disassembler::new(&object).lines.into_iter().map(|line| line.text).collect();
+            assert_eq!(aliased, [
+                "nop",
+                "li x10, 10",
+                "mv x10, x11",
+                "not x10, x11",
+                "neg x10, x11",
+                "seqz x10, x11",
+                "beqz x10, start",
+                "j start",
+                "ret",
+                "addi x10, x11, 4",
+                "fmv.s f1, f2"
+            ]);
+
+            let canonical: Vec<String> = Disassembler::with_aliases(&object, false).lines.into_iter().map(|line| line.text).collect();
+            assert_eq!(canonical[0], "addi x0, x0, 0");
+            assert_eq!(canonical[8], "jalr x0, 0(x1)");
+            assert_eq!(canonical[10], "fsgnj.s f1, f2, f2");
+        },
+        Err(asm_err) =>
+        {
+            panic!("failed {:?}", asm_err)
+        }
+    }
+}
@@ -0,0 +1,867 @@
+// Fixtures shared with the other emulator and device tests.
+mod common;
+
+use std::{
+    cell::RefCell, rc::Rc
+};
+use common::*;
+use aem::{
+    asm::*, assemble,
+    mmu::*, dev::clint::*,
+    emu::hart::*,
+    emu::muldiv::*,
+    emu::fpu::*,
+    emu::csr::*,
+    emu::syscall::*,
+    emu::semihost::{Semihosting, ADP_STOPPED_APPLICATION_EXIT, SEMIHOST_ENTRY, SEMIHOST_EBREAK, SEMIHOST_EXIT},
+    emu::trace::*
+};
+
+// Runs a loop, memory accesses and shifts on both XLENs until the environment call.
+#[test]
+fn execute_programs()
+{
+    let code = r#"
+        addi a0, zero, 0
+        addi t0, zero, 10
+    loop:
+        add  a0, a0, t0
+        addi t0, t0, -1
+        bnez t0, loop
+        addi t1, zero, -2
+        sw   t1, 0x100(zero)
+        lbu  a1, 0x100(zero)
+        lb   a2, 0x101(zero)
+        srli a3, t1, 1
+        sltu a4, a0, t1
+        mul  a5, a0, t1
+        jal  ra, end
+        addi a0, zero, -1
+    end:
+        ecall"#;
+
+    for (xlen, srli) in [(Xlen::X32, 0x7FFF_FFFF), (Xlen::X64, 0x7FFF_FFFF_FFFF_FFFF)]
+    {
+        let mut mmu = load(code);
+        let mut hart = Hart::new(0, xlen);
+
+        assert_eq!(hart.run(&mut mmu), Trap::EnvironmentCall);
+        assert_eq!(hart.pc, 0x38);
+        assert_eq!(&hart.x[10..16], &[55, 0xFE, u64::MAX, srli, 1, -110i64 as u64]);
+        assert_eq!(hart.x[1], 0x34);
+    }
+}
+
+// Faults leave the pc at the trapping instruction.
+#[test]
+fn typed_traps()
+{
+    let traps = [
+        ("addiw a0, a0, 1",       Trap::IllegalInstruction(0x0015051b)),
+        ("slli a0, a0, 32",       Trap::IllegalInstruction(0x02051513)),
+        ("sw a0, 0(zero)",        Trap::StoreAccessFault(0)),
+        ("lw a0, 0x102(zero)",    Trap::LoadAddressMisaligned(0x102)),
+        ("lw a0, 0x200(zero)",    Trap::LoadAccessFault(0x200)),
+        ("jalr zero, 0x100(zero)", Trap::InstructionAccessFault(0x100)),
+        ("ebreak",                Trap::Breakpoint(0))
+    ];
+
+    for (code, trap) in traps
+    {
+        let mut mmu = load(code);
+        let mut hart = Hart::new(0, Xlen::X32);
+
+        assert_eq!(hart.run(&mut mmu), trap, "Mismatch running {}", code);
+        assert_eq!(hart.pc, if code.starts_with("jalr") { 0x100 } else { 0 });
+    }
+}
+
+// Division by zero and signed overflow produce the results defined by the spec on both XLENs.
+#[test]
+fn muldiv_corner_cases()
+{
+    const MIN32: u64 = i32::MIN as i64 as u64;
+    const MIN64: u64 = i64::MIN as u64;
+    const NEG1: u64 = u64::MAX;
+
+    let expected = [
+        ("div",    Xlen::X32, 7, 0, NEG1),             ("div",    Xlen::X64, 7, 0, NEG1),
+        ("divu",   Xlen::X32, 7, 0, NEG1),             ("divu",   Xlen::X64, 7, 0, NEG1),
+        ("rem",    Xlen::X32, 7, 0, 7),                ("rem",    Xlen::X64, MIN64, 0, MIN64),
+        ("remu",   Xlen::X32, MIN32, 0, MIN32),        ("remu",   Xlen::X64, 7, 0, 7),
+        ("div",    Xlen::X32, MIN32, NEG1, MIN32),     ("div",    Xlen::X64, MIN64, NEG1, MIN64),
+        ("rem",    Xlen::X32, MIN32, NEG1, 0),         ("rem",    Xlen::X64, MIN64, NEG1, 0),
+        ("div",    Xlen::X32, -7i64 as u64, 2, -3i64 as u64),
+        ("rem",    Xlen::X64, -7i64 as u64, 2, NEG1),
+        ("divu",   Xlen::X32, NEG1, 2, 0x7FFF_FFFF),
+        ("mulh",   Xlen::X32, MIN32, MIN32, 0x4000_0000),
+        ("mulh",   Xlen::X64, MIN64, NEG1, 0),
+        ("mulhsu", Xlen::X32, NEG1, NEG1, NEG1),       ("mulhsu", Xlen::X64, NEG1, NEG1, NEG1),
+        ("mulhu",  Xlen::X32, NEG1, NEG1, -2i64 as u64), ("mulhu", Xlen::X64, NEG1, NEG1, -2i64 as u64),
+        ("mul",    Xlen::X32, 0x10000, 0x10000, 0),    ("mul",    Xlen::X64, 0x10000, 0x10000, 1 << 32),
+        ("mulw",   Xlen::X64, 0x10000, 0x18000, MIN32),
+        ("divw",   Xlen::X64, MIN32, NEG1, MIN32),     ("divuw",  Xlen::X64, 1 << 32, 0, NEG1),
+        ("remw",   Xlen::X64, 1 << 32 | 5, 0, 5),      ("remuw",  Xlen::X64, NEG1, 0, NEG1)
+    ];
+
+    for (mnemonic, xlen, rs1, rs2, result) in expected
+    {
+        assert_eq!(muldiv(mnemonic, xlen, rs1, rs2), Some(result), "Mismatch for {} {:?} 0x{:x}, 0x{:x}", mnemonic, xlen, rs1, rs2);
+    }
+
+    // RV64M instructions are illegal on RV32 harts.
+    let mut mmu = load("divw a0, a1, a2");
+    assert_eq!(Hart::new(0, Xlen::X32).run(&mut mmu), Trap::IllegalInstruction(0x02c5c53b));
+}
+
+// Stores from other harts break reservations, AMOs return the previous memory value.
+#[test]
+fn atomics()
+{
+    let mut mmu = load(r#"
+        addi      a1, zero, 0x100
+        addi      a2, zero, -5
+        lr.w      a0, a1
+        sc.w      a3, a1, a2
+        lr.w      a0, a1
+        sc.w      a4, a1, a2
+        amoadd.w  a5, a1, a2
+        amomaxu.w a6, a1, a2
+        amomin.w  a7, a1, zero
+        ecall
+    other:
+        sw        zero, 0x104(zero)
+        ecall"#);
+
+    let (mut hart, mut other) = (Hart::new(0, Xlen::X32), Hart::new(1, Xlen::X32));
+    other.pc = 0x28;
+
+    for _ in 0..3
+    {
+        hart.step(&mut mmu).unwrap();
+    }
+    other.step(&mut mmu).unwrap();
+
+    assert_eq!(hart.run(&mut mmu), Trap::EnvironmentCall);
+    assert_eq!(&hart.x[13..18], &[1, 0, -5i64 as u64, -10i64 as u64, -5i64 as u64]);
+    assert_eq!(mmu.read::<i32>(0x100), Ok(-5));
+    assert!(mmu.reservations.is_empty());
+
+    // Misaligned atomics raise store/AMO exceptions.
+    let mut mmu = load("addi a1, zero, 0x102\n amoswap.w a0, a1, a2");
+    assert_eq!(Hart::new(0, Xlen::X32).run(&mut mmu), Trap::StoreAddressMisaligned(0x102));
+}
+
+// Rounding modes, accrued exception flags and IEEE 754 special cases on single and double precision.
+#[test]
+fn fpu_corner_cases()
+{
+    const BOX: u64 = 0xFFFF_FFFF_0000_0000;
+    const ONE: u64 = BOX | 0x3F80_0000;
+    const THREE: u64 = BOX | 0x4040_0000;
+    const QNAN: u64 = BOX | 0x7FC0_0000;
+    const SNAN: u64 = BOX | 0x7F80_0001;
+
+    let single = |rounding| Fpu::new(Precision::Single, rounding);
+
+    let divisions = [
+        (Rounding::NearestEven, ONE, 0x3EAA_AAAB), (Rounding::TowardZero, ONE, 0x3EAA_AAAA),
+        (Rounding::Up, ONE, 0x3EAA_AAAB),          (Rounding::Down, ONE, 0x3EAA_AAAA),
+        (Rounding::Down, BOX | 0xBF80_0000, 0xBEAA_AAAB)
+    ];
+
+    for (rounding, dividend, quotient) in divisions
+    {
+        let mut fpu = single(rounding);
+        assert_eq!(fpu.arithmetic("fdiv", dividend, THREE), BOX | quotient, "Mismatch for {:?}", rounding);
+        assert_eq!(fpu.flags, FloatFlags::NX);
+    }
+
+    // Overflow rounds to infinity or the largest finite value depending on the direction.
+    let mut fpu = single(Rounding::NearestEven);
+    assert_eq!(fpu.arithmetic("fmul", BOX | 0x7F7F_FFFF, BOX | 0x4000_0000), BOX | 0x7F80_0000);
+    assert_eq!(fpu.flags, FloatFlags::OF | FloatFlags::NX);
+
+    let mut fpu = single(Rounding::TowardZero);
+    assert_eq!(fpu.arithmetic("fmul", BOX | 0x7F7F_FFFF, BOX | 0x4000_0000), BOX | 0x7F7F_FFFF);
+
+    let mut fpu = single(Rounding::NearestEven);
+    assert_eq!(fpu.arithmetic("fdiv", BOX | 0x0080_0000, THREE), BOX | 0x002A_AAAB);
+    assert_eq!(fpu.flags, FloatFlags::UF | FloatFlags::NX);
+
+    let mut fpu = single(Rounding::NearestEven);
+    assert_eq!(fpu.arithmetic("fdiv", ONE, BOX), BOX | 0x7F80_0000);
+    assert_eq!(fpu.flags, FloatFlags::DZ);
+
+    // Quiet NaNs are ignored by fmin/fmax, signaling NaNs also raise the invalid flag.
+    let mut fpu = single(Rounding::NearestEven);
+    assert_eq!(fpu.min_max(BOX | 0x8000_0000, BOX, false), BOX | 0x8000_0000);
+    assert_eq!(fpu.min_max(QNAN, ONE, false), ONE);
+    assert_eq!(fpu.flags, FloatFlags::empty());
+    assert_eq!(fpu.min_max(SNAN, ONE, true), ONE);
+    assert_eq!(fpu.min_max(SNAN, QNAN, true), QNAN);
+    assert_eq!(fpu.flags, FloatFlags::NV);
+
+    // Improperly NaN-boxed operands read as the canonical NaN.
+    let mut fpu = single(Rounding::NearestEven);
+    assert_eq!(fpu.arithmetic("fadd", 0x3F80_0000, ONE), QNAN);
+    assert_eq!(fpu.classify(0x3F80_0000), 1 << 9);
+    assert_eq!(fpu.classify(SNAN), 1 << 8);
+    assert_eq!(fpu.classify(BOX | 0x8000_0001), 1 << 2);
+    assert_eq!(fpu.flags, FloatFlags::empty());
+
+    // Out of range and NaN conversions saturate.
+    let mut fpu = single(Rounding::NearestEven);
+    assert_eq!(fpu.to_integer(BOX | 0x4F32_D05E, true, 32), 0x7FFF_FFFF);
+    assert_eq!(fpu.to_integer(QNAN, true, 64), i64::MAX as u64);
+    assert_eq!(fpu.to_integer(BOX | 0xFF80_0000, false, 32), 0);
+    assert_eq!(fpu.to_integer(BOX | 0xC000_0000, false, 64), 0);
+    assert_eq!(fpu.flags, FloatFlags::NV);
+    assert_eq!(single(Rounding::TowardZero).to_integer(BOX | 0xC020_0000, true, 32), -2i64 as u64);
+
+    // Infinity times zero is invalid even with a quiet NaN addend.
+    let mut fpu = single(Rounding::NearestEven);
+    assert_eq!(fpu.fused_multiply_add(BOX | 0x7F80_0000, BOX, QNAN, false, false), QNAN);
+    assert_eq!(fpu.flags, FloatFlags::NV);
+
+    let mut fpu = single(Rounding::NearestEven);
+    assert_eq!(fpu.square_root(BOX | 0xBF80_0000), QNAN);
+    assert_eq!(fpu.square_root(BOX | 0x4110_0000), THREE);
+    assert_eq!(fpu.flags, FloatFlags::NV);
+
+    let mut fpu = Fpu::new(Precision::Double, Rounding::NearestEven);
+    assert_eq!(fpu.arithmetic("fadd", 0x3FB9_9999_9999_999A, 0x3FC9_9999_9999_999A), 0x3FD3_3333_3333_3334);
+    assert_eq!(fpu.convert(BOX | 0x3EAA_AAAB, Precision::Single), 0x3FD5_5555_6000_0000);
+    assert_eq!(fpu.flags, FloatFlags::NX);
+}
+
+// Floating point registers are NaN-boxed and instructions accrue exceptions in fcsr.
+#[test]
+fn floating_point()
+{
+    let mut mmu = load(r#"
+        addi     t0, zero, 1
+        fcvt.s.w f1, t0
+        addi     t0, zero, 3
+        fcvt.s.w f2, t0
+        fdiv.s   f3, f1, f2
+        fsw      f3, 0x100(zero)
+        lw       a0, 0x100(zero)
+        fcvt.d.s f4, f3
+        fmv.x.d  a1, f4
+        addi     t0, zero, 5
+        fcvt.s.w f5, t0
+        addi     t0, zero, 2
+        fcvt.s.w f6, t0
+        fdiv.s   f5, f5, f6
+        fcvt.w.s a2, f5
+        fcvt.w.s a3, f5, rmm
+        fld      f7, 0x100(zero)
+        fadd.s   f8, f7, f7
+        fmv.x.w  a4, f8
+        fclass.s a5, f8
+        flt.s    a6, f8, f1
+        ecall"#);
+
+    let mut hart = Hart::new(0, Xlen::X64);
+
+    assert_eq!(hart.run(&mut mmu), Trap::EnvironmentCall);
+    assert_eq!(&hart.x[10..17], &[0x3EAA_AAAB, 0x3FD5_5555_6000_0000, 2, 3, 0x7FC0_0000, 1 << 9, 0]);
+    assert_eq!(hart.f[3], 0xFFFF_FFFF_3EAA_AAAB);
+    assert_eq!(hart.csrs.fcsr, 0b10001);
+
+    // Reserved rounding modes in frm are illegal for instructions that round.
+    let mut mmu = load("fadd.s f1, f2, f3");
+    let mut hart = Hart::new(0, Xlen::X32);
+    hart.csrs.fcsr = 0b101 << 5;
+    assert_eq!(hart.run(&mut mmu), Trap::IllegalInstruction(0x003170d3));
+
+    // RV64 only conversions are illegal on RV32 harts.
+    let mut mmu = load("fmv.x.d a0, f1");
+    assert_eq!(Hart::new(0, Xlen::X32).run(&mut mmu), Trap::IllegalInstruction(0xe2008553));
+}
+
+// CSR reads and writes follow WARL rules, read-only and missing CSRs are illegal.
+#[test]
+fn csr_file()
+{
+    let mut mmu = load(r#"
+        csrrs  a0, 0x301, zero
+        csrrs  a1, 0xF14, zero
+        addi   t0, zero, -1
+        csrrw  a2, 0x305, t0
+        csrrs  a3, 0x305, zero
+        csrrwi zero, 0x002, 1
+        csrrs  a4, 0x003, zero
+        csrrs  a5, 0xC02, zero
+        csrrs  a6, 0x300, zero
+        ecall"#);
+
+    let mut hart = Hart::new(1, Xlen::X32);
+
+    assert_eq!(hart.run(&mut mmu), Trap::EnvironmentCall);
+    assert_eq!(&hart.x[10..17], &[0x4014_1129, 1, 0, -4i64 as u64, 0x20, 7, 0xFFFF_FFFF_8000_7800]);
+    assert_eq!((hart.csrs.cycle, hart.csrs.instret), (10, 9));
+
+    let illegal = [
+        ("csrrw  zero, 0xC00, t0", Xlen::X32),
+        ("csrrs  a0, 0x7C0, zero", Xlen::X32),
+        ("csrrs  a0, 0xC80, zero", Xlen::X64),
+        ("csrrs  a0, 0x3A1, zero", Xlen::X64),
+        ("lui    t0, 6\n csrrc zero, 0x300, t0\n fadd.s f1, f2, f3", Xlen::X64)
+    ];
+
+    for (code, xlen) in illegal
+    {
+        let mut mmu = load(code);
+        assert!(matches!(Hart::new(0, xlen).run(&mut mmu), Trap::IllegalInstruction(_)), "Expected illegal instruction for {}", code);
+    }
+}
+
+// Locked PMP entries restrict machine mode accesses, violations raise access faults.
+#[test]
+fn physical_memory_protection()
+{
+    let mut mmu = load(r#"
+        addi   t0, zero, 0x40
+        csrrw  zero, 0x3B0, t0
+        addi   t0, zero, 0x91
+        csrrw  zero, 0x3A0, t0
+        csrrw  zero, 0x3B0, zero
+        csrrs  a0, 0x3A0, zero
+        csrrs  a1, 0x3B0, zero
+        lw     a2, 0x100(zero)
+        sw     a2, 0x100(zero)"#);
+
+    let mut hart = Hart::new(0, Xlen::X32);
+
+    assert_eq!(hart.run(&mut mmu), Trap::StoreAccessFault(0x100));
+    assert_eq!((hart.x[10], hart.x[11], hart.pc), (0x91, 0x40, 0x20));
+}
+
+// Exceptions enter the mtvec handler with mepc, mcause and mtval set, mret resumes after the faulting instruction.
+#[test]
+fn machine_exceptions()
+{
+    let mut mmu = load(r#"
+        jal    zero, start
+    handler:
+        csrrs  t1, 0x342, zero
+        csrrs  t2, 0x343, zero
+        sw     t1, 0(s0)
+        sw     t2, 4(s0)
+        addi   s0, s0, 8
+        csrrs  t3, 0x341, zero
+        addi   t3, t3, 4
+        csrrw  zero, 0x341, t3
+        mret
+    start:
+        addi   t0, zero, 4
+        csrrw  zero, 0x305, t0
+        addi   s0, zero, 0x100
+        lw     a0, 0x102(zero)
+        sw     a0, 0x80(zero)
+        ecall
+        ebreak
+        csrrw  zero, 0xC00, t0
+    done:
+        jal    zero, done"#);
+
+    let mut hart = Hart::new(0, Xlen::X32);
+    for _ in 0..100
+    {
+        hart.tick(&mut mmu);
+    }
+
+    let log: Vec<u32> = (0..10).map(|index| mmu.read::<u32>(0x100 + index * 4).unwrap()).collect();
+    assert_eq!(log, [4, 0x102, 7, 0x80, 11, 0, 3, 0x40, 2, 0xC002_9073]);
+    assert_eq!(hart.pc, 0x48);
+    assert_eq!(hart.csrs.mstatus & (MSTATUS_MIE | MSTATUS_MPIE | MSTATUS_MPP), MSTATUS_MPIE);
+}
+
+// Vectored interrupts wake the hart from wfi, the handler runs with interrupts disabled until mret.
+#[test]
+fn machine_interrupts()
+{
+    let mut mmu = load(r#"
+        jal    zero, start
+        addi   zero, zero, 0
+        addi   zero, zero, 0
+        addi   zero, zero, 0
+        addi   zero, zero, 0
+        addi   zero, zero, 0
+        addi   zero, zero, 0
+    timer:
+        csrrs  a1, 0x342, zero
+        csrrs  a2, 0x300, zero
+        mret
+    start:
+        addi   t0, zero, 1
+        csrrw  zero, 0x305, t0
+        addi   t0, zero, 0x80
+        csrrs  zero, 0x304, t0
+        csrrsi zero, 0x300, 8
+        wfi
+        addi   a0, zero, 1
+    done:
+        jal    zero, done"#);
+
+    let clint = Rc::new(RefCell::new(Clint::new(1, Timebase::Instructions(1))));
+    mmu.map(CLINT_BASE, CLINT_BASE + CLINT_SIZE - 1, clint.clone()).unwrap();
+
+    let mut hart = Hart::new(0, Xlen::X64);
+    for _ in 0..20
+    {
+        hart.tick(&mut mmu);
+    }
+    assert!(hart.waiting);
+    assert_eq!((hart.pc, hart.x[10]), (0x40, 0));
+
+    clint.borrow_mut().mtimecmp[0] = 0;
+    hart.tick(&mut mmu);
+    assert_eq!((hart.pc, hart.csrs.mepc, hart.csrs.mcause), (0x1C, 0x40, 1 << 63 | 7));
+
+    clint.borrow_mut().mtimecmp[0] = u64::MAX;
+    for _ in 0..5
+    {
+        hart.tick(&mut mmu);
+    }
+    assert_eq!(hart.x[10..12], [1, 1 << 63 | 7]);
+    assert_eq!(hart.x[12] & (MSTATUS_MIE | MSTATUS_MPIE), MSTATUS_MPIE);
+    assert_eq!(hart.csrs.mstatus & (MSTATUS_MIE | MSTATUS_MPIE), MSTATUS_MIE | MSTATUS_MPIE);
+}
+
+// Supervisor code runs under Sv39 translation, delegated page faults are handled at stvec and other traps reach machine mode.
+#[test]
+fn supervisor_mode()
+{
+    let object = assemble!(r#"
+        jal    zero, start
+    supervisor_trap:
+        csrrs  t1, 0x142, zero
+        csrrs  t2, 0x143, zero
+        slli   a6, a6, 8
+        or     a6, a6, t1
+        add    a7, a7, t2
+        csrrs  t3, 0x141, zero
+        addi   t3, t3, 4
+        csrrw  zero, 0x141, t3
+        sret
+    machine_trap:
+        csrrs  t4, 0x342, zero
+        csrrs  t5, 0x300, zero
+    done:
+        jal    zero, done
+    supervisor:
+        addi   a0, zero, 0x7F
+        lui    a1, 1
+        sw     a0, 0(a1)
+        lw     a2, 0(a1)
+        lui    a3, 2
+        lw     a4, 0(a3)
+        lui    a5, 3
+        sw     a0, 0(a5)
+        sfence.vma zero, zero
+        ecall
+    start:
+        addi   t0, zero, -1
+        csrrw  zero, 0x3B0, t0
+        addi   t0, zero, 0x1F
+        csrrw  zero, 0x3A0, t0
+        addi   t0, zero, 0x04
+        csrrw  zero, 0x105, t0
+        addi   t0, zero, 0x28
+        csrrw  zero, 0x305, t0
+        lui    t0, 0xA
+        csrrw  zero, 0x302, t0
+        addi   t0, zero, 8
+        slli   t0, t0, 60
+        addi   t0, t0, 1
+        csrrw  zero, 0x180, t0
+        addi   t0, zero, 3
+        slli   t0, t0, 11
+        csrrc  zero, 0x300, t0
+        addi   t0, zero, 1
+        slli   t0, t0, 11
+        csrrs  zero, 0x300, t0
+        addi   t0, zero, 0x34
+        csrrw  zero, 0x341, t0
+        mret"#).unwrap_or_else(|assembler_err| panic!("failed {:?}", assembler_err));
+
+    let memory = ||
+    {
+        let mut mmu = MMU::new(0x5000);
+        mmu.memory[..object.binary.len()].copy_from_slice(&object.binary);
+        mmu.protect(0x0000, 0x0FFF, Protection::READ | Protection::EXECUTE).unwrap();
+        mmu.protect(0x1000, 0x4FFF, Protection::READ | Protection::WRITE).unwrap();
+
+        // Code is identity mapped, 0x1000 maps the data page at 0x4000, 0x2000 maps it to user mode and 0x3000 is unmapped.
+        mmu.write::<u64>(0x1000, 0x0801).unwrap();
+        mmu.write::<u64>(0x2000, 0x0C01).unwrap();
+        mmu.write::<u64>(0x3000, 0x000B).unwrap();
+        mmu.write::<u64>(0x3008, 0x1007).unwrap();
+        mmu.write::<u64>(0x3010, 0x1017).unwrap();
+        mmu
+    };
+    let mut mmu = memory();
+
+    // A single NAPOT entry grants supervisor mode access to all of memory.
+    let mut hart = Hart::new(0, Xlen::X64);
+    for _ in 0..64
+    {
+        hart.tick(&mut mmu);
+    }
+
+    assert_eq!((hart.x[12], mmu.read::<u64>(0x4000)), (0x7F, Ok(0x7F)));
+    assert_eq!((hart.x[16], hart.x[17]), (0x0D0F, 0x5000));
+    assert_eq!((hart.x[29], (hart.x[30] & MSTATUS_MPP) >> 11), (9, 0b01));
+    assert_eq!((hart.privilege, hart.pc), (Privilege::Machine, 0x30));
+
+    // Accessed and dirty bits of the leaf entries are set by the walk.
+    assert_eq!((mmu.read::<u64>(0x3000), mmu.read::<u64>(0x3008)), (Ok(0x004B), Ok(0x10C7)));
+
+    // Only the fetch of the environment call refilled the TLB after the fence.
+    assert!(hart.tlb.data.entries.is_empty());
+    assert_eq!(hart.tlb.instruction.entries.len(), 1);
+    assert!(hart.tlb.instruction.hits > 0 && hart.tlb.data.misses == 3);
+
+    // Tracing the same run leaves the TLB and the page tables as they were.
+    let mut traced = memory();
+    let mut tracer = Tracer::new(Box::new(Shared::default()));
+    let mut traced_hart = Hart::new(0, Xlen::X64);
+    for _ in 0..64
+    {
+        tracer.tick(&mut traced_hart, &mut traced);
+    }
+    assert_eq!((traced_hart.tlb.instruction.hits, traced_hart.tlb.instruction.misses), (hart.tlb.instruction.hits, hart.tlb.instruction.misses));
+    assert_eq!((traced_hart.tlb.data.hits, traced.memory), (hart.tlb.data.hits, mmu.memory));
+}
+
+// User programs write to the captured stdout, grow the heap, map and unmap pages and use host files until they exit.
+#[test]
+fn linux_syscalls()
+{
+    let object = assemble!(r#"
+        addi   a7, zero, 64
+        addi   a0, zero, 1
+        lui    a1, 1
+        addi   a2, zero, 6
+        ecall
+        addi   t3, a0, 0
+        addi   a7, zero, 214
+        addi   a0, zero, 0
+        ecall
+        addi   t4, a0, 0
+        lui    a0, 3
+        ecall
+        sd     t4, -8(a0)
+        addi   a7, zero, 222
+        addi   a0, zero, 0
+        lui    a1, 2
+        addi   a2, zero, 3
+        addi   a3, zero, 0x22
+        addi   a4, zero, -1
+        addi   a5, zero, 0
+        ecall
+        addi   t5, a0, 0
+        sd     t5, 0(a0)
+        addi   a7, zero, 215
+        ecall
+        addi   t6, a0, 0
+        addi   a7, zero, 56
+        addi   a0, zero, -100
+        lui    a1, 1
+        addi   a1, a1, 0x10
+        addi   a2, zero, 0x242
+        addi   a3, zero, 420
+        ecall
+        addi   s0, a0, 0
+        addi   a7, zero, 64
+        lui    a1, 1
+        addi   a2, zero, 6
+        ecall
+        addi   a7, zero, 62
+        addi   a0, s0, 0
+        addi   a1, zero, 1
+        addi   a2, zero, 0
+        ecall
+        addi   a7, zero, 63
+        addi   a0, s0, 0
+        lui    a1, 1
+        addi   a1, a1, 0x100
+        addi   a2, zero, 16
+        ecall
+        addi   s1, a0, 0
+        addi   a7, zero, 80
+        addi   a0, s0, 0
+        lui    a1, 1
+        addi   a1, a1, 0x200
+        ecall
+        addi   a7, zero, 57
+        addi   a0, s0, 0
+        ecall
+        addi   a0, s0, 0
+        ecall
+        addi   a6, a0, 0
+        addi   a7, zero, 999
+        ecall
+        addi   a5, a0, 0
+        addi   a7, zero, 56
+        addi   a0, zero, -100
+        lui    a1, 1
+        addi   a1, a1, 0x300
+        addi   a2, zero, 0
+        ecall
+        addi   t0, a0, 0
+        addi   a7, zero, 215
+        lui    a0, 0xF
+        addi   a1, zero, -1
+        ecall
+        addi   t1, a0, 0
+        addi   a7, zero, 94
+        addi   a0, zero, 7
+        ecall"#).unwrap_or_else(|assembler_err| panic!("failed {:?}", assembler_err));
+
+    let name = format!("/aem-syscalls-{}.txt", std::process::id());
+
+    let mut mmu = MMU::new(0x10000);
+    mmu.memory[..object.binary.len()].copy_from_slice(&object.binary);
+    mmu.memory[0x1000..0x1006].copy_from_slice(b"hello\n");
+    mmu.memory[0x1010..0x1010 + name.len()].copy_from_slice(name.as_bytes());
+    mmu.memory[0x1300..0x1306].copy_from_slice(b"../etc");
+    mmu.protect(0x0000, 0x0FFF, Protection::READ | Protection::EXECUTE).unwrap();
+    mmu.protect(0x1000, 0x1FFF, Protection::READ | Protection::WRITE).unwrap();
+
+    let output = Shared::default();
+    let mut syscalls = Syscalls::new(0x2000, 0x10000)
+        .with_file(1, Descriptor::Output(Box::new(output.clone())))
+        .with_root(std::env::temp_dir());
+    let mut hart = Hart::new(0, Xlen::X64);
+
+    assert_eq!(syscalls.run(&mut hart, &mut mmu), Ok(7));
+    assert_eq!((&output.0.borrow()[..], hart.x[28]), (&b"hello\n"[..], 6));
+
+    // The heap grew by a page, the mapping was placed below the top and removed again.
+    assert_eq!((hart.x[29], syscalls.brk, mmu.read::<u64>(0x2FF8)), (0x2000, 0x3000, Ok(0x2000)));
+    assert_eq!((hart.x[30], hart.x[31], mmu.query(0xE000)), (0xE000, 0, None));
+
+    // The file was created inside the root, written, read back from offset 1 up to its end and closed once.
+    assert_eq!((hart.x[8], hart.x[9], &mmu.memory[0x1100..0x1105]), (3, 5, &b"ello\n"[..]));
+    assert_eq!(mmu.read::<u64>(0x1230), Ok(6));
+    assert_eq!((hart.x[16] as i64, hart.x[15] as i64, hart.privilege), (-EBADF, -ENOSYS, Privilege::User));
+
+    // Paths can't leave the root and unmapped ranges can't wrap around.
+    assert_eq!((hart.x[5] as i64, hart.x[6] as i64), (-EACCES, -EINVAL));
+
+    std::fs::remove_file(std::env::temp_dir().join(&name[1..])).unwrap();
+}
+
+// Semihosting calls are served against the sandbox directory, plain breakpoints still trap.
+#[test]
+fn semihosting()
+{
+    let object = assemble!(r#"
+    .macro semihost
+        slli   zero, zero, 0x1f
+        ebreak
+        srai   zero, zero, 7
+    .endm
+        lui    s0, 1
+        addi   a0, zero, 1
+        addi   a1, s0, 0
+        semihost
+        sd     a0, 0x20(s0)
+        sd     a0, 0x40(s0)
+        sd     a0, 0x60(s0)
+        sd     a0, 0x80(s0)
+        addi   a0, zero, 5
+        addi   a1, s0, 0x20
+        semihost
+        addi   t3, a0, 0
+        addi   a0, zero, 10
+        addi   a1, s0, 0x40
+        semihost
+        addi   a0, zero, 6
+        addi   a1, s0, 0x60
+        semihost
+        addi   t4, a0, 0
+        addi   a0, zero, 2
+        addi   a1, s0, 0x80
+        semihost
+        addi   a0, zero, 1
+        addi   a1, s0, 0xA0
+        semihost
+        addi   t5, a0, 0
+        addi   a0, zero, 0x13
+        semihost
+        addi   t6, a0, 0
+        addi   a0, zero, 4
+        lui    a1, 1
+        addi   a1, a1, 0x400
+        semihost
+        ebreak
+        addi   a0, zero, 0x18
+        addi   a1, s0, 0xC0
+        semihost"#).unwrap_or_else(|assembler_err| panic!("failed {:?}", assembler_err));
+
+    let root = std::env::temp_dir().join(format!("aem-semihosting-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+
+    let mut mmu = MMU::new(0x2000);
+    mmu.memory[..object.binary.len()].copy_from_slice(&object.binary);
+    for (address, fields) in [(0x1000, [0x1100, 6, 7]), (0x1020, [0, 0x1200, 3]), (0x1040, [0, 1, 0]), (0x1060, [0, 0x1300, 8]),
+                              (0x10A0, [0x1140, 0, 4]), (0x10C0, [ADP_STOPPED_APPLICATION_EXIT, 3, 0])]
+    {
+        for (index, field) in fields.iter().enumerate()
+        {
+            mmu.memory[address + index * 8..address + index * 8 + 8].copy_from_slice(&field.to_le_bytes());
+        }
+    }
+    mmu.memory[0x1100..0x1107].copy_from_slice(b"out.txt");
+    mmu.memory[0x1140..0x1144].copy_from_slice(b"../x");
+    mmu.memory[0x1200..0x1203].copy_from_slice(b"hi\n");
+    mmu.memory[0x1400..0x1407].copy_from_slice(b"hello\n\0");
+    mmu.protect(0x0000, 0x0FFF, Protection::READ | Protection::EXECUTE).unwrap();
+    mmu.protect(0x1000, 0x1FFF, Protection::READ | Protection::WRITE).unwrap();
+
+    let output = Shared::default();
+    let mut semihosting = Semihosting::new(&root);
+    semihosting.output = Box::new(output.clone());
+    let mut hart = Hart::new(0, Xlen::X64);
+
+    assert!(matches!(semihosting.run(&mut hart, &mut mmu), Err(Trap::Breakpoint(pc)) if !Semihosting::at_call(&mut hart, &mut mmu, pc)));
+    hart.pc += 4;
+    assert_eq!(semihosting.run(&mut hart, &mut mmu), Ok(3));
+
+    // Written, read back from offset 1 with 6 of 8 bytes missing, and the path outside the sandbox refused.
+    assert_eq!((hart.x[28], hart.x[29], hart.x[30] as i64, hart.x[31]), (0, 6, -1, 13));
+    assert_eq!((&mmu.memory[0x1300..0x1302], &output.0.borrow()[..]), (&b"i\n"[..], &b"hello\n"[..]));
+    assert_eq!(std::fs::read(root.join("out.txt")).unwrap(), b"hi\n");
+
+    // Call sequences are matched at their virtual address, here mapped by Sv39 onto the physical page at 0x4000.
+    let mut mmu = MMU::new(0x5000);
+    for (index, word) in [SEMIHOST_ENTRY, SEMIHOST_EBREAK, SEMIHOST_EXIT].iter().enumerate()
+    {
+        mmu.memory[0x4000 + index * 4..0x4004 + index * 4].copy_from_slice(&word.to_le_bytes());
+    }
+    mmu.protect(0x0000, 0x3FFF, Protection::READ | Protection::WRITE).unwrap();
+    mmu.protect(0x4000, 0x4FFF, Protection::READ | Protection::EXECUTE).unwrap();
+    for (address, entry) in [(0x1000, 0x0801), (0x2000, 0x0C01), (0x3000, 0x104B)]
+    {
+        mmu.write::<u64>(address, entry).unwrap();
+    }
+
+    let mut hart = Hart::new(0, Xlen::X64);
+    hart.csrs.write(0x3B0, u64::MAX, Privilege::Machine).unwrap();
+    hart.csrs.write(0x3A0, 0x1F, Privilege::Machine).unwrap();
+    hart.csrs.satp = 8 << 60 | 1;
+    hart.privilege = Privilege::Supervisor;
+    assert!(Semihosting::at_call(&mut hart, &mut mmu, 0x4));
+    assert!(!Semihosting::at_call(&mut hart, &mut mmu, 0x4004));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+// Retired instructions are logged in Spike's commit layout, optionally limited to the code of a symbol.
+#[test]
+fn commit_trace()
+{
+    let object = assemble!(r#"
+    _start:
+        addi     t0, zero, 0x100
+        addi     a1, zero, 32
+        sd       a1, 8(t0)
+        ld       a2, 8(t0)
+        csrrw    zero, 0x340, a1
+        amoadd.d a3, t0, a1
+        jal      ra, helper
+        ecall
+    helper:
+        addi     a4, zero, 1
+        ret"#).unwrap_or_else(|assembler_err| panic!("failed {:?}", assembler_err));
+
+    let mut mmu = load_object(&object);
+
+    let output = Shared::default();
+    let mut tracer = Tracer::new(Box::new(output.clone())).with_symbols(&object.symbols);
+    let mut hart = Hart::new(0, Xlen::X64);
+
+    assert_eq!(tracer.run(&mut hart, &mut mmu), Trap::EnvironmentCall);
+    let trace = String::from_utf8(output.0.borrow().clone()).unwrap();
+    let lines: Vec<&str> = trace.lines().collect();
+
+    assert_eq!(lines.len(), 18, "The trapping ecall doesn't retire");
+    assert_eq!(lines[..2], ["core   0: 0x0000000000000000 (0x10000293) li      t0, 256",
+                            "core   0: 3 0x0000000000000000 (0x10000293) x5  0x0000000000000100"]);
+    assert_eq!(lines[5], "core   0: 3 0x0000000000000008 (0x00b2b423) mem 0x0000000000000108 0x0000000000000020");
+    assert_eq!(lines[7], "core   0: 3 0x000000000000000c (0x0082b603) x12 0x0000000000000020 mem 0x0000000000000108");
+    assert_eq!(lines[9], "core   0: 3 0x0000000000000010 (0x34059073) c832_mscratch 0x0000000000000020");
+    assert_eq!(lines[11], "core   0: 3 0x0000000000000014 (0x00b2b6af) x13 0x0000000000000000 \
+                           mem 0x0000000000000100 mem 0x0000000000000100 0x0000000000000020");
+    assert_eq!(lines[12], "core   0: 0x0000000000000018 (0x008000ef) jal     ra, pc + 0x8");
+
+    // Branch targets are only named after symbols on request.
+    tracer.symbolize = true;
+    hart.pc = 0x18;
+    output.0.borrow_mut().clear();
+    tracer.step(&mut hart, &mut mmu).unwrap();
+    assert!(String::from_utf8(output.0.borrow().clone()).unwrap().starts_with("core   0: 0x0000000000000018 (0x008000ef) jal     ra, helper\n"));
+
+    // Only the commit lines of the helper on an RV32 hart.
+    let output = Shared::default();
+    let mut tracer = Tracer::new(Box::new(output.clone())).with_symbol(&object.symbols, "helper").unwrap();
+    tracer.disassemble = false;
+    let mut hart = Hart::new(0, Xlen::X32);
+
+    assert_eq!(tracer.run(&mut hart, &mut mmu), Trap::IllegalInstruction(0x00b2b423));
+    (hart.pc, hart.x[1]) = (0x20, 0x1c);
+    assert_eq!(tracer.run(&mut hart, &mut mmu), Trap::EnvironmentCall);
+    assert_eq!(String::from_utf8(output.0.borrow().clone()).unwrap(),
+        "core   0: 3 0x00000020 (0x00100713) x14 0x00000001\ncore   0: 3 0x00000024 (0x00008067)\n");
+
+    assert!(matches!(Tracer::new(Box::new(Shared::default())).with_symbol(&object.symbols, "main"), Err(TraceErr::Symbol(_))));
+}
+
+// The first instruction whose effects differ is reported with the instructions before it, a shorter trace ends early.
+#[test]
+fn compare_traces()
+{
+    let code = r#"
+        addi   a0, zero, 3
+    loop:
+        add    a2, a2, a1
+        addi   a0, a0, -1
+        bne    a0, zero, loop
+        sd     a2, 0x100(t0)
+        ecall"#;
+
+    let trace = |offset: u64|
+    {
+        let mut mmu = load(code);
+
+        let output = Shared::default();
+        let mut hart = Hart::new(0, Xlen::X64);
+        (hart.x[5], hart.x[11]) = (offset, 2);
+        Tracer::new(Box::new(output.clone())).run(&mut hart, &mut mmu);
+
+        let trace = output.0.borrow().clone();
+        trace
+    };
+    let (first, second) = (trace(0), trace(8));
+    let diff = TraceDiff::new(2);
+
+    assert_eq!(diff.compare(&first[..], &first[..]).unwrap(), None);
+
+    let divergence = diff.compare(&first[..], &second[..]).unwrap().unwrap();
+    let (left, right) = (divergence.left.clone().unwrap(), divergence.right.unwrap());
+    assert_eq!((divergence.index, left.pc, left.line, right.line), (10, 0x10, 22, 22));
+    assert_eq!((left.accesses, right.accesses), (vec![(0x100, Some(6))], vec![(0x108, Some(6))]));
+    assert_eq!(divergence.context.iter().map(|commit| commit.pc).collect::<Vec<_>>(), [0x8, 0xC]);
+    assert_eq!(left.disassembly.as_deref(), Some("core   0: 0x0000000000000010 (0x10c2b023) sd      a2, 256(t0)"));
+    assert_eq!(divergence.context[1].disassembly.as_deref(), Some("core   0: 0x000000000000000c (0xfe051ce3) bnez    a0, pc - 0x8"));
+
+    let truncated: Vec<u8> = first.split_inclusive(|&byte| byte == b'\n').take(20).flatten().copied().collect();
+    let divergence = diff.compare(&first[..], &truncated[..]).unwrap().unwrap();
+    assert_eq!((divergence.index, &divergence.right), (10, &None));
+    assert!(divergence.to_string().ends_with("+ (end of trace)\n"));
+}
@@ -7,7 +7,7 @@ use aem::{
 #[test]
 fn parse_instructions() 
 {
-    const CODE_STR: &'static str = 
+    const CODE_STR: &str = 
     r#"
         # x5 = x6 + 255
         addi x5, x6, 0xff
@@ -41,7 +41,7 @@ fn parse_instructions()
                 Emittable::Instruction("lw".into(),
                     vec![
                         RValue::Register('x', 12).into(),
-                        Operand::Address(RValue::Register('x', 2), RValue::Immediate(-8)).into()
+                        Operand::Address(RValue::Register('x', 2), RValue::Immediate(-8))
                     ]
                 ).into()
             );
@@ -89,7 +89,7 @@ fn parse_instructions()
             {
                 LexerErr::Syntax(ref message) => 
                 { // Expected error produced by incomplete relocation function at "lw   a2, -8()".
-                    assert_ne!(message.contains(r#"Unexpected instruction operand: -8()"#), true)
+                    assert!(!message.contains(r#"Unexpected instruction operand: -8()"#))
                 },
                 LexerErr::Parsing(ref message) => 
                 {
@@ -103,7 +103,7 @@ fn parse_instructions()
 #[test]
 fn parse_directives()
 {
-    const CODE_STR: &'static str = 
+    const CODE_STR: &str = 
             r#" # alignment directives
                 .p2align 0x4, 0xff, 0
                 .align 4
@@ -148,58 +148,58 @@ fn parse_directives()
             // .byte 0x08, 0x7f, 126, 125, 0, -125, -126
             assert_eq!(tokens[4],
                 Token::Emittable(Emittable::Byte(vec![
-                    RValue::Immediate(0x08).into(),
-                    RValue::Immediate(0x7f).into(),
-                    RValue::Immediate(126).into(),
-                    RValue::Immediate(125).into(),
-                    RValue::Immediate(0).into(),
-                    RValue::Immediate(-125).into(),
-                    RValue::Immediate(-126).into()
-                ]).into())
+                    RValue::Immediate(0x08),
+                    RValue::Immediate(0x7f),
+                    RValue::Immediate(126),
+                    RValue::Immediate(125),
+                    RValue::Immediate(0),
+                    RValue::Immediate(-125),
+                    RValue::Immediate(-126)
+                ]))
             );
 
             // .half 0x7fff, 0x7ffe, 32763, 32764,  -32763, -32764, zval
             assert_eq!(tokens[5],
                 Token::Emittable(Emittable::Half(vec![
-                    RValue::Immediate(0x7fff).into(),
-                    RValue::Immediate(0x7ffe).into(),
-                    RValue::Immediate(32763).into(),
-                    RValue::Immediate(32764).into(),
-                    RValue::Immediate(-32763).into(),
-                    RValue::Immediate(-32764).into(),
-                    RValue::Identifier("zval".into()).into()
-                ]).into())
+                    RValue::Immediate(0x7fff),
+                    RValue::Immediate(0x7ffe),
+                    RValue::Immediate(32763),
+                    RValue::Immediate(32764),
+                    RValue::Immediate(-32763),
+                    RValue::Immediate(-32764),
+                    RValue::Identifier("zval".into())
+                ]))
             );
 
             // .word 0x7fffffff, 0x7fffffe, 2147483645, 2147483644, -2147483644, -2147483645, zval
             assert_eq!(tokens[6],
                 Token::Emittable(Emittable::Word(vec![
-                    RValue::Immediate(0x7fffffff).into(),
-                    RValue::Immediate(0x7fffffe).into(),
-                    RValue::Immediate(2147483645).into(),
-                    RValue::Immediate(2147483644).into(),
-                    RValue::Immediate(-2147483644).into(),
-                    RValue::Immediate(-2147483645).into(),
-                    RValue::Identifier("zval".into()).into()
-                ]).into())
+                    RValue::Immediate(0x7fffffff),
+                    RValue::Immediate(0x7fffffe),
+                    RValue::Immediate(2147483645),
+                    RValue::Immediate(2147483644),
+                    RValue::Immediate(-2147483644),
+                    RValue::Immediate(-2147483645),
+                    RValue::Identifier("zval".into())
+                ]))
             );
 
             // .dword 0x7fffffffffffffff, 0x7ffffffffffffffe, 9223372036854775805, 9223372036854775804, -9223372036854775804, -9223372036854775805, zval
             assert_eq!(tokens[7],
                 Token::Emittable(Emittable::Dword(vec![
-                    RValue::Immediate(0x7fffffffffffffff).into(),
-                    RValue::Immediate(0x7ffffffffffffffe).into(),
-                    RValue::Immediate(9223372036854775805).into(),
-                    RValue::Immediate(9223372036854775804).into(),
-                    RValue::Immediate(-9223372036854775804).into(),
-                    RValue::Immediate(-9223372036854775805).into(),
-                    RValue::Identifier("zval".into()).into()
-                ]).into())
+                    RValue::Immediate(0x7fffffffffffffff),
+                    RValue::Immediate(0x7ffffffffffffffe),
+                    RValue::Immediate(9223372036854775805),
+                    RValue::Immediate(9223372036854775804),
+                    RValue::Immediate(-9223372036854775804),
+                    RValue::Immediate(-9223372036854775805),
+                    RValue::Identifier("zval".into())
+                ]))
             );
 
             // .string "hello world!"
             assert_eq!(tokens[8],
-                Token::Emittable(Emittable::String("hello world!".into()).into())
+                Token::Emittable(Emittable::String("hello world!".into()))
             );
 
             // .endm
@@ -209,7 +209,7 @@ fn parse_directives()
 
             // macro 0x001be64a
             assert_eq!(tokens[10],
-                Token::Emittable(Emittable::Instruction("macro".into(), vec![RValue::Immediate(0x001be64a).into()]).into())
+                Token::Emittable(Emittable::Instruction("macro".into(), vec![RValue::Immediate(0x001be64a).into()]))
             );
 
             // .bss
@@ -219,7 +219,7 @@ fn parse_directives()
             
             // .zero 24
             assert_eq!(tokens[12],
-                Token::Emittable(Emittable::Byte(vec![RValue::Immediate(0); 24]).into())
+                Token::Emittable(Emittable::Byte(vec![RValue::Immediate(0); 24]))
             );
         },
         Err(lex_err) =>
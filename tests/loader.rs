@@ -0,0 +1,173 @@
+use aem::{
+    asm::*, assemble,
+    mem::*, mmu::*,
+    elf::*, loader::*,
+    emu::{hart::*, syscall::*}
+};
+
+const CODE_STR: &str = r#"
+    .text
+    _start:
+        lw   a0, 0x110(zero)
+        lbu  a1, 0x114(zero)
+        sw   a0, 0x120(zero)
+        sw   a0, 0x100(zero)
+    .data
+    value:
+        .word 0x12345678, value
+    .rodata
+        .string "hi"
+    .section .stack, "aw"
+        .zero 8"#;
+
+// Sections are laid out consecutively and loaded with protections derived from their attributes.
+#[test]
+fn load_sections()
+{
+    let object = assemble!(CODE_STR).unwrap_or_else(|assembler_err| panic!("failed {:?}", assembler_err));
+
+    let layout: Vec<(&str, usize, usize)> = object.sections.iter()
+        .map(|section| (section.name.as_str(), section.address, section.length))
+        .collect();
+    assert_eq!(layout, [("text", 0x00, 16), ("data", 0x10, 8), ("rodata", 0x18, 3), ("stack", 0x20, 8)]);
+    assert_eq!(object.sections[3].attributes, SectionFlags::ALLOCATE | SectionFlags::WRITE);
+    assert_eq!(object.symbols["value"], 0x10);
+
+    let loader = Loader::new(&object, 0x100, 0x200).unwrap();
+
+    assert_eq!(loader.entry, 0x100);
+    assert_eq!(loader.symbols["value"], 0x110);
+    assert_eq!(loader.mmu.read::<u32>(0x114), Ok(0x10), "Symbols in data resolve to object addresses");
+    assert_eq!(&loader.mmu.memory[0x118..0x11b], b"hi\0");
+    assert_eq!(loader.mmu.query(0x10f), Some(Protection::EXECUTE));
+    assert_eq!(loader.mmu.query(0x110), Some(Protection::READ | Protection::WRITE));
+    assert_eq!(loader.mmu.query(0x11a), Some(Protection::READ | Protection::WRITE));
+    assert_eq!(loader.mmu.query(0x11c), None, "Custom sections are aligned to 16 bytes");
+    assert_eq!(loader.mmu.query(0x127), Some(Protection::READ | Protection::WRITE));
+    assert_eq!(loader.mmu.query(0x128), None);
+
+    assert!(matches!(Loader::new(&object, 0x1f0, 0x200), Err(LoaderErr::Layout(_))));
+}
+
+// Assembly text runs from the entry point of a loaded object.
+#[test]
+fn run_loaded_object()
+{
+    let object = assemble!(CODE_STR).unwrap();
+    let mut loader = Loader::new(&object, 0x100, 0x200).unwrap();
+
+    let mut hart = Hart::new(0, Xlen::X32);
+    hart.pc = loader.entry as u64;
+
+    assert_eq!(hart.run(&mut loader.mmu), Trap::StoreAccessFault(0x100));
+    assert_eq!(hart.pc, 0x10c);
+    assert_eq!((hart.x[10], hart.x[11]), (0x12345678, 0x10));
+    assert_eq!(loader.mmu.read::<u32>(0x120), Ok(0x12345678));
+}
+
+// Minimal static ELF64 executable, text with the headers at 0x1000 and data with a .bss tail at 0x2000.
+fn executable(text: &[u8], data: &[u8]) -> Vec<u8>
+{
+    fn put(file: &mut [u8], offset: usize, value: u64, size: usize)
+    {
+        file[offset..offset + size].copy_from_slice(&value.to_le_bytes()[..size]);
+    }
+
+    let data_offset = align_address(0x100 + text.len(), 8);
+    let strings_offset = data_offset + data.len();
+    let strings = b"\0_start\0value\0";
+    let symbols_offset = align_address(strings_offset + strings.len(), 8);
+    let sections_offset = symbols_offset + 3 * 24;
+
+    let mut file = vec![0; sections_offset + 3 * 64];
+    file[..8].copy_from_slice(b"\x7FELF\x02\x01\x01\0");
+    for (offset, value, size) in [(16, 2, 2), (18, 243, 2), (20, 1, 4), (24, 0x1100, 8), (32, 64, 8),
+                                  (40, sections_offset as u64, 8), (52, 64, 2), (54, 56, 2), (56, 2, 2), (58, 64, 2), (60, 3, 2)]
+    {
+        put(&mut file, offset, value, size);
+    }
+
+    let segments = [(PF_R | PF_X, 0, 0x1000, 0x100 + text.len(), 0x100 + text.len()), (PF_R | PF_W, data_offset, 0x2000, data.len(), 0x20)];
+    for (index, (flags, offset, address, file_size, memory_size)) in segments.into_iter().enumerate()
+    {
+        let header = 64 + index * 56;
+        put(&mut file, header, PT_LOAD as u64, 4);
+        put(&mut file, header + 4, flags as u64, 4);
+        put(&mut file, header + 8, offset as u64, 8);
+        put(&mut file, header + 16, address, 8);
+        put(&mut file, header + 32, file_size as u64, 8);
+        put(&mut file, header + 40, memory_size as u64, 8);
+    }
+
+    file[0x100..0x100 + text.len()].copy_from_slice(text);
+    file[data_offset..strings_offset].copy_from_slice(data);
+    file[strings_offset..strings_offset + strings.len()].copy_from_slice(strings);
+    for (index, (name, value)) in [(1, 0x1100), (8, 0x2000)].into_iter().enumerate()
+    {
+        put(&mut file, symbols_offset + (index + 1) * 24, name, 4);
+        put(&mut file, symbols_offset + (index + 1) * 24 + 8, value, 8);
+    }
+
+    // Section headers: null, .symtab linked to .strtab.
+    for (offset, value) in [(64 + 4, 2), (64 + 24, symbols_offset as u64), (64 + 32, 3 * 24), (64 + 40, 2),
+                            (128 + 4, 3), (128 + 24, strings_offset as u64), (128 + 32, strings.len() as u64)]
+    {
+        put(&mut file, sections_offset + offset, value, if offset % 64 == 4 || offset % 64 == 40 { 4 } else { 8 });
+    }
+
+    file
+}
+
+// Segments of an executable are loaded with their flags and it runs with argc, argv and auxv on the stack.
+#[test]
+fn run_elf_executable()
+{
+    let object = assemble!(r#"
+        ld     a0, 0(sp)
+        ld     a1, 16(sp)
+        lbu    t3, 0(a1)
+        lui    t0, 2
+        ld     t4, 24(t0)
+        addi   a7, zero, 94
+        ecall"#).unwrap_or_else(|assembler_err| panic!("failed {:?}", assembler_err));
+    let file = executable(&object.binary, &0x1122334455667788u64.to_le_bytes());
+
+    let mut mmu = MMU::new(0x10000);
+    mmu.memory[0x2000..0x2020].fill(0xFF);
+    let mut loader = Loader::with_elf(mmu, &file).unwrap();
+
+    assert_eq!((loader.entry, loader.symbols["_start"], loader.symbols["value"], loader.brk), (0x1100, 0x1100, 0x2000, 0x3000));
+    assert_eq!(loader.mmu.query(0x1000), Some(Protection::READ | Protection::EXECUTE));
+    assert_eq!(loader.mmu.query(0x201F), Some(Protection::READ | Protection::WRITE));
+    assert_eq!(loader.mmu.query(0x2020), None);
+    assert_eq!((loader.mmu.read::<u64>(0x2000), loader.mmu.read::<u64>(0x2008)), (Ok(0x1122334455667788), Ok(0)), "The .bss tail is zero filled");
+
+    let sp = loader.stack(0x10000, 0x1000, &["prog", "x"], &["HOME=/"]).unwrap();
+    assert_eq!(sp % 16, 0);
+
+    let words: Vec<u64> = (0..20).map(|index| loader.mmu.read::<u64>(sp + index * 8).unwrap()).collect();
+    assert_eq!((words[0], words[3], words[5]), (2, 0, 0), "argc, argv and envp are null terminated");
+    let auxv: Vec<(u64, u64)> = words[6..].chunks(2).map(|pair| (pair[0], pair[1])).collect();
+    assert_eq!(auxv[..2], [(AT_PAGESZ, 4096), (AT_ENTRY, 0x1100)]);
+    assert_eq!(auxv[3..], [(AT_PHDR, 0x1040), (AT_PHENT, 56), (AT_PHNUM, 2), (AT_NULL, 0)]);
+    assert!(auxv[2].0 == AT_RANDOM && (sp as u64..0x10000).contains(&auxv[2].1));
+
+    let mut hart = Hart::new(0, Xlen::X64);
+    hart.pc = loader.entry as u64;
+    hart.x[2] = sp as u64;
+    let mut syscalls = Syscalls::new(loader.brk, 0xF000);
+
+    assert_eq!(syscalls.run(&mut hart, &mut loader.mmu), Ok(2));
+    assert_eq!((hart.x[28], hart.x[29]), (b'x' as u64, 0));
+
+    assert!(matches!(Loader::from_elf(&file[..0x100], 0x10000), Err(LoaderErr::Elf(ElfErr::Truncated(_)))));
+    assert!(matches!(Loader::from_elf(&file, 0x2000), Err(LoaderErr::Layout(_))));
+
+    // Offsets overflowing with the sizes added are truncated files.
+    for field in [32, 64 + 8, 64 + 56 + 8]
+    {
+        let mut file = file.clone();
+        file[field..field + 8].copy_from_slice(&(u64::MAX - 1).to_le_bytes());
+        assert!(matches!(Loader::from_elf(&file, 0x10000), Err(LoaderErr::Elf(ElfErr::Truncated(_)))));
+    }
+}
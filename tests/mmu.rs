@@ -0,0 +1,286 @@
+use std::{
+    cell::RefCell, rc::Rc
+};
+use aem::{
+    asm::*, assemble,
+    mem::*, mmu::*, pmp::*, dev::*,
+    emu::tlb::*, emu::hart::*
+};
+
+// Page table entry permission bits.
+const V: u64 = 1 << 0;
+const R: u64 = 1 << 1;
+const W: u64 = 1 << 2;
+const X: u64 = 1 << 3;
+const U: u64 = 1 << 4;
+const A: u64 = 1 << 6;
+const D: u64 = 1 << 7;
+
+// Without PMP entries every physical access is permitted.
+fn translation(paging: Paging, root: usize, user: bool) -> Translation
+{
+    Translation{ paging, root, asid: 0, user, sum: false, mxr: false, pmp: Pmp::new(0) }
+}
+
+// Sv32 megapages and 4 KiB pages, with the permission, privilege and alignment checks of the leaf entries.
+#[test]
+fn sv32_walks()
+{
+    let mut mmu = MMU::new(0x3000);
+    mmu.protect(0x0000, 0x2FFF, Protection::READ | Protection::WRITE).unwrap();
+
+    mmu.write::<u32>(0x1000, ((0x2 << 10) | V) as u32).unwrap();     // 0x0000_0000: Next level at 0x2000.
+    mmu.write::<u32>(0x1004, (X | R | V) as u32).unwrap();            // 0x0040_0000: Megapage at 0.
+    mmu.write::<u32>(0x1008, ((0x1 << 10) | R | V) as u32).unwrap();  // 0x0080_0000: Misaligned megapage.
+    mmu.write::<u32>(0x2004, ((0x2 << 10) | U | X | V) as u32).unwrap(); // 0x0000_1000: Execute-only user page.
+
+    let supervisor = translation(Paging::Sv32, 0x1000, false);
+    let user = translation(Paging::Sv32, 0x1000, true);
+
+    assert_eq!(mmu.translate(0x0040_0123, Access::Fetch, &supervisor), Ok(0x123));
+    assert_eq!(mmu.read::<u32>(0x1004), Ok((A | X | R | V) as u32));
+
+    let faults = [
+        (0x0040_0000, Access::Store, supervisor),
+        (0x0040_0000, Access::Load, user),
+        (0x0080_0000, Access::Load, supervisor),
+        (0x0000_1000, Access::Load, user),
+        (0x0000_1000, Access::Fetch, supervisor),
+        (0x0000_2000, Access::Load, supervisor)
+    ];
+
+    for (address, access, translation) in faults
+    {
+        assert!(matches!(mmu.translate(address, access, &translation), Err(MMUErr::PageFault(_))), "Expected page fault at 0x{:x}", address);
+    }
+
+    // MXR makes executable pages readable, SUM grants supervisor loads and stores to user pages.
+    assert_eq!(mmu.translate(0x0000_1010, Access::Load, &Translation{ mxr: true, ..user }), Ok(0x2010));
+    assert_eq!(mmu.translate(0x0000_1010, Access::Fetch, &user), Ok(0x2010));
+    assert_eq!(mmu.translate(0x0000_1010, Access::Load, &Translation{ sum: true, mxr: true, ..supervisor }), Ok(0x2010));
+    assert_eq!(mmu.read::<u32>(0x2004), Ok(((0x2 << 10) | A | U | X | V) as u32));
+}
+
+// Sv39 and Sv48 walks set the dirty bit on stores and reject non-canonical addresses and reserved encodings.
+#[test]
+fn sv39_sv48_walks()
+{
+    let mut mmu = MMU::new(0x5000);
+    mmu.protect(0x0000, 0x4FFF, Protection::READ | Protection::WRITE).unwrap();
+
+    // Sv48 adds a level above the Sv39 root, both map 0x1000 to 0x4000.
+    mmu.write::<u64>(0x0000, (0x1 << 10) | V).unwrap();
+    mmu.write::<u64>(0x1000, (0x2 << 10) | V).unwrap();
+    mmu.write::<u64>(0x2000, (0x3 << 10) | V).unwrap();
+    mmu.write::<u64>(0x3008, (0x4 << 10) | W | R | V).unwrap();
+    mmu.write::<u64>(0x3010, (0x4 << 10) | W | V).unwrap();
+    mmu.write::<u64>(0x3018, (0x4 << 10) | (A | D) | V).unwrap();
+
+    let sv39 = translation(Paging::Sv39, 0x1000, false);
+    let sv48 = translation(Paging::Sv48, 0x0000, false);
+
+    assert_eq!(mmu.translate(0x1234, Access::Load, &sv39), Ok(0x4234));
+    assert_eq!(mmu.read::<u64>(0x3008), Ok((0x4 << 10) | A | W | R | V));
+
+    assert_eq!(mmu.translate(0x1238, Access::Store, &sv48), Ok(0x4238));
+    assert_eq!(mmu.read::<u64>(0x3008), Ok((0x4 << 10) | D | A | W | R | V));
+
+    let faults = [
+        (0x0000_0040_0000_1000, sv39), // Bit 38 is not sign-extended.
+        (0x0000_8000_0000_1000, sv48), // Bit 47 is not sign-extended.
+        (0x2000, sv39),                // Write without read.
+        (0x3000, sv39)                 // Non-leaf entry at the last level.
+    ];
+
+    for (address, translation) in faults
+    {
+        assert!(matches!(mmu.translate(address, Access::Load, &translation), Err(MMUErr::PageFault(_))), "Expected page fault at 0x{:x}", address);
+    }
+
+    // Bare translation is the identity, entries outside memory are access errors.
+    assert_eq!(mmu.translate(0xABC, Access::Fetch, &translation(Paging::Bare, 0, true)), Ok(0xABC));
+    assert!(matches!(mmu.translate(0x1000, Access::Load, &translation(Paging::Sv39, 0x10000, false)), Err(MMUErr::OutOfBounds(_))));
+}
+
+// Cached translations are tagged by address space and side, stale entries survive until flushed.
+#[test]
+fn tlb_flushes()
+{
+    let mut mmu = MMU::new(0x5000);
+    mmu.protect(0x0000, 0x4FFF, Protection::READ | Protection::WRITE).unwrap();
+
+    mmu.write::<u64>(0x1000, (0x2 << 10) | V).unwrap();
+    mmu.write::<u64>(0x2000, (0x3 << 10) | V).unwrap();
+    mmu.write::<u64>(0x3008, (0x4 << 10) | X | W | R | V).unwrap();
+    mmu.write::<u64>(0x3010, (0x4 << 10) | (1 << 5) | R | V).unwrap(); // Global.
+    mmu.write::<u64>(0x3018, (0x3 << 10) | R | V).unwrap();
+
+    let (mut tlb, sv39) = (Tlb::new(2), translation(Paging::Sv39, 0x1000, false));
+    tlb.verify = true;
+
+    assert_eq!(tlb.translate(&mut mmu, 0x1234, Access::Load, &sv39), Ok(0x4234));
+    assert_eq!(tlb.translate(&mut mmu, 0x1238, Access::Load, &sv39), Ok(0x4238));
+    assert_eq!(tlb.translate(&mut mmu, 0x1000, Access::Fetch, &sv39), Ok(0x4000));
+    assert_eq!((tlb.data.hits, tlb.data.misses, tlb.instruction.misses), (1, 1, 1));
+
+    // Remapping without a fence keeps using, and reports, the cached translation.
+    mmu.write::<u64>(0x3008, (0x3 << 10) | W | R | V).unwrap();
+    assert_eq!(tlb.translate(&mut mmu, 0x1000, Access::Load, &sv39), Ok(0x4000));
+    assert!(tlb.stale.contains(&0x1000));
+
+    tlb.flush(Some(0x1000), None);
+    assert_eq!(tlb.translate(&mut mmu, 0x1000, Access::Load, &sv39), Ok(0x3000));
+    assert!(tlb.instruction.entries.is_empty());
+
+    // Address space flushes keep global entries, replacements are bounded by the capacity.
+    let other = Translation{ asid: 1, ..sv39 };
+    assert_eq!(tlb.translate(&mut mmu, 0x2000, Access::Load, &other), Ok(0x4000));
+    assert_eq!(tlb.translate(&mut mmu, 0x3000, Access::Load, &other), Ok(0x3000));
+    assert_eq!(tlb.data.entries.len(), 2);
+
+    tlb.flush(None, Some(1));
+    assert_eq!(tlb.data.entries.iter().map(|entry| entry.mapping.virtual_page).collect::<Vec<_>>(), [0x2]);
+    assert_eq!(tlb.translate(&mut mmu, 0x2010, Access::Load, &sv39), Ok(0x4010));
+    assert_eq!(tlb.data.misses, 4);
+}
+
+// PMP entries match by priority and must cover the whole access, locked entries also restrict machine mode.
+#[test]
+fn pmp_entries()
+{
+    let mut pmp = Pmp::new(PMP_ENTRIES);
+    pmp.write_config(0, PMP_NA4 | PMP_R);
+    pmp.write_address(0, 0x100 >> 2);
+    pmp.write_config(1, PMP_TOR | PMP_R | PMP_W);
+    pmp.write_address(1, 0x200 >> 2);
+    pmp.write_config(2, PMP_NAPOT | PMP_X);
+    pmp.write_address(2, (0x1000 >> 2) | 0x1FF);
+
+    let checks = [
+        (0x100, 4, Access::Load, false, true),
+        (0x100, 4, Access::Store, false, false), // The NA4 entry has priority over the TOR range.
+        (0x104, 4, Access::Store, false, true),
+        (0x0FE, 4, Access::Load, false, false),  // Partially matching accesses fail.
+        (0x1FC, 8, Access::Load, false, false),
+        (0x1800, 4, Access::Fetch, false, true),
+        (0x1800, 4, Access::Load, false, false),
+        (0x3000, 4, Access::Load, false, false), // Unmatched supervisor and user accesses fail.
+        (0x3000, 4, Access::Store, true, true),
+        (0x100, 4, Access::Store, true, true)    // Unlocked entries don't apply to machine mode.
+    ];
+
+    for (address, size, access, machine, permitted) in checks
+    {
+        assert_eq!(pmp.permits(address, size, access, machine), permitted, "Mismatch for {:?} at 0x{:x}", access, address);
+    }
+
+    // Locked entries, and addresses bounding a locked TOR range, ignore writes.
+    pmp.write_config(0, PMP_NA4 | PMP_R | PMP_L);
+    pmp.write_config(0, PMP_NA4 | PMP_R | PMP_W);
+    pmp.write_address(0, 0);
+    assert!(!pmp.permits(0x100, 4, Access::Store, true));
+    assert_eq!((pmp.config[0], pmp.address[0]), (PMP_NA4 | PMP_R | PMP_L, 0x40));
+
+    pmp.write_config(3, PMP_TOR | PMP_L);
+    pmp.write_address(2, 0);
+    assert_eq!(pmp.address[2], 0x5FF);
+
+    // Write-only permissions are reserved.
+    pmp.write_config(4, PMP_NA4 | PMP_W);
+    assert_eq!(pmp.config[4], PMP_NA4);
+}
+
+// Device with a single 8-byte register, accesses past it are bus errors.
+struct Register
+{
+    value: u64,
+    accesses: Vec<(Address, usize)>
+}
+
+impl Device for Register
+{
+    fn read(&mut self, offset: Address, width: usize) -> Result<u64, MMUErr>
+    {
+        self.accesses.push((offset, width));
+        match offset + width <= 8
+        {
+            true => Ok((self.value >> (offset * 8)) & (u64::MAX >> (64 - width * 8))),
+            false => Err(MMUErr::BusError(format!("No register at offset: {}", offset)))
+        }
+    }
+
+    fn write(&mut self, offset: Address, width: usize, value: u64) -> Result<(), MMUErr>
+    {
+        self.accesses.push((offset, width));
+        if offset + width > 8
+        {
+            return Err(MMUErr::BusError(format!("No register at offset: {}", offset)))
+        }
+
+        let mask = (u64::MAX >> (64 - width * 8)) << (offset * 8);
+        self.value = (self.value & !mask) | ((value << (offset * 8)) & mask);
+        Ok(())
+    }
+}
+
+// Accesses within a device mapping reach the device with their width, bus errors become access faults.
+#[test]
+fn device_bus()
+{
+    let object = assemble!(r#"
+        lui   a1, 1
+        lw    a0, 4(a1)
+        sh    a0, 0(a1)
+        sw    a0, 8(a1)"#).unwrap_or_else(|assembler_err| panic!("failed {:?}", assembler_err));
+
+    let mut mmu = MMU::new(0x100);
+    mmu.memory[..object.binary.len()].copy_from_slice(&object.binary);
+    mmu.protect(0x000, 0x0FF, Protection::READ | Protection::EXECUTE).unwrap();
+
+    let register = Rc::new(RefCell::new(Register{ value: 0x1234_5678_0000_0000, accesses: Vec::new() }));
+    mmu.map(0x1000, 0x100B, register.clone()).unwrap();
+    assert!(mmu.map(0x0F0, 0x10F, register.clone()).is_err());
+
+    let mut hart = Hart::new(0, Xlen::X64);
+    assert_eq!(hart.run(&mut mmu), Trap::StoreAccessFault(0x1008));
+    assert_eq!(hart.x[10], 0x1234_5678);
+    assert_eq!(register.borrow().value, 0x1234_5678_0000_5678);
+    assert_eq!(register.borrow().accesses, [(4, 4), (0, 2), (8, 4)]);
+
+    // Accesses crossing the end of the mapping never reach the device.
+    assert_eq!(mmu.read::<u64>(0x1000), Ok(0x1234_5678_0000_5678));
+    assert!(matches!(mmu.read::<u64>(0x1008), Err(MMUErr::BusError(_))));
+    assert!(matches!(mmu.read::<u32>(0x100C), Err(MMUErr::OutOfBounds(_))));
+    assert_eq!(register.borrow().accesses.len(), 4);
+}
+
+// Any store overlapping the reserved granule, including direct physical stores, drops the reservation.
+#[test]
+fn reservations()
+{
+    let mut mmu = MMU::new(0x100);
+    mmu.protect(0x000, 0x0FF, Protection::READ | Protection::WRITE).unwrap();
+
+    // Stores outside the reserved granule leave it intact.
+    mmu.reserve(0, 0x4C);
+    mmu.write_byte(0x50, 1).unwrap();
+    mmu.physical_mut(0x40..0x48).unwrap().fill(0);
+    assert!(mmu.release(0, 0x48));
+
+    // Byte stores, word stores and direct physical stores all drop it.
+    mmu.reserve(0, 0x4C);
+    mmu.write_byte(0x4F, 1).unwrap();
+    assert!(!mmu.release(0, 0x48));
+
+    mmu.reserve(0, 0x4C);
+    mmu.write::<u32>(0x48, 1).unwrap();
+    assert!(!mmu.release(0, 0x48));
+
+    mmu.reserve(0, 0x4C);
+    mmu.physical_mut(0x44..0x49).unwrap().fill(0);
+    assert!(!mmu.release(0, 0x48));
+
+    mmu.reserve(0, 0x4C);
+    mmu.physical().get_mut(0x4E..0x4F).unwrap()[0] = 1;
+    assert!(!mmu.release(0, 0x48));
+}